@@ -0,0 +1,70 @@
+//! Compares link extraction through select's document tree with the `fast-parser` tokenizer.
+//!
+//! ```bash
+//! cargo bench --features fast-parser
+//! ```
+
+use find_broken_links::link_tokenizer;
+use find_broken_links::links::{self, LinkSources};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// A page with `sections` sections of nested markup, each with a few links, some HTMX
+/// attributes and an inline script.
+fn large_page(sections: usize) -> String {
+    let mut html = String::from("<!DOCTYPE html><html><head><title>Docs</title></head><body>");
+    for i in 0..sections {
+        html.push_str(&format!(
+            r#"<section id="s{i}"><div class="row"><div class="col">
+            <h2>Section {i}</h2>
+            <p>Some <em>text</em> with <a href="/docs/{i}">a link</a> and
+            <a href="https://example.com/ref/{i}?q=1&amp;r=2">a reference</a>.</p>
+            <ul><li><a href="/a/{i}">a</a></li><li><a href="mailto:x@example.com">mail</a></li></ul>
+            <button hx-get="/fragments/{i}" hx-target="closest div">load</button>
+            <script>var s{i} = "<a href='/not-a-link'>";</script>
+            </div></div></section>"#
+        ));
+        if i % 50 == 0 {
+            html.push_str(r#"<noscript><a href="/static">static</a></noscript>"#);
+        }
+    }
+    html.push_str("</body></html>");
+    html
+}
+
+fn time(iterations: u32, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    let sources = LinkSources {
+        htmx: true,
+        ..LinkSources::default()
+    };
+    for sections in [100, 1_000, 10_000] {
+        let html = large_page(sections);
+        assert_eq!(
+            links::document_links(&html, sources),
+            link_tokenizer::document_links(&html, sources)
+        );
+
+        let iterations = (20_000 / sections as u32).max(3);
+        let tree = time(iterations, || {
+            black_box(links::document_links(black_box(&html), sources));
+        });
+        let tokenizer = time(iterations, || {
+            black_box(link_tokenizer::document_links(black_box(&html), sources));
+        });
+        println!(
+            "{:>7} KiB: select {:>10.2?}  tokenizer {:>10.2?}  ({:.1}x)",
+            html.len() / 1024,
+            tree,
+            tokenizer,
+            tree.as_secs_f64() / tokenizer.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use crate::links::file_extension;
+use reqwest::header::HeaderValue;
+use std::str::FromStr;
+use url::Url;
+
+/// `Accept` for pages and anything without a more specific type, as a browser navigation sends.
+pub const HTML_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+/// Which `Accept` header to send with each request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AcceptHeader {
+    /// The same value for every request
+    Fixed(HeaderValue),
+    /// Picked per request from the URL's file extension; see [`accept_for_url`]
+    Auto,
+}
+
+impl AcceptHeader {
+    pub fn for_url(&self, url: &Url) -> HeaderValue {
+        match self {
+            AcceptHeader::Fixed(value) => value.clone(),
+            AcceptHeader::Auto => HeaderValue::from_static(accept_for_url(url)),
+        }
+    }
+}
+
+impl FromStr for AcceptHeader {
+    type Err = String;
+
+    /// Parses `auto`, or any valid header value such as `application/json`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(AcceptHeader::Auto);
+        }
+        HeaderValue::from_str(s)
+            .map(AcceptHeader::Fixed)
+            .map_err(|_| format!("invalid Accept header value '{}'", s))
+    }
+}
+
+/// `Accept` value for the kind of resource the URL's file extension suggests: `image/*` for
+/// images, `application/json` for `.json`, and so on, with [`HTML_ACCEPT`] for pages and
+/// anything unrecognized.
+///
+/// Every value ends in a low-priority `*/*`, so a server that can't match the type still
+/// answers rather than sending a 406.
+pub fn accept_for_url(url: &Url) -> &'static str {
+    match file_extension(url).as_deref() {
+        Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "avif" | "svg" | "ico" | "bmp") => {
+            "image/*,*/*;q=0.8"
+        }
+        Some("css") => "text/css,*/*;q=0.1",
+        Some("js" | "mjs") => "text/javascript,application/javascript,*/*;q=0.1",
+        Some("json") => "application/json,*/*;q=0.1",
+        Some("xml" | "rss" | "atom") => "application/xml,text/xml,*/*;q=0.1",
+        Some("pdf") => "application/pdf,*/*;q=0.1",
+        Some("mp4" | "webm" | "mov") => "video/*,*/*;q=0.8",
+        Some("mp3" | "ogg" | "wav") => "audio/*,*/*;q=0.8",
+        _ => HTML_ACCEPT,
+    }
+}
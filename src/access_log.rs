@@ -0,0 +1,60 @@
+use regex::Regex;
+use std::collections::HashSet;
+use std::io::{self, BufRead};
+use url::Url;
+
+/// Matches the request line of Common and Combined Log Format entries, e.g.
+/// `"GET /docs/intro?lang=en HTTP/1.1"`. Only `GET` and `HEAD` requests count: other methods
+/// don't say anything about a page that can be fetched.
+pub const DEFAULT_PATTERN: &str = r#""(?:GET|HEAD) (?P<path>\S+) HTTP/[0-9.]+""#;
+
+/// Pulls the URLs visitors requested out of a web server access log, to seed a crawl with.
+#[derive(Debug, Clone)]
+pub struct AccessLogParser {
+    pattern: Regex,
+}
+
+impl AccessLogParser {
+    /// A parser for log lines matching `pattern`, which must capture the requested path (or
+    /// absolute URL) in a group named `path`.
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let pattern = Regex::new(pattern).map_err(|e| e.to_string())?;
+        if !pattern.capture_names().any(|name| name == Some("path")) {
+            return Err("the access log pattern has no (?P<path>...) group".to_string());
+        }
+        Ok(AccessLogParser { pattern })
+    }
+
+    /// Distinct URLs on `root`'s host requested in the log, in the order first seen.
+    ///
+    /// Paths are resolved against `root`; absolute URLs for other hosts and lines that don't
+    /// match the pattern are skipped.
+    pub fn seed_urls(&self, log: impl BufRead, root: &Url) -> io::Result<Vec<Url>> {
+        let mut seen = HashSet::new();
+        let mut urls = Vec::new();
+        for line in log.lines() {
+            let line = line?;
+            let Some(path) = self.pattern.captures(&line).and_then(|c| c.name("path")) else {
+                continue;
+            };
+            let Ok(mut url) = root.join(path.as_str()) else {
+                log::debug!("Skipping unparseable access log path: {}", path.as_str());
+                continue;
+            };
+            if url.host_str() != root.host_str() {
+                continue;
+            }
+            url.set_fragment(None);
+            if seen.insert(url.to_string()) {
+                urls.push(url);
+            }
+        }
+        Ok(urls)
+    }
+}
+
+impl Default for AccessLogParser {
+    fn default() -> Self {
+        AccessLogParser::new(DEFAULT_PATTERN).unwrap()
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{HeaderArgs, LoginArgs};
+use find_broken_links::accept::AcceptHeader;
+use find_broken_links::crawler::Crawler;
+use find_broken_links::retry::RetryPolicy;
+use find_broken_links::user_agent::UserAgentPicker;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// The final response had a 2xx status (redirects are followed).
+pub const EXIT_OK: i32 = 0;
+/// The final response had any other status.
+pub const EXIT_HTTP_ERROR: i32 = 1;
+/// No response at all: DNS failure, refused connection, timeout, ...
+pub const EXIT_NO_RESPONSE: i32 = 3;
+
+/// Check a single URL, print its status and exit with a code reflecting it
+#[derive(clap::Args, Debug)]
+pub struct CheckArgs {
+    /// URL to request
+    url: Url,
+
+    /// User-Agent header to send
+    #[arg(long, value_name = "UA")]
+    user_agent: Option<String>,
+
+    /// Accept header to send, or 'auto' to pick one from the URL's file extension
+    #[arg(long, value_name = "VALUE")]
+    accept: Option<AcceptHeader>,
+
+    /// Give up on the request after this long
+    #[arg(long, default_value = "30s", value_parser = humantime::parse_duration, value_name = "DURATION")]
+    timeout: Duration,
+
+    /// Retries per HTTP status, e.g. 429:5,503:3,500:1 (replaces the built-in policy)
+    #[arg(long, value_name = "STATUS:RETRIES,...")]
+    retry_status: Option<RetryPolicy>,
+
+    /// Retries for a request that gets no response (refused or dropped connection, timeout) and
+    /// for 5xx statuses --retry-status doesn't list
+    #[arg(long, default_value_t = 0, value_name = "N")]
+    max_retries: u32,
+
+    /// Delay before the first retry, doubled for each one after it, plus some jitter
+    #[arg(long, default_value = "500ms", value_parser = humantime::parse_duration, value_name = "DURATION")]
+    retry_delay: Duration,
+
+    /// When the URL returns 403, retry it once with a full set of browser headers
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, value_name = "BOOL")]
+    ua_fallback: bool,
+
+    /// Send the request through this proxy (http://, https:// or socks5://)
+    #[arg(long, value_name = "URL", value_parser = crate::parse_proxy)]
+    proxy: Option<reqwest::Proxy>,
+
+    /// Accept an invalid TLS certificate (self-signed, expired, for another host)
+    #[arg(long)]
+    insecure: bool,
+
+    #[command(flatten)]
+    headers: HeaderArgs,
+
+    #[command(flatten)]
+    login: LoginArgs,
+}
+
+/// Runs the request and returns the process exit code.
+pub async fn run(args: CheckArgs) -> Result<i32, Box<dyn std::error::Error>> {
+    let user_agents = UserAgentPicker::new(args.user_agent.into_iter().collect(), false);
+    let headers = args.headers.header_map();
+    let cookies = args.headers.cookie_jar(&args.url);
+    let client = args
+        .login
+        .log_in(
+            user_agents.pick(&args.url),
+            headers.clone(),
+            Arc::clone(&cookies),
+            args.proxy.clone(),
+            args.insecure,
+        )
+        .await?;
+    let mut crawler = Crawler::builder(args.url.clone())
+        .user_agents(user_agents)
+        .request_timeout(Some(args.timeout))
+        .retry_policy(
+            args.retry_status
+                .unwrap_or_default()
+                .max_retries(args.max_retries)
+                .base_delay(args.retry_delay),
+        )
+        .ua_fallback(args.ua_fallback)
+        .accept(args.accept)
+        .headers(headers)
+        .proxy(args.proxy)
+        .accept_invalid_certs(args.insecure)
+        .cookie_jar(cookies);
+    if let Some(client) = client {
+        crawler = crawler.client(client);
+    }
+
+    match crawler.build().check(&args.url).await {
+        Ok(checked) => {
+            let status = checked.status.unwrap_or_default();
+            let reason = reqwest::StatusCode::from_u16(status)
+                .ok()
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or_default();
+            println!("{} {}", status, reason);
+            Ok(if (200..300).contains(&status) {
+                EXIT_OK
+            } else {
+                EXIT_HTTP_ERROR
+            })
+        }
+        Err(e) => {
+            eprintln!("{}: {}", args.url, e);
+            Ok(EXIT_NO_RESPONSE)
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Adjusts how many requests may be in flight from observed latency and errors (AIMD).
+///
+/// The limit starts at `min` and grows by one after every `limit` consecutive healthy responses
+/// (roughly one "round" of requests at the current level), up to `max`. A response that signals
+/// strain halves it, down to `min`:
+///
+/// - a 429 or any 5xx status, or no response at all (timeouts, connection errors)
+/// - latency, smoothed with an exponential moving average, above twice the lowest average seen
+///
+/// After a decrease, the next `limit` responses are ignored: they were already in flight under
+/// the old limit, and counting them would halve again for the same burst.
+#[derive(Debug)]
+pub struct AimdController {
+    min: usize,
+    max: usize,
+    limit: usize,
+    healthy_streak: usize,
+    cooldown: usize,
+    latency_ewma: Option<f64>,
+    baseline: Option<f64>,
+}
+
+/// Weight of the newest sample in the latency moving average.
+const EWMA_ALPHA: f64 = 0.3;
+/// Smoothed latency above `baseline * LATENCY_FACTOR` counts as strain.
+const LATENCY_FACTOR: f64 = 2.0;
+
+impl AimdController {
+    pub fn new(min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        AimdController {
+            min,
+            max: max.max(min),
+            limit: min,
+            healthy_streak: 0,
+            cooldown: 0,
+            latency_ewma: None,
+            baseline: None,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Records one finished request and returns the (possibly changed) limit.
+    ///
+    /// `status` is the HTTP status of the response, or `None` if none was received.
+    pub fn record(&mut self, latency: Duration, status: Option<u16>) -> usize {
+        let sample = latency.as_secs_f64();
+        let ewma = match self.latency_ewma {
+            Some(ewma) => EWMA_ALPHA * sample + (1.0 - EWMA_ALPHA) * ewma,
+            None => sample,
+        };
+        self.latency_ewma = Some(ewma);
+        let baseline = self.baseline.map_or(ewma, |baseline| baseline.min(ewma));
+        self.baseline = Some(baseline);
+
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return self.limit;
+        }
+
+        let server_strained = is_strained(status);
+        let slow = ewma > baseline * LATENCY_FACTOR;
+
+        if server_strained || slow {
+            self.limit = (self.limit / 2).max(self.min);
+            self.healthy_streak = 0;
+            self.cooldown = self.limit;
+        } else {
+            self.healthy_streak += 1;
+            if self.healthy_streak >= self.limit && self.limit < self.max {
+                self.limit += 1;
+                self.healthy_streak = 0;
+            }
+        }
+        self.limit
+    }
+}
+
+/// Whether a response (`None`: no response at all) signals that the server is struggling.
+fn is_strained(status: Option<u16>) -> bool {
+    match status {
+        Some(status) => status == 429 || (500..600).contains(&status),
+        None => true,
+    }
+}
+
+/// Responses considered when computing the recent error rate.
+const ERROR_WINDOW: usize = 20;
+/// Error rate above which [`ErrorBackoff`] halves the limit.
+const ERROR_RATE_THRESHOLD: f64 = 0.2;
+
+/// A simpler safety valve than [`AimdController`]: runs at a fixed concurrency, halving it while
+/// the server returns errors and restoring it once they subside.
+///
+/// Every [`ERROR_WINDOW`] responses, the share that were a 429, a 5xx or no response at all is
+/// compared to [`ERROR_RATE_THRESHOLD`]. Above it, the limit is halved (down to 1); at or below
+/// half of it, the limit is doubled back up to `max`. Latency is ignored.
+#[derive(Debug)]
+pub struct ErrorBackoff {
+    max: usize,
+    limit: usize,
+    recent: VecDeque<bool>,
+}
+
+impl ErrorBackoff {
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        ErrorBackoff {
+            max,
+            limit: max,
+            recent: VecDeque::with_capacity(ERROR_WINDOW),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Records one finished request and returns the (possibly changed) limit.
+    ///
+    /// `status` is the HTTP status of the response, or `None` if none was received.
+    pub fn record(&mut self, status: Option<u16>) -> usize {
+        self.recent.push_back(is_strained(status));
+        if self.recent.len() < ERROR_WINDOW {
+            return self.limit;
+        }
+        let errors = self.recent.iter().filter(|&&error| error).count();
+        let rate = errors as f64 / self.recent.len() as f64;
+        // Each decision is based on a fresh window of responses
+        self.recent.clear();
+        if rate > ERROR_RATE_THRESHOLD {
+            self.limit = (self.limit / 2).max(1);
+        } else if rate <= ERROR_RATE_THRESHOLD / 2.0 {
+            self.limit = (self.limit * 2).min(self.max);
+        }
+        self.limit
+    }
+}
@@ -0,0 +1,42 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Settings read from a `--config` file, a JSON object standing in for the command-line options
+/// of the same name, so a crawl doesn't need a dozen flags every time.
+///
+/// Every key is optional. Options given on the command line win over the file, which wins over
+/// the defaults; a list on the command line (`--seed`, `--include`, ...) replaces the file's
+/// rather than adding to it. Unknown keys are an error, so a typo doesn't go unnoticed.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CrawlConfig {
+    pub root_url: Option<String>,
+    /// `--seed` URLs
+    #[serde(default)]
+    pub seeds: Vec<String>,
+    /// The fuzzy match string
+    pub fuzzy: Option<String>,
+    pub concurrency: Option<usize>,
+    pub timeout_secs: Option<u64>,
+    pub delay_ms: Option<u64>,
+    pub max_pages: Option<u64>,
+    pub channel_buffer: Option<usize>,
+    /// `--soft-404-pattern` regexes, used with `--soft-404`
+    #[serde(default)]
+    pub soft_404_patterns: Vec<String>,
+    /// `--include` patterns
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// `--exclude` patterns
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Header name -> value, like `--header`
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl CrawlConfig {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
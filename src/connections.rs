@@ -0,0 +1,72 @@
+//! Keeping the number of open connections under the process's open file limit.
+//!
+//! Every connection uses a file descriptor, and wide crawls on systems with the common default
+//! limit of 1024 can run out, which shows up as cryptic connection errors. The crawler caps
+//! simultaneous requests with a semaphore of its own, independent of the crawl concurrency.
+
+/// File descriptors left for everything other than connections: the results files, logs, DNS
+/// lookups and whatever the runtime has open.
+pub const RESERVED_FDS: u64 = 64;
+
+/// The soft `RLIMIT_NOFILE` of this process; `None` if unlimited or unknown.
+#[cfg(unix)]
+pub fn open_file_limit() -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `limit` is a valid, writable rlimit for the duration of the call
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return None;
+    }
+    // `rlim_t` is narrower than u64 on some platforms
+    #[allow(clippy::unnecessary_cast)]
+    let soft_limit = limit.rlim_cur as u64;
+    (limit.rlim_cur != libc::RLIM_INFINITY).then_some(soft_limit)
+}
+
+#[cfg(not(unix))]
+pub fn open_file_limit() -> Option<u64> {
+    None
+}
+
+/// How many connections fit under `open_file_limit` with [`RESERVED_FDS`] to spare; `None`
+/// (no cap) without a limit.
+pub fn default_max_connections(open_file_limit: Option<u64>) -> Option<usize> {
+    open_file_limit.map(|limit| limit.saturating_sub(RESERVED_FDS).max(1) as usize)
+}
+
+/// A warning for the start of the crawl, if `concurrency` or `max_connections` won't work well
+/// with the open file limit.
+pub fn concurrency_warning(
+    concurrency: usize,
+    max_connections: Option<usize>,
+    open_file_limit: Option<u64>,
+) -> Option<String> {
+    match (max_connections, open_file_limit) {
+        (Some(max), Some(limit)) if max as u64 + RESERVED_FDS > limit => Some(format!(
+            "--max-connections {} is close to the open file limit of {}, connections may fail \
+             with 'too many open files'; lower it, or raise the limit with `ulimit -n`",
+            max, limit
+        )),
+        (Some(max), limit) if concurrency > max => Some(format!(
+            "concurrency {} is more than the {} connections allowed at once{}; the other \
+             requests wait for a free connection",
+            concurrency,
+            max,
+            match limit {
+                Some(limit) => format!(
+                    " (to stay under the open file limit of {}, raise it with `ulimit -n`)",
+                    limit
+                ),
+                None => String::new(),
+            }
+        )),
+        (None, Some(limit)) if concurrency as u64 + RESERVED_FDS > limit => Some(format!(
+            "concurrency {} is close to the open file limit of {}; lower --concurrency or raise \
+             the limit with `ulimit -n`",
+            concurrency, limit
+        )),
+        _ => None,
+    }
+}
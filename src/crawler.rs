@@ -0,0 +1,3304 @@
+use crate::accept::AcceptHeader;
+use crate::concurrency::{AimdController, ErrorBackoff};
+use crate::debug_channel::{DebugChannel, DebugSender};
+use crate::ignore::IgnoreList;
+use crate::json_links::{find_json_links, JsonPath};
+use crate::links::{
+    extract_links_from, fragment_exists, link_header_targets, make_absolute_url,
+    needs_percent_encoding, resolve_redirect, ExtractedLinks, HtmxMethod, LinkClass, LinkKind,
+    LinkSources,
+};
+use crate::normalize::UrlNormalizer;
+use crate::pacing::{HostPacer, SlowStart};
+use crate::page_cache::{CachedPage, PageCache};
+use crate::progress::CrawlProgress;
+use crate::report::{add_finding, NotFoundError};
+use crate::retry::RetryPolicy;
+use crate::robots::RobotsDirectives;
+use crate::safety::UnsafeUrlFilter;
+use crate::scope::{fuzzy_matches, is_subdomain_of, ScopeEntry};
+use crate::sitemap::Sitemap;
+use crate::skipped::{SkipReason, SkippedLink};
+use crate::soft_404::{PageText, Soft404Detector};
+use crate::url_filter::UrlPatternFilter;
+use crate::user_agent::{browser_headers, UserAgentPicker, DEFAULT_USER_AGENT};
+use crate::warnings::{LinkWarning, WarningCategory};
+use reqwest::cookie::Jar;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Method, StatusCode};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::field::Empty;
+use tracing::Instrument;
+use url::Url;
+
+/// What the crawler sends the caller over the channel: each finding as it's made, then
+/// `Complete` once the crawl is over.
+pub enum CrawlMessage {
+    Finding(Finding),
+    /// The crawl is over, whether every page was visited or it stopped early; always the last
+    /// message
+    Complete,
+}
+
+/// What the crawler reports back to the caller about the site.
+#[derive(Clone)]
+pub enum Finding {
+    NotFound {
+        url: String,
+        /// `<title>` of the error page, if it was HTML with one
+        title: Option<String>,
+        /// Page (or PDF) the broken link was found on; `None` for the root URL
+        referrer: Option<String>,
+        suggestion: Option<String>,
+        kind: LinkKind,
+        /// Why a link without an error status counts as broken: the error in its body, or why
+        /// another site didn't answer with `check_external`
+        error: Option<String>,
+        class: Option<LinkClass>,
+        /// HTTP status of the response: a 2xx for a body error, 0 if another site didn't
+        /// answer, 400 or more otherwise
+        status: Option<u16>,
+        /// Where `url` redirected to, if it did: the URL that answered with `status`, or the
+        /// one that redirected back into a loop
+        redirected_to: Option<String>,
+        /// The page loaded, but it's an error page; see [`Soft404Detector`]
+        soft_404: bool,
+    },
+    /// Another page with a link to a URL already sent as `NotFound`, whose `url` this is
+    AlsoLinkedFrom {
+        url: String,
+        referrer: String,
+    },
+    Warning(LinkWarning),
+    /// Sent for every URL fetched, broken or not, when enabled with `report_checked`
+    Checked(CheckedUrl),
+    /// Sent for every link deliberately not checked, when enabled with `report_skipped`
+    Skipped(SkippedLink),
+    /// A link the crawl would fetch, sent instead of fetching it with `dry_run`
+    WouldFetch {
+        url: String,
+        referrer: String,
+        /// Only checked, not crawled: a link to another site, or a resource like an image
+        check_only: bool,
+    },
+}
+
+/// The outcome of fetching one URL.
+#[derive(Debug, Clone)]
+pub struct CheckedUrl {
+    pub url: String,
+    /// HTTP status of the final response; `None` if no response was received
+    pub status: Option<u16>,
+    pub elapsed: Duration,
+    pub content_type: Option<String>,
+    pub referrer: Option<String>,
+    pub checked_at: SystemTime,
+}
+
+/// Body and content type of a successful response.
+struct FetchedPage {
+    status: u16,
+    content_type: Option<String>,
+    robots: RobotsDirectives,
+    /// `rel="next"`/`rel="prev"` targets from the `Link` header
+    link_header: Vec<String>,
+    body: Vec<u8>,
+    /// Length of `body` as fetched; the body itself is dropped once its links are extracted
+    body_len: usize,
+    /// The URL that answered, if the request was redirected
+    redirected_to: Option<String>,
+    /// From sending the request to the end of the body, for the attempt that loaded; unlike
+    /// [`PageOutcome::elapsed`], leaves out retries and waiting for a request slot
+    elapsed: Duration,
+    /// The body went over the size limit, so it was dropped unread rather than parsed
+    oversized: bool,
+    /// Validators of the response, for asking for the page again only if it changed
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl FetchedPage {
+    fn is_pdf(&self) -> bool {
+        self.content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.starts_with("application/pdf"))
+    }
+
+    /// `application/json`, or a structured JSON type like `application/ld+json`
+    fn is_json(&self) -> bool {
+        self.content_type.as_deref().is_some_and(|content_type| {
+            let essence = content_type.split(';').next().unwrap_or_default().trim();
+            essence == "application/json" || essence.ends_with("+json")
+        })
+    }
+
+    fn is_html(&self) -> bool {
+        self.content_type.as_deref().is_some_and(|content_type| {
+            content_type.starts_with("text/html")
+                || content_type.starts_with("application/xhtml+xml")
+        })
+    }
+
+    /// HTML, or a response that doesn't say what it is and might be; anything else (images,
+    /// stylesheets, archives, ...) has no links to follow, so isn't downloaded or parsed.
+    fn is_crawlable(&self) -> bool {
+        self.content_type.is_none() || self.is_html()
+    }
+}
+
+/// What a soft 404 probe got back for a page that can't exist.
+#[derive(Debug)]
+enum ErrorPage {
+    /// An error status: the site reports missing pages properly
+    Status,
+    /// A page, requested from `path`
+    Page { text: PageText, path: String },
+}
+
+/// A request that failed, with the `<title>` of the error page if one came back.
+#[derive(Debug)]
+struct HttpError {
+    error: reqwest::Error,
+    title: Option<String>,
+}
+
+impl HttpError {
+    fn status(&self) -> Option<reqwest::StatusCode> {
+        self.error.status()
+    }
+
+    /// The URL that answered, or that redirected back into a loop, if the request was
+    /// redirected.
+    fn redirected_to(&self, requested: &str) -> Option<String> {
+        redirect_target(requested, self.error.url()?)
+    }
+
+    /// Whether no response came back because of the network, e.g. a refused or dropped
+    /// connection or a timeout, rather than a bad URL or a redirect loop.
+    fn is_transient(&self) -> bool {
+        self.status().is_none()
+            && (self.error.is_connect()
+                || self.error.is_timeout()
+                || self.error.is_request()
+                || self.error.is_body())
+    }
+
+    /// Whether the TLS handshake failed, as with a self-signed, expired or wrong-host
+    /// certificate. reqwest has no kind for it, so this goes by the messages of the error's
+    /// sources, whatever the TLS backend.
+    fn is_tls(&self) -> bool {
+        self.sources().any(|e| {
+            let message = e.to_string().to_ascii_lowercase();
+            ["certificate", "ssl", "tls"]
+                .iter()
+                .any(|word| message.contains(word))
+        })
+    }
+
+    /// Whether the host name couldn't be resolved, as for a domain that's gone. Like
+    /// [`HttpError::is_tls`], this goes by the messages of the error's sources.
+    fn is_dns(&self) -> bool {
+        self.error.is_connect()
+            && self
+                .sources()
+                .any(|e| e.to_string().to_ascii_lowercase().starts_with("dns error"))
+    }
+
+    /// Whether the server refused the connection: nothing listens on the port.
+    fn is_refused(&self) -> bool {
+        self.sources().any(|e| {
+            e.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::ConnectionRefused)
+        })
+    }
+
+    /// The errors behind this one, from its immediate cause on.
+    fn sources(&self) -> impl Iterator<Item = &(dyn std::error::Error + 'static)> {
+        std::iter::successors(std::error::Error::source(&self.error), |e| e.source())
+    }
+}
+
+impl From<reqwest::Error> for HttpError {
+    fn from(error: reqwest::Error) -> Self {
+        HttpError { error, title: None }
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+/// Most redirects followed for one request, as in reqwest's default policy.
+const MAX_REDIRECTS: usize = 10;
+
+/// Redirect policy of the crawler's client: follows redirects like reqwest's default policy,
+/// but fails as soon as one leads back to a URL already on the way, so loops are reported as
+/// such rather than as too many redirects.
+pub fn redirect_policy() -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(|attempt| {
+        log::debug!(
+            "{} redirects to {}",
+            attempt.previous().last().map_or("", Url::as_str),
+            attempt.url()
+        );
+        if attempt.previous().contains(attempt.url()) {
+            let message = format!("redirect loop at {}", attempt.url());
+            attempt.error(message)
+        } else if attempt.previous().len() > MAX_REDIRECTS {
+            attempt.error(format!("more than {} redirects", MAX_REDIRECTS))
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// `Authorization` value for HTTP Basic Auth with `user` and `password`, marked sensitive so it
+/// never shows up in debug logs.
+pub fn basic_auth(user: &str, password: &str) -> HeaderValue {
+    use base64::Engine;
+    let credentials =
+        base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password));
+    let mut value = HeaderValue::from_str(&format!("Basic {}", credentials))
+        .expect("base64 is a valid header value");
+    value.set_sensitive(true);
+    value
+}
+
+/// How long the default client keeps an idle connection open for reuse.
+pub const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Settings of the default client, which is built once the builder has them all.
+#[derive(Debug, Default)]
+struct ClientOptions {
+    max_connections: Option<usize>,
+    proxy: Option<reqwest::Proxy>,
+    accept_invalid_certs: bool,
+    cookies: Option<Arc<Jar>>,
+}
+
+/// The client used unless one is passed to [`CrawlerBuilder::client`]. With `max_connections`,
+/// each host keeps at most a quarter of them idle: with several hosts, their idle connections
+/// together would otherwise pile up past the cap. Without a `proxy`, the `HTTP_PROXY`,
+/// `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` environment variables apply, as in reqwest.
+/// Like every client, it asks for gzip, brotli or deflate compressed bodies and decompresses
+/// them, so links are always extracted from the HTML itself. Cookies the site sets are kept in
+/// `cookies`, or a jar of its own, and sent back, so a session started on one page carries on.
+fn default_client(options: ClientOptions) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .redirect(redirect_policy())
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .cookie_provider(options.cookies.unwrap_or_default())
+        .danger_accept_invalid_certs(options.accept_invalid_certs);
+    if let Some(max) = options.max_connections {
+        builder = builder.pool_max_idle_per_host((max / 4).max(1));
+    }
+    if let Some(proxy) = options.proxy {
+        builder = builder.proxy(proxy);
+    }
+    builder.build().expect("the default client builds")
+}
+
+/// `url` without its query string and fragment.
+fn query_stem(url: &Url) -> Url {
+    let mut stem = url.clone();
+    stem.set_query(None);
+    stem.set_fragment(None);
+    stem
+}
+
+/// Where a request for `requested` ended up, if that's a different URL, fragments aside.
+///
+/// reqwest resolves each `Location` against the URL it was requested from, but drops the
+/// fragment of the link, so `answered` goes through [`resolve_redirect`] to inherit it.
+fn redirect_target(requested: &str, answered: &Url) -> Option<String> {
+    let requested = Url::parse(requested).ok()?;
+    let target = resolve_redirect(&requested, answered.as_str()).ok()?;
+    let without_fragment = |url: &Url| {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        url
+    };
+    (without_fragment(&requested) != without_fragment(&target)).then(|| target.to_string())
+}
+
+/// Requests `url` with the crawl's one client, so connections are pooled across requests.
+/// `user_agent` replaces the client's own; `headers` replace both, and `extra_headers` all of
+/// them. The body of a successful response is only downloaded if `wants_body` says so, given
+/// everything but the body, and only up to `max_body_bytes`: past that, the download stops and
+/// the page comes back empty and [`FetchedPage::oversized`].
+#[allow(clippy::too_many_arguments)]
+async fn fetch_html(
+    client: &reqwest::Client,
+    method: &Method,
+    url: &str,
+    user_agent: Option<&str>,
+    accept: Option<HeaderValue>,
+    headers: Option<&HeaderMap>,
+    extra_headers: &HeaderMap,
+    timeout: Option<Duration>,
+    bytes_downloaded: &AtomicU64,
+    max_body_bytes: Option<u64>,
+    wants_body: impl Fn(&FetchedPage) -> bool,
+) -> Result<FetchedPage, HttpError> {
+    let mut request = client.request(method.clone(), url);
+    if let Some(user_agent) = user_agent {
+        request = request.header(reqwest::header::USER_AGENT, user_agent);
+    }
+    if let Some(accept) = accept {
+        request = request.header(reqwest::header::ACCEPT, accept);
+    }
+    // Set after `accept`, so a full set of browser headers keeps the browser's own Accept
+    if let Some(headers) = headers {
+        request = request.headers(headers.clone());
+    }
+    if !extra_headers.is_empty() {
+        request = request.headers(extra_headers.clone());
+    }
+    if let Some(timeout) = timeout {
+        request = request.timeout(timeout);
+    }
+    let started = Instant::now();
+    let mut resp = request.send().await?;
+
+    // Anything but an error status comes back as a page: a 304 only comes back for a request
+    // with validators, and is the cached page unchanged, and a redirect that's still the final
+    // response couldn't be followed, which [`Crawler::fetch_page`] reports
+    match resp.error_for_status_ref().err() {
+        None => {
+            let content_type = resp
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_ascii_lowercase());
+            let robots = RobotsDirectives::from_x_robots_tag(
+                resp.headers()
+                    .get_all("x-robots-tag")
+                    .iter()
+                    .filter_map(|value| value.to_str().ok()),
+            );
+            let link_header = link_header_targets(
+                resp.headers()
+                    .get_all(reqwest::header::LINK)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok()),
+                &["next", "prev"],
+            );
+            let header = |name| {
+                resp.headers()
+                    .get(name)
+                    .and_then(|value: &HeaderValue| value.to_str().ok())
+                    .map(str::to_string)
+            };
+            let etag = header(reqwest::header::ETAG);
+            let last_modified = header(reqwest::header::LAST_MODIFIED);
+            let mut page = FetchedPage {
+                status: resp.status().as_u16(),
+                content_type,
+                robots,
+                link_header,
+                body: Vec::new(),
+                body_len: 0,
+                redirected_to: redirect_target(url, resp.url()),
+                elapsed: Duration::ZERO,
+                oversized: false,
+                etag,
+                last_modified,
+            };
+            if !wants_body(&page) {
+                log::debug!(
+                    "{} is {}, not downloading it",
+                    url,
+                    page.content_type.as_deref().unwrap_or_default()
+                );
+                page.elapsed = started.elapsed();
+                return Ok(page);
+            }
+            let too_big = |len: u64| max_body_bytes.is_some_and(|max| len > max);
+            // A declared length is only there for uncompressed bodies, which can then be turned down
+            // before downloading any of it
+            if resp.content_length().is_some_and(too_big) {
+                log::info!(
+                    "{} is {} bytes, over the body size limit, not downloading it",
+                    url,
+                    resp.content_length().unwrap_or_default()
+                );
+                page.body_len = resp.content_length().unwrap_or_default() as usize;
+                page.oversized = true;
+                page.elapsed = started.elapsed();
+                return Ok(page);
+            }
+            // Read the body chunk by chunk so the byte budget reflects what was actually downloaded,
+            // once decompressed
+            while let Some(chunk) = resp.chunk().await? {
+                bytes_downloaded.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                page.body_len += chunk.len();
+                if too_big(page.body_len as u64) {
+                    log::info!("{} is over the body size limit, dropping it", url);
+                    page.body = Vec::new();
+                    page.oversized = true;
+                    break;
+                }
+                page.body.extend_from_slice(&chunk);
+            }
+            page.elapsed = started.elapsed();
+            Ok(page)
+        }
+        Some(error) => {
+            // Error pages are usually HTML too, and their title can tell a removed page from a typo
+            let is_html = resp
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value.to_ascii_lowercase().starts_with("text/html"));
+            let title = if is_html {
+                let mut body = Vec::new();
+                // The title is a nice-to-have: a body that breaks off keeps what was read, and the
+                // status still stands
+                while let Ok(Some(chunk)) = resp.chunk().await {
+                    bytes_downloaded.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                    body.extend_from_slice(&chunk);
+                    // The title is at the top, whatever comes after the limit
+                    if max_body_bytes.is_some_and(|max| body.len() as u64 > max) {
+                        break;
+                    }
+                }
+                crate::links::page_title(&String::from_utf8_lossy(&body))
+            } else {
+                None
+            };
+            Err(HttpError { error, title })
+        }
+    }
+}
+
+/// Why a page couldn't be loaded.
+#[derive(Debug)]
+enum FetchError {
+    Http(HttpError),
+    /// Reading the page from `local_dir` failed; a missing file counts as a 404
+    File(std::io::Error),
+    /// The response was a success, but its JSON body holds an error at `body_error_paths`
+    Body {
+        status: u16,
+        message: String,
+    },
+    /// The response was a success, but the page is an error page
+    Soft404 {
+        status: u16,
+        message: String,
+    },
+    /// The final response was a redirect, with no usable `Location` or from a client that
+    /// doesn't follow redirects, or a 304 for a page that isn't cached
+    Redirect {
+        status: u16,
+        message: String,
+    },
+}
+
+impl FetchError {
+    /// HTTP status of the final response; `None` if no response was received
+    fn status(&self) -> Option<u16> {
+        match self {
+            FetchError::Http(e) => e.status().map(|status| status.as_u16()),
+            FetchError::File(e) if e.kind() == std::io::ErrorKind::NotFound => Some(404),
+            FetchError::File(_) => None,
+            FetchError::Body { status, .. }
+            | FetchError::Soft404 { status, .. }
+            | FetchError::Redirect { status, .. } => Some(*status),
+        }
+    }
+
+    /// Whether no response came back because of the network, see [`HttpError::is_transient`]
+    fn is_transient(&self) -> bool {
+        matches!(self, FetchError::Http(e) if e.is_transient())
+    }
+
+    /// Whether redirects were followed into a loop, or too many of them, or one couldn't be
+    /// followed at all
+    fn is_broken_redirect(&self) -> bool {
+        match self {
+            FetchError::Http(e) => e.error.is_redirect(),
+            FetchError::Redirect { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the page came back but can't be read: a corrupt compressed body, or a file in
+    /// `local_dir` that's there but can't be opened, e.g. for lack of permission
+    fn is_unreadable(&self) -> bool {
+        match self {
+            FetchError::Http(e) => e.error.is_decode(),
+            FetchError::File(e) => e.kind() != std::io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+
+    /// Whether the URL is reported as broken, rather than failing the crawl: any error status,
+    /// or only a 404 with `only_404`, requests that got no response because of the network,
+    /// redirect loops, and pages that can't be read.
+    fn is_broken_link(&self, only_404: bool) -> bool {
+        if matches!(self, FetchError::Body { .. } | FetchError::Soft404 { .. })
+            || self.is_transient()
+            || self.is_broken_redirect()
+            || self.is_unreadable()
+        {
+            return true;
+        }
+        match self.status() {
+            Some(status) if only_404 => status == 404,
+            Some(status) => status >= 400,
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Http(e) => e.fmt(f),
+            FetchError::File(e) => e.fmt(f),
+            FetchError::Body { status, message } => write!(f, "{} with error: {}", status, message),
+            FetchError::Soft404 { status, message } => write!(f, "{}, but {}", status, message),
+            FetchError::Redirect { message, .. } => write!(f, "broken redirect: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Why a page that came back with `status` has nothing to show for it, if it's a redirect that
+/// wasn't followed, or a 304 that isn't for a cached page.
+fn unfollowed_redirect(status: u16) -> Option<String> {
+    match status {
+        304 => Some("304 for a page that isn't cached".to_string()),
+        300..=399 => Some(format!("{} that couldn't be followed", status)),
+        _ => None,
+    }
+}
+
+/// Reads the file `local_dir` serves for `url` as if it had been fetched over HTTP.
+async fn read_local_file(
+    dir: &Path,
+    url: &str,
+    bytes_downloaded: &AtomicU64,
+) -> Result<FetchedPage, FetchError> {
+    let started = Instant::now();
+    let not_found = || FetchError::File(std::io::ErrorKind::NotFound.into());
+    let url = Url::parse(url).map_err(|_| not_found())?;
+    let path = crate::local_dir::file_for_url(dir, &url).ok_or_else(not_found)?;
+    let content_type = crate::local_dir::content_type_for(&path);
+    // Only read files links are extracted from; for the rest, existing is enough
+    let body = if content_type.is_some() {
+        tokio::fs::read(&path).await.map_err(FetchError::File)?
+    } else {
+        tokio::fs::metadata(&path).await.map_err(FetchError::File)?;
+        Vec::new()
+    };
+    bytes_downloaded.fetch_add(body.len() as u64, Ordering::Relaxed);
+    Ok(FetchedPage {
+        status: 200,
+        content_type: content_type.map(str::to_string),
+        robots: RobotsDirectives::default(),
+        link_header: Vec::new(),
+        body_len: body.len(),
+        body,
+        redirected_to: None,
+        elapsed: started.elapsed(),
+        oversized: false,
+        etag: None,
+        last_modified: None,
+    })
+}
+
+/// A fetched page and the links found on it, handed from a fetch task back to the crawl loop.
+struct PageOutcome {
+    url: String,
+    referrer: Option<String>,
+    /// Links followed from the root URL to get here; 0 for the root itself
+    depth: u32,
+    /// Checked with `check_external`, rather than crawled
+    external: bool,
+    /// Loaded by a page, like an image or stylesheet, so checked rather than crawled
+    resource: bool,
+    elapsed: Duration,
+    result: Result<FetchedPage, FetchError>,
+    /// `None` if extracting links timed out
+    links: Option<ExtractedLinks>,
+    /// The URL's `page` span, closed once its links have been queued
+    span: tracing::Span,
+}
+
+impl PageOutcome {
+    /// HTTP status of the final response; `None` if no response was received
+    fn status(&self) -> Option<u16> {
+        match &self.result {
+            Ok(page) => Some(page.status),
+            Err(e) => e.status(),
+        }
+    }
+}
+
+/// The links waiting to be fetched. Depth-first takes the newest first, finishing a section of
+/// the site before the next; breadth-first takes the oldest, so every page at one depth comes
+/// before any deeper one.
+struct Frontier<T> {
+    queue: VecDeque<T>,
+    breadth_first: bool,
+}
+
+impl<T> Frontier<T> {
+    fn new(breadth_first: bool) -> Self {
+        Frontier {
+            queue: VecDeque::new(),
+            breadth_first,
+        }
+    }
+
+    /// Queues `items` so that they are taken in the order given, before anything already queued
+    /// deeper down. For the starting URLs, which either ordering takes first.
+    fn push_in_order(&mut self, items: Vec<T>) {
+        if self.breadth_first {
+            self.queue.extend(items);
+        } else {
+            self.queue.extend(items.into_iter().rev());
+        }
+    }
+
+    fn push(&mut self, item: T) {
+        self.queue.push_back(item);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.breadth_first {
+            self.queue.pop_front()
+        } else {
+            self.queue.pop_back()
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.queue.iter()
+    }
+}
+
+/// How the number of requests in flight is decided.
+enum ConcurrencyLimit {
+    Fixed(usize),
+    Auto(AimdController),
+    Backoff(ErrorBackoff),
+}
+
+impl ConcurrencyLimit {
+    fn limit(&self) -> usize {
+        match self {
+            ConcurrencyLimit::Fixed(limit) => *limit,
+            ConcurrencyLimit::Auto(controller) => controller.limit(),
+            ConcurrencyLimit::Backoff(backoff) => backoff.limit(),
+        }
+    }
+
+    fn is_adjustable(&self) -> bool {
+        !matches!(self, ConcurrencyLimit::Fixed(_))
+    }
+
+    /// Records one finished request and returns the (possibly changed) limit.
+    fn record(&mut self, elapsed: Duration, status: Option<u16>) -> usize {
+        match self {
+            ConcurrencyLimit::Fixed(limit) => *limit,
+            ConcurrencyLimit::Auto(controller) => controller.record(elapsed, status),
+            ConcurrencyLimit::Backoff(backoff) => backoff.record(status),
+        }
+    }
+}
+
+async fn send_warning(tx: &DebugSender<CrawlMessage>, warning: LinkWarning) {
+    if let Err(send_err) = tx
+        .send(CrawlMessage::Finding(Finding::Warning(warning)))
+        .await
+    {
+        log::error!("Failed to send warning through the channel: {}", send_err);
+    }
+}
+
+async fn send_also_linked_from(tx: &DebugSender<CrawlMessage>, url: &str, referrer: &str) {
+    let finding = Finding::AlsoLinkedFrom {
+        url: url.to_string(),
+        referrer: referrer.to_string(),
+    };
+    if let Err(send_err) = tx.send(CrawlMessage::Finding(finding)).await {
+        log::error!(
+            "Failed to send a referrer through the channel: {}",
+            send_err
+        );
+    }
+}
+
+async fn send_skipped(
+    tx: &DebugSender<CrawlMessage>,
+    root_url: &Url,
+    url: String,
+    referrer: &str,
+    reason: SkipReason,
+) {
+    log::debug!("Skipping {} ({:?})", url, reason);
+    // Relative hrefs are classified as if found on the referrer
+    let class = Url::parse(referrer)
+        .unwrap_or_else(|_| root_url.clone())
+        .join(&url)
+        .ok()
+        .map(|absolute| LinkClass::of(&absolute, root_url));
+    let skipped = SkippedLink {
+        url,
+        referrer: referrer.to_string(),
+        reason,
+        class,
+    };
+    if let Err(send_err) = tx
+        .send(CrawlMessage::Finding(Finding::Skipped(skipped)))
+        .await
+    {
+        log::error!(
+            "Failed to send skipped link through the channel: {}",
+            send_err
+        );
+    }
+}
+
+/// Links queued from a page between yields to the runtime, so a page with many thousands of
+/// links doesn't keep other tasks, like the CTRL+C handler, waiting.
+const LINKS_PER_YIELD: usize = 256;
+
+/// Sitemaps fetched at most with `use_sitemap`, counting the index files, so an index that
+/// lists itself or a generator gone wrong can't hold up the crawl.
+const MAX_SITEMAPS: usize = 100;
+
+/// Largest response body downloaded by default, see [`CrawlerBuilder::max_body_bytes`].
+pub const DEFAULT_MAX_BODY_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Runs CPU-heavy link extraction from a PDF or JSON document on the blocking thread pool, like
+/// HTML parsing in [`find_links_with_timeout`].
+async fn extract_blocking(extract: impl FnOnce() -> Vec<String> + Send + 'static) -> Vec<String> {
+    tokio::task::spawn_blocking(extract)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("Link extraction failed: {}", e);
+            Vec::new()
+        })
+}
+
+/// Runs `extract_links_from` on the blocking thread pool so a pathological page can't stall the
+/// crawl.
+///
+/// Returns `None` if parsing took longer than `timeout` (a zero timeout means no limit). The
+/// blocking task can't be cancelled, so a timed-out parse keeps running in the background until
+/// it finishes; the crawl just stops waiting for it.
+async fn find_links_with_timeout(
+    html: String,
+    timeout: Duration,
+    sources: LinkSources,
+) -> Option<ExtractedLinks> {
+    let parse = tokio::task::spawn_blocking(move || extract_links_from(&html, sources));
+    let result = if timeout.is_zero() {
+        parse.await
+    } else {
+        tokio::time::timeout(timeout, parse).await.ok()?
+    };
+    match result {
+        Ok(links) => Some(links),
+        Err(e) => {
+            log::error!("Link extraction failed: {}", e);
+            Some(ExtractedLinks::default())
+        }
+    }
+}
+
+/// Called with each broken link as it's found; see [`CrawlerBuilder::on_broken_link`].
+pub type BrokenLinkHook = Arc<dyn Fn(&NotFoundError) + Send + Sync>;
+
+/// Decides which URLs are followed; see [`CrawlerBuilder::should_crawl`].
+pub type CrawlPredicate = Arc<dyn Fn(&Url) -> bool + Send + Sync>;
+
+/// Crawls a site from a root URL, reporting broken links and warnings over a channel.
+///
+/// Build one with [`Crawler::builder`].
+pub struct Crawler {
+    root_url: Url,
+    fuzzy_match_string: Option<String>,
+    include_subdomains: bool,
+    scope: Vec<ScopeEntry>,
+    should_crawl: Option<CrawlPredicate>,
+    on_broken_link: Option<BrokenLinkHook>,
+    path_prefix: Option<String>,
+    check_out_of_scope: bool,
+    url_filter: UrlPatternFilter,
+    user_agents: UserAgentPicker,
+    suggest_fixes: bool,
+    parse_timeout: Duration,
+    /// Shared by every request, for its connection pool
+    client: reqwest::Client,
+    /// Set with `client`, whose own user agent is then sent
+    custom_client: bool,
+    max_bytes: Option<u64>,
+    max_pages: Option<u64>,
+    bytes_downloaded: Arc<AtomicU64>,
+    retry_policy: RetryPolicy,
+    cancel: CancellationToken,
+    #[cfg(feature = "pdf")]
+    check_pdf_links: bool,
+    report_checked: bool,
+    report_skipped: bool,
+    respect_robots_header: bool,
+    respect_robots_meta: bool,
+    flag_redirects: bool,
+    follow_link_header: bool,
+    follow_canonical: bool,
+    unsafe_urls: Option<UnsafeUrlFilter>,
+    dns_prefetch: bool,
+    breadth_first: bool,
+    ua_fallback: bool,
+    only_404: bool,
+    check_external: bool,
+    max_distinct_hosts: Option<usize>,
+    external_concurrency: Option<usize>,
+    external_delay: Duration,
+    max_errors: Option<usize>,
+    ignore_broken: IgnoreList,
+    json_url_paths: Option<Vec<JsonPath>>,
+    body_error_paths: Option<Vec<JsonPath>>,
+    soft_404: Option<Arc<Soft404Detector>>,
+    /// What the soft 404 probe found, once it's run; unset if it didn't or couldn't
+    error_page: OnceLock<ErrorPage>,
+    normalizer: UrlNormalizer,
+    request_timeout: Option<Duration>,
+    slow_start: Option<SlowStart>,
+    pacer: Arc<HostPacer>,
+    concurrency: usize,
+    backoff_on_errors: bool,
+    max_auto_concurrency: Option<usize>,
+    effective_concurrency: Arc<AtomicUsize>,
+    progress: Arc<CrawlProgress>,
+    local_dir: Option<PathBuf>,
+    page_cache: Option<Arc<PageCache>>,
+    link_sources: LinkSources,
+    min_content_length: Option<usize>,
+    slow_page_threshold: Option<Duration>,
+    max_body_bytes: Option<u64>,
+    accept: Option<AcceptHeader>,
+    headers: HeaderMap,
+    seed_urls: Vec<Url>,
+    use_sitemap: bool,
+    dry_run: bool,
+    /// One permit per request in flight, when connections are capped
+    connections: Option<Arc<Semaphore>>,
+}
+
+pub struct CrawlerBuilder {
+    crawler: Crawler,
+    /// For the default client, built in `build` unless `client` replaced it
+    client_options: ClientOptions,
+}
+
+impl CrawlerBuilder {
+    /// Also follow links whose domain matches this string, by whole labels; see
+    /// [`fuzzy_matches`].
+    pub fn fuzzy_match_string(mut self, fuzzy_match_string: Option<String>) -> Self {
+        self.crawler.fuzzy_match_string = fuzzy_match_string;
+        self
+    }
+
+    /// Also follow links to subdomains of the root URL's domain, like `blog.example.com` for
+    /// `example.com`; see [`is_subdomain_of`]. Only below the root's own domain, so from
+    /// `www.example.com` that's `*.www.example.com`, not `example.com`.
+    pub fn include_subdomains(mut self, include_subdomains: bool) -> Self {
+        self.crawler.include_subdomains = include_subdomains;
+        self
+    }
+
+    /// Only follow links matching one of these entries, instead of comparing domains with the
+    /// root URL (and the fuzzy match string). Empty means the domain comparison.
+    pub fn scope(mut self, scope: Vec<ScopeEntry>) -> Self {
+        self.crawler.scope = scope;
+        self
+    }
+
+    /// Follow the links `should_crawl` returns true for, instead of deciding by domain, the fuzzy
+    /// match string or `scope`; the rest are only checked with `check_out_of_scope`, like links
+    /// to other sites. `path_prefix`, `include`/`exclude` patterns and the like still apply.
+    ///
+    /// It's called from the crawl loop for every link found, so it should return quickly.
+    /// `Send + Sync`, since the crawler can be moved to and shared between tasks.
+    pub fn should_crawl(
+        mut self,
+        should_crawl: impl Fn(&Url) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.crawler.should_crawl = Some(Arc::new(should_crawl));
+        self
+    }
+
+    /// Call `on_broken_link` with each broken link as it's found, before it's sent over the
+    /// channel, e.g. to post it somewhere. Other pages with the same broken link come later as
+    /// [`Finding::AlsoLinkedFrom`], so `also_linked_from` is always empty here.
+    ///
+    /// It's called from the crawl loop, which waits for it: anything slow, like a request,
+    /// belongs on a task of its own. `Send + Sync`, as for [`Self::should_crawl`].
+    pub fn on_broken_link(
+        mut self,
+        on_broken_link: impl Fn(&NotFoundError) + Send + Sync + 'static,
+    ) -> Self {
+        self.crawler.on_broken_link = Some(Arc::new(on_broken_link));
+        self
+    }
+
+    /// Only follow links on the site whose path is under `path_prefix`, like `/docs`: that
+    /// covers `/docs`, `/docs/` and `/docs/intro`, but not `/docsearch`. Links elsewhere on the
+    /// site are skipped as [`SkipReason::OutsidePathPrefix`], unless `check_out_of_scope` is on.
+    /// The images and stylesheets pages load are checked wherever they are, and the root URL and
+    /// seeds are crawled regardless.
+    pub fn path_prefix(mut self, path_prefix: Option<String>) -> Self {
+        self.crawler.path_prefix = path_prefix.map(|prefix| match prefix.starts_with('/') {
+            true => prefix,
+            false => format!("/{}", prefix),
+        });
+        self
+    }
+
+    /// Check the links on the site outside `path_prefix` rather than skip them, without
+    /// following their links, like links to other sites with `check_external`.
+    pub fn check_out_of_scope(mut self, check_out_of_scope: bool) -> Self {
+        self.crawler.check_out_of_scope = check_out_of_scope;
+        self
+    }
+
+    /// Only check the links `url_filter` allows, on this site or any other; the rest are skipped
+    /// as [`SkipReason::Excluded`]. The root URL and seeds are checked regardless.
+    pub fn url_filter(mut self, url_filter: UrlPatternFilter) -> Self {
+        self.crawler.url_filter = url_filter;
+        self
+    }
+
+    pub fn user_agents(mut self, user_agents: UserAgentPicker) -> Self {
+        self.crawler.user_agents = user_agents;
+        self
+    }
+
+    pub fn suggest_fixes(mut self, suggest_fixes: bool) -> Self {
+        self.crawler.suggest_fixes = suggest_fixes;
+        self
+    }
+
+    pub fn parse_timeout(mut self, parse_timeout: Duration) -> Self {
+        self.crawler.parse_timeout = parse_timeout;
+        self
+    }
+
+    /// Use an already configured client for every request.
+    ///
+    /// Per-request settings still apply: `request_timeout`, `accept`, `headers`, `retry_policy`,
+    /// the browser headers of `ua_fallback`, `request_delay` and `max_connections`. What the
+    /// client is built with is its own: its user agent is sent instead of `user_agents`, and its
+    /// redirect policy, connection pool, proxy, TLS and cookie settings replace
+    /// [`redirect_policy`], and the pool limits, `proxy`, `accept_invalid_certs` and
+    /// `cookie_jar` of the default client.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.crawler.client = client;
+        self.crawler.custom_client = true;
+        self
+    }
+
+    /// Stop crawling once this many body bytes have been downloaded in total.
+    pub fn max_bytes(mut self, max_bytes: Option<u64>) -> Self {
+        self.crawler.max_bytes = max_bytes;
+        self
+    }
+
+    /// Stop crawling once this many URLs have been requested, counting links that are only
+    /// checked. Requests already in flight finish, and no more are started.
+    pub fn max_pages(mut self, max_pages: Option<u64>) -> Self {
+        self.crawler.max_pages = max_pages;
+        self
+    }
+
+    /// How often to retry each HTTP error status. Defaults to [`RetryPolicy::default`].
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.crawler.retry_policy = retry_policy;
+        self
+    }
+
+    /// Extract hyperlinks from PDF documents and check them like links found on a page.
+    #[cfg(feature = "pdf")]
+    pub fn check_pdf_links(mut self, check_pdf_links: bool) -> Self {
+        self.crawler.check_pdf_links = check_pdf_links;
+        self
+    }
+
+    /// Send a [`Finding::Checked`] for every URL fetched, not just the broken ones.
+    pub fn report_checked(mut self, report_checked: bool) -> Self {
+        self.crawler.report_checked = report_checked;
+        self
+    }
+
+    /// Send a [`Finding::Skipped`] for every link that is deliberately not checked.
+    pub fn report_skipped(mut self, report_skipped: bool) -> Self {
+        self.crawler.report_skipped = report_skipped;
+        self
+    }
+
+    /// Honor `X-Robots-Tag` response headers: don't follow links on `nofollow` pages, and
+    /// report `noindex` pages.
+    pub fn respect_robots_header(mut self, respect_robots_header: bool) -> Self {
+        self.crawler.respect_robots_header = respect_robots_header;
+        self
+    }
+
+    /// Honor `<meta name="robots">` tags like `respect_robots_header` does the header: don't
+    /// follow links on `nofollow` pages, and report `noindex` pages. The page itself is still
+    /// checked.
+    pub fn respect_robots_meta(mut self, respect_robots_meta: bool) -> Self {
+        self.crawler.respect_robots_meta = respect_robots_meta;
+        self
+    }
+
+    /// Warn about every link that only works through a redirect, as
+    /// [`WarningCategory::Redirect`], so it can be updated to where it ends up.
+    pub fn flag_redirects(mut self, flag_redirects: bool) -> Self {
+        self.crawler.flag_redirects = flag_redirects;
+        self
+    }
+
+    /// Also follow `rel="next"` and `rel="prev"` targets of the `Link` response header, for
+    /// resources paginated through headers rather than HTML links.
+    pub fn follow_link_header(mut self, follow_link_header: bool) -> Self {
+        self.crawler.follow_link_header = follow_link_header;
+        self
+    }
+
+    /// Never request in-scope links matching the filter; report them as
+    /// [`WarningCategory::UnsafeLink`] instead.
+    pub fn safe_only(mut self, unsafe_urls: Option<UnsafeUrlFilter>) -> Self {
+        self.crawler.unsafe_urls = unsafe_urls;
+        self
+    }
+
+    /// When a page returns 403, retry it once with a full set of browser headers (see
+    /// [`browser_headers`]) before reporting it. On by default.
+    pub fn ua_fallback(mut self, ua_fallback: bool) -> Self {
+        self.crawler.ua_fallback = ua_fallback;
+        self
+    }
+
+    /// Only report 404s as broken links; any other error status fails the crawl, like a network
+    /// error does. Off by default: every status of 400 or more is reported.
+    pub fn only_404(mut self, only_404: bool) -> Self {
+        self.crawler.only_404 = only_404;
+        self
+    }
+
+    /// Check links to other sites too, with a `HEAD` request (or `GET` if the server doesn't
+    /// allow `HEAD`), without following their links. Each URL is checked once, however many
+    /// pages link to it. Off by default: out-of-scope links are skipped.
+    pub fn check_external(mut self, check_external: bool) -> Self {
+        self.crawler.check_external = check_external;
+        self
+    }
+
+    /// With `check_external`, contact at most this many other sites: links to further hosts are
+    /// skipped, and each of those hosts is logged once. Hosts in scope don't count. Seed URLs on
+    /// other sites are always checked, and count like the first hosts linked. Bounds the
+    /// requests a page linking to hundreds of sites can cause.
+    pub fn max_distinct_hosts(mut self, max_distinct_hosts: Option<usize>) -> Self {
+        self.crawler.max_distinct_hosts = max_distinct_hosts;
+        self
+    }
+
+    /// With `check_external`, check links to other sites with up to this many requests in flight
+    /// of their own, besides the crawl's `concurrency`, so they're checked alongside the crawl
+    /// without taking its slots, or being held up by it. `None`, the default, has them share
+    /// the crawl's.
+    pub fn external_concurrency(mut self, external_concurrency: Option<usize>) -> Self {
+        self.crawler.external_concurrency = external_concurrency.map(|max| max.max(1));
+        self
+    }
+
+    /// With `check_external`, leave at least this long between the starts of requests to each
+    /// other site, to be polite to servers the crawl isn't about. Hosts in scope keep the
+    /// `request_delay`, and so do other sites when it's longer. Zero by default.
+    pub fn external_delay(mut self, external_delay: Duration) -> Self {
+        self.crawler.external_delay = external_delay;
+        self
+    }
+
+    /// Stop crawling once this many broken links have been reported. Requests already in flight
+    /// finish, but any further broken links they turn up are dropped. Whether any were left
+    /// unchecked is [`CrawlProgress::stopped_at_max_errors`].
+    pub fn max_errors(mut self, max_errors: Option<usize>) -> Self {
+        self.crawler.max_errors = max_errors;
+        self
+    }
+
+    /// Don't report broken links matching `ignore`, known to be broken for good, like a
+    /// login-gated page; they don't count towards `max_errors` either. Each one left out is
+    /// logged at debug level.
+    pub fn ignore_broken(mut self, ignore: IgnoreList) -> Self {
+        self.crawler.ignore_broken = ignore;
+        self
+    }
+
+    /// Extract links from JSON responses too: the strings selected by `paths`, or with no paths
+    /// every string that is a complete http(s) URL. See [`find_json_links`].
+    pub fn json_links(mut self, paths: Option<Vec<JsonPath>>) -> Self {
+        self.crawler.json_url_paths = paths;
+        self
+    }
+
+    /// Report JSON responses as broken when any of `paths` selects a non-null value, like
+    /// `{"error": "not found"}` served with a 200. The value is reported as the error message.
+    pub fn body_error_paths(mut self, paths: Option<Vec<JsonPath>>) -> Self {
+        self.crawler.body_error_paths = paths;
+        self
+    }
+
+    /// Report HTML pages that load with a 2xx but are error pages, as `soft_404` findings; see
+    /// [`Soft404Detector`]. Only pages that are crawled are checked, not other sites or resources.
+    pub fn soft_404(mut self, detector: Option<Soft404Detector>) -> Self {
+        self.crawler.soft_404 = detector.map(Arc::new);
+        self
+    }
+
+    /// Rules for treating different URLs as the same page; exact matches only by default.
+    pub fn url_normalizer(mut self, normalizer: UrlNormalizer) -> Self {
+        self.crawler.normalizer = normalizer;
+        self
+    }
+
+    /// Give up on a request (including reading its body) after this long.
+    pub fn request_timeout(mut self, request_timeout: Option<Duration>) -> Self {
+        self.crawler.request_timeout = request_timeout;
+        self
+    }
+
+    /// Space out the first requests of the crawl, ramping up to full speed; see [`SlowStart`].
+    pub fn slow_start(mut self, slow_start: Option<SlowStart>) -> Self {
+        self.crawler.slow_start = slow_start;
+        self
+    }
+
+    /// Minimum gap between the starts of requests to the same host, retries included. Requests
+    /// to other hosts aren't held up. Zero by default; see [`Crawler::pacer`] to set the gap for
+    /// one host.
+    pub fn request_delay(mut self, delay: Duration) -> Self {
+        self.crawler.pacer = Arc::new(HostPacer::new(delay));
+        self
+    }
+
+    /// Resolve the hosts of the starting URLs concurrently before crawling; see
+    /// [`crate::dns::prefetch`].
+    pub fn dns_prefetch(mut self, dns_prefetch: bool) -> Self {
+        self.crawler.dns_prefetch = dns_prefetch;
+        self
+    }
+
+    /// Crawl breadth-first, every page linked from the root URL before any page linked from
+    /// those, rather than depth-first, the default, which follows the last link found as deep
+    /// as it goes before the others. With `max_pages` or `max_errors`, this decides which pages
+    /// get checked: the shallow ones, usually the most visited, rather than one deep section.
+    /// Breadth-first keeps more links queued at once, so it uses more memory on large sites.
+    pub fn breadth_first(mut self, breadth_first: bool) -> Self {
+        self.crawler.breadth_first = breadth_first;
+        self
+    }
+
+    /// How many requests may be in flight at once. Defaults to 1; ignored with
+    /// `auto_concurrency`.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.crawler.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Halve `concurrency` while many recent responses are server errors, and restore it once
+    /// they subside; see [`ErrorBackoff`]. Ignored with `auto_concurrency`.
+    pub fn backoff_on_errors(mut self, backoff_on_errors: bool) -> Self {
+        self.crawler.backoff_on_errors = backoff_on_errors;
+        self
+    }
+
+    /// Never have more than `max_connections` requests in flight, each holding a connection
+    /// (and so a file descriptor) open, whatever the concurrency. The default client also keeps
+    /// at most a quarter as many idle connections per host, closed after
+    /// [`POOL_IDLE_TIMEOUT`], so connections left open between requests stay under the cap too.
+    /// `None` for no cap; see [`crate::connections`] for a default that fits the open file limit.
+    pub fn max_connections(mut self, max_connections: Option<usize>) -> Self {
+        self.crawler.connections = max_connections.map(|max| Arc::new(Semaphore::new(max.max(1))));
+        self.client_options.max_connections = max_connections;
+        self
+    }
+
+    /// Send every request through this proxy, an `http://`, `https://` or `socks5://` URL with
+    /// any credentials in it, instead of the ones the environment variables name.
+    pub fn proxy(mut self, proxy: Option<reqwest::Proxy>) -> Self {
+        self.client_options.proxy = proxy;
+        self
+    }
+
+    /// Accept any TLS certificate, even a self-signed or expired one or one for another host,
+    /// as on a staging site. Otherwise a certificate the client doesn't trust makes the link
+    /// broken, with a `tls error`.
+    pub fn accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.client_options.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
+    /// Cookie jar of the default client, to start the crawl with cookies already set, like a
+    /// session cookie from a browser. Cookies the site sets go into it too. Without one, the
+    /// client starts with an empty jar of its own.
+    pub fn cookie_jar(mut self, jar: Arc<Jar>) -> Self {
+        self.client_options.cookies = Some(jar);
+        self
+    }
+
+    /// Tune how many requests are in flight, between 1 and `max_concurrency`, from observed
+    /// latency and errors; see [`AimdController`]. Takes precedence over `concurrency`.
+    pub fn auto_concurrency(mut self, max_concurrency: Option<usize>) -> Self {
+        self.crawler.max_auto_concurrency = max_concurrency;
+        self
+    }
+
+    /// Also check links inside `<template>` elements, which the page may or may not render; see
+    /// [`crate::links::extract_template_links`].
+    pub fn scan_templates(mut self, scan_templates: bool) -> Self {
+        self.crawler.link_sources.templates = scan_templates;
+        self
+    }
+
+    /// Also check the URLs of HTMX `hx-get` attributes, and of `hx-post` ones unless
+    /// `safe_only` is set. Broken ones are reported as [`LinkKind::Htmx`].
+    pub fn htmx_links(mut self, htmx_links: bool) -> Self {
+        self.crawler.link_sources.htmx = htmx_links;
+        self
+    }
+
+    /// Don't check `<a rel="nofollow">` links; they're skipped as [`SkipReason::Nofollow`].
+    pub fn respect_nofollow(mut self, respect_nofollow: bool) -> Self {
+        self.crawler.link_sources.respect_nofollow = respect_nofollow;
+        self
+    }
+
+    /// Check that the `#fragment` of each in-scope link is the `id` of an element (or the
+    /// `name` of an `<a>`) on the page it points to. Each page is parsed once, however many
+    /// links point into it. Once the crawl is done, a missing fragment is reported as a broken
+    /// link of kind [`LinkKind::Fragment`], with status 200.
+    pub fn check_fragments(mut self, check_fragments: bool) -> Self {
+        self.crawler.link_sources.anchors = check_fragments;
+        self
+    }
+
+    /// `Accept` header to send with every request; by default reqwest's `*/*`. Ignored by the
+    /// 403 retry with browser headers, which sends a browser's own.
+    pub fn accept(mut self, accept: Option<AcceptHeader>) -> Self {
+        self.crawler.accept = accept;
+        self
+    }
+
+    /// Headers to send with every request, e.g. the one a staging site's gate checks, or an
+    /// `Authorization` from [`basic_auth`]. They're set last, so they replace the user agent,
+    /// `Accept` and browser headers of the same name, and apply to a custom client too. Mark
+    /// credentials sensitive with [`HeaderValue::set_sensitive`] to keep them out of debug logs.
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.crawler.headers = headers;
+        self
+    }
+
+    /// Also start crawling from these URLs, e.g. the paths in an access log (see
+    /// [`crate::access_log`]). Like the root URL, they're reported without a referrer if broken.
+    /// Seeds outside the crawl's scope are checked, not crawled.
+    pub fn seed_urls(mut self, seed_urls: Vec<Url>) -> Self {
+        self.crawler.seed_urls = seed_urls;
+        self
+    }
+
+    /// Treat a page whose `<link rel="canonical">` names another URL on the site as a duplicate
+    /// of it: its links aren't followed, and the canonical URL is queued in their place, to be
+    /// crawled once like any other link. When the duplicate is the canonical URL with a query
+    /// string, as with tracking parameters or print versions, every other query string on it
+    /// is taken for a duplicate too and isn't fetched, so it's reported as
+    /// [`SkipReason::CanonicalAlias`] with `report_skipped`.
+    pub fn follow_canonical(mut self, follow_canonical: bool) -> Self {
+        self.crawler.follow_canonical = follow_canonical;
+        self
+    }
+
+    /// Also start crawling from the URLs in the site's `/sitemap.xml`, following sitemap index
+    /// files and gunzipping gzipped sitemaps (see [`Sitemap`]). They're treated like seeds,
+    /// except that they're reported as linked from the sitemap that lists them if broken, since
+    /// that's where the stale entry needs fixing. A missing or unreadable sitemap is only logged.
+    pub fn use_sitemap(mut self, use_sitemap: bool) -> Self {
+        self.crawler.use_sitemap = use_sitemap;
+        self
+    }
+
+    /// Only fetch the root URL and seeds, and send a [`Finding::WouldFetch`] for every link on
+    /// them the crawl would fetch, instead of fetching it. Links are filtered as usual, so with
+    /// `report_skipped` this shows what the scope and filters let through and what they don't.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.crawler.dry_run = dry_run;
+        self
+    }
+
+    /// Warn about HTML pages whose body is shorter than this many bytes, as
+    /// [`WarningCategory::ThinContent`].
+    pub fn min_content_length(mut self, min_content_length: Option<usize>) -> Self {
+        self.crawler.min_content_length = min_content_length;
+        self
+    }
+
+    /// Warn about pages that took longer than this to load, from sending the request to the
+    /// end of the body, as [`WarningCategory::SlowPage`]. Only pages that are crawled count, not
+    /// other sites or resources, and retries aren't added up: the attempt that loaded is timed.
+    pub fn report_slow(mut self, threshold: Option<Duration>) -> Self {
+        self.crawler.slow_page_threshold = threshold;
+        self
+    }
+
+    /// Stop downloading a response body once it's over this many bytes, decompressed, so one
+    /// huge page can't take up all the memory. The page isn't parsed and is reported as
+    /// [`WarningCategory::Oversized`]; a link that's only checked, to another site or a resource,
+    /// still works. Defaults to [`DEFAULT_MAX_BODY_BYTES`]; `None` for no limit.
+    pub fn max_body_bytes(mut self, max_body_bytes: Option<u64>) -> Self {
+        self.crawler.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    /// Read pages from this directory instead of requesting them, as a static file server rooted
+    /// there would serve them; see [`crate::local_dir::file_for_url`]. Missing files are reported
+    /// as broken links, and relative links resolve against the page they're on.
+    pub fn local_dir(mut self, local_dir: Option<PathBuf>) -> Self {
+        self.crawler.local_dir = local_dir;
+        self
+    }
+
+    /// Ask for each page found in `cache` only if it changed since, and reuse its links on a
+    /// `304 Not Modified`. Pages loaded with an `ETag` or `Last-Modified` are recorded in it, for
+    /// the caller to [`PageCache::save`] after the crawl. Links to other sites and resources are
+    /// checked as usual.
+    pub fn page_cache(mut self, cache: Option<Arc<PageCache>>) -> Self {
+        self.crawler.page_cache = cache;
+        self
+    }
+
+    pub fn build(mut self) -> Crawler {
+        if !self.crawler.custom_client {
+            self.crawler.client = default_client(self.client_options);
+        }
+        self.crawler
+    }
+}
+
+impl Crawler {
+    pub fn builder(root_url: Url) -> CrawlerBuilder {
+        CrawlerBuilder {
+            crawler: Crawler {
+                root_url,
+                fuzzy_match_string: None,
+                include_subdomains: false,
+                scope: Vec::new(),
+                should_crawl: None,
+                on_broken_link: None,
+                path_prefix: None,
+                check_out_of_scope: false,
+                url_filter: UrlPatternFilter::default(),
+                user_agents: UserAgentPicker::new(Vec::new(), false),
+                suggest_fixes: false,
+                parse_timeout: Duration::from_millis(5000),
+                // Replaced in `build`, once the settings of the default client are known
+                client: reqwest::Client::new(),
+                custom_client: false,
+                max_bytes: None,
+                max_pages: None,
+                bytes_downloaded: Arc::new(AtomicU64::new(0)),
+                retry_policy: RetryPolicy::default(),
+                cancel: CancellationToken::new(),
+                #[cfg(feature = "pdf")]
+                check_pdf_links: false,
+                report_checked: false,
+                report_skipped: false,
+                respect_robots_header: false,
+                respect_robots_meta: false,
+                flag_redirects: false,
+                follow_link_header: false,
+                follow_canonical: false,
+                unsafe_urls: None,
+                dns_prefetch: false,
+                breadth_first: false,
+                ua_fallback: true,
+                only_404: false,
+                check_external: false,
+                max_distinct_hosts: None,
+                external_concurrency: None,
+                external_delay: Duration::ZERO,
+                max_errors: None,
+                ignore_broken: IgnoreList::default(),
+                json_url_paths: None,
+                body_error_paths: None,
+                soft_404: None,
+                error_page: OnceLock::new(),
+                normalizer: UrlNormalizer::default(),
+                request_timeout: None,
+                slow_start: None,
+                pacer: Arc::new(HostPacer::default()),
+                concurrency: 1,
+                backoff_on_errors: false,
+                max_auto_concurrency: None,
+                effective_concurrency: Arc::new(AtomicUsize::new(1)),
+                progress: Arc::new(CrawlProgress::default()),
+                local_dir: None,
+                page_cache: None,
+                link_sources: LinkSources::default(),
+                min_content_length: None,
+                slow_page_threshold: None,
+                max_body_bytes: Some(DEFAULT_MAX_BODY_BYTES),
+                accept: None,
+                headers: HeaderMap::new(),
+                seed_urls: Vec::new(),
+                use_sitemap: false,
+                dry_run: false,
+                connections: None,
+            },
+            client_options: ClientOptions::default(),
+        }
+    }
+
+    /// Shared counter of body bytes downloaded so far, readable while the crawl runs.
+    pub fn bytes_downloaded(&self) -> Arc<AtomicU64> {
+        self.bytes_downloaded.clone()
+    }
+
+    /// Shared view of how many requests may currently be in flight, readable while the crawl runs.
+    pub fn effective_concurrency(&self) -> Arc<AtomicUsize> {
+        self.effective_concurrency.clone()
+    }
+
+    /// Shared counters of pages crawled, queue size and broken links, readable while the crawl
+    /// runs.
+    pub fn progress(&self) -> Arc<CrawlProgress> {
+        self.progress.clone()
+    }
+
+    /// Per-host request gaps, shared with the crawl: a gap set here for a host, say from the
+    /// `Crawl-delay` in its robots.txt, applies to the requests to it from then on.
+    pub fn pacer(&self) -> Arc<HostPacer> {
+        self.pacer.clone()
+    }
+
+    /// Token that stops the crawl when cancelled.
+    ///
+    /// Cancellation is checked between pages: requests in flight are allowed to finish, then
+    /// the completion signal is sent as usual. Callers that need a hard bound on shutdown time
+    /// should also abort the crawl task after a grace period.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Crawls until every in-scope page has been visited, then sends [`CrawlMessage::Complete`].
+    pub async fn run(self, tx: DebugSender<CrawlMessage>) -> Result<(), anyhow::Error> {
+        Arc::new(self).crawl_and_collect_404s(tx).await
+    }
+
+    /// Crawls like [`Crawler::run`] and returns the broken links, one per URL with every page
+    /// linking to it, as the CLI saves them. Other findings, like warnings, are dropped; use
+    /// `run` to get those too.
+    pub async fn broken_links(self) -> Result<Vec<NotFoundError>, anyhow::Error> {
+        let mut channel = DebugChannel::new(100);
+        let sender = channel.sender();
+        let collect = async {
+            let mut errors = Vec::new();
+            while let Some(CrawlMessage::Finding(finding)) = channel.recv().await {
+                add_finding(&mut errors, finding);
+            }
+            Ok(errors)
+        };
+        let ((), errors) = tokio::try_join!(self.run(sender), collect)?;
+        Ok(errors)
+    }
+
+    /// Fetches a single URL the way the crawl would (user agent, retries, 403 fallback) without
+    /// following any of its links.
+    ///
+    /// An HTTP error status is an `Ok` result with that status; `Err` means no response was
+    /// received at all (DNS failure, refused connection, timeout, ...).
+    pub async fn check(&self, url: &Url) -> Result<CheckedUrl, reqwest::Error> {
+        self.recheck(url).await.map(|(checked, _)| checked)
+    }
+
+    /// Like [`Crawler::check`], also returning the error in the response body at
+    /// `body_error_paths`, or why it's a soft 404, if any: re-checking a broken link has to find
+    /// every way it can be broken.
+    pub async fn recheck(&self, url: &Url) -> Result<(CheckedUrl, Option<String>), reqwest::Error> {
+        let started = Instant::now();
+        let result = self
+            .fetch_with_retries(&Method::GET, url.as_str(), self.user_agents.pick(url))
+            .await;
+        let (status, content_type, error) = match result {
+            Ok(page) => {
+                let error = match self.body_error(url.as_str(), &page) {
+                    Some(error) => Some(error),
+                    None => self.soft_404(url.as_str(), &page).await,
+                };
+                (page.status, page.content_type, error)
+            }
+            Err(e) => match e.status() {
+                Some(status) => (status.as_u16(), None, None),
+                None => return Err(e.error),
+            },
+        };
+        let checked = CheckedUrl {
+            url: url.to_string(),
+            status: Some(status),
+            elapsed: started.elapsed(),
+            content_type,
+            referrer: None,
+            checked_at: SystemTime::now(),
+        };
+        Ok((checked, error))
+    }
+
+    /// Checks a link to another site with a `HEAD` request, falling back to `GET` when the
+    /// server doesn't answer `HEAD`.
+    async fn fetch_external(&self, url: &str, user_agent: &str) -> Result<FetchedPage, HttpError> {
+        match self
+            .fetch_with_retries(&Method::HEAD, url, user_agent)
+            .await
+        {
+            Err(e)
+                if matches!(
+                    e.status(),
+                    Some(StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED)
+                ) =>
+            {
+                log::debug!("{} doesn't allow HEAD ({}), retrying with GET", url, e);
+                self.fetch_with_retries(&Method::GET, url, user_agent).await
+            }
+            result => result,
+        }
+    }
+
+    /// `fetch_html`, retrying error statuses and network failures as many times as the retry
+    /// policy allows, then once more with browser headers on a 403 if `ua_fallback` is on.
+    async fn fetch_with_retries(
+        &self,
+        method: &Method,
+        url: &str,
+        user_agent: &str,
+    ) -> Result<FetchedPage, HttpError> {
+        self.fetch_with_headers(method, url, user_agent, &HeaderMap::new())
+            .await
+    }
+
+    /// `fetch_with_retries`, sending `headers` with every request.
+    async fn fetch_with_headers(
+        &self,
+        method: &Method,
+        url: &str,
+        user_agent: &str,
+        headers: &HeaderMap,
+    ) -> Result<FetchedPage, HttpError> {
+        match self
+            .fetch_with_retry_policy(method, url, user_agent, Some(headers))
+            .await
+        {
+            Err(e) if self.ua_fallback && e.status() == Some(StatusCode::FORBIDDEN) => {
+                log::info!("{} returned 403, retrying with browser headers", url);
+                let mut browser = browser_headers();
+                browser.extend(headers.clone());
+                let result = self
+                    .fetch_with_retry_policy(method, url, DEFAULT_USER_AGENT, Some(&browser))
+                    .await;
+                if result.is_ok() {
+                    log::info!(
+                        "{} loaded with browser headers, the 403 was likely a firewall block",
+                        url
+                    );
+                }
+                result
+            }
+            result => result,
+        }
+    }
+
+    async fn fetch_with_retry_policy(
+        &self,
+        method: &Method,
+        url: &str,
+        user_agent: &str,
+        headers: Option<&HeaderMap>,
+    ) -> Result<FetchedPage, HttpError> {
+        let parsed = Url::parse(url).ok();
+        let accept = match (&self.accept, &parsed) {
+            (Some(accept), Some(parsed)) => Some(accept.for_url(parsed)),
+            _ => None,
+        };
+        let host = parsed.as_ref().and_then(|parsed| parsed.host_str());
+        let mut attempt = 0;
+        loop {
+            if let Some(host) = host {
+                self.pacer.wait(host).await;
+            }
+            let result = {
+                // Held until the body has been read, and not while waiting to retry
+                let _permit = match &self.connections {
+                    Some(connections) => Some(connections.acquire().await.expect("never closed")),
+                    None => None,
+                };
+                fetch_html(
+                    &self.client,
+                    method,
+                    url,
+                    (!self.custom_client).then_some(user_agent),
+                    accept.clone(),
+                    headers,
+                    &self.headers,
+                    self.request_timeout,
+                    &self.bytes_downloaded,
+                    self.max_body_bytes,
+                    |page| self.wants_body(page),
+                )
+                .await
+            };
+            match result {
+                Err(e)
+                    if match e.status() {
+                        Some(status) => attempt < self.retry_policy.retries_for(status.as_u16()),
+                        // A certificate is no better the second time
+                        None => {
+                            e.is_transient()
+                                && !e.is_tls()
+                                && attempt < self.retry_policy.retries_without_response()
+                        }
+                    } =>
+                {
+                    let delay = self.retry_policy.backoff(attempt);
+                    log::info!("{} ({}), retrying in {:?}", url, e, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    #[cfg(feature = "pdf")]
+    fn reads_pdfs(&self) -> bool {
+        self.check_pdf_links
+    }
+
+    #[cfg(not(feature = "pdf"))]
+    fn reads_pdfs(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "pdf")]
+    fn find_pdf_links(&self, url: &str, pdf: &[u8]) -> Vec<String> {
+        if !self.check_pdf_links {
+            return Vec::new();
+        }
+        crate::pdf::find_pdf_links(pdf).unwrap_or_else(|e| {
+            log::warn!("Failed to read links from PDF {}: {}", url, e);
+            Vec::new()
+        })
+    }
+
+    #[cfg(not(feature = "pdf"))]
+    fn find_pdf_links(&self, _url: &str, _pdf: &[u8]) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Fetches one URL and extracts its links, in the URL's `page` span. Runs as its own task so
+    /// several can be in flight. An `external` link or a `resource` a page loads is only
+    /// checked, not parsed.
+    async fn fetch_page(
+        self: Arc<Self>,
+        url: String,
+        referrer: Option<String>,
+        depth: u32,
+        external: bool,
+        resource: bool,
+        span: tracing::Span,
+    ) -> PageOutcome {
+        let user_agent = match Url::parse(&url) {
+            Ok(parsed) => self.user_agents.pick(&parsed),
+            Err(_) => DEFAULT_USER_AGENT,
+        };
+        let cached = match &self.page_cache {
+            Some(cache) if !external && !resource && self.local_dir.is_none() => cache
+                .get(&self.normalizer.dedup_key(&url))
+                .filter(|cached| cached.sources == self.html_link_sources()),
+            _ => None,
+        };
+        let started = Instant::now();
+        let fetch = async {
+            match &self.local_dir {
+                _ if external => self
+                    .fetch_external(&url, user_agent)
+                    .await
+                    .map_err(FetchError::Http),
+                Some(dir) => read_local_file(dir, &url, &self.bytes_downloaded).await,
+                None if resource => self
+                    .fetch_external(&url, user_agent)
+                    .await
+                    .map_err(FetchError::Http),
+                None => self
+                    .fetch_if_changed(&url, user_agent, cached.as_ref())
+                    .await
+                    .map_err(FetchError::Http),
+            }
+        };
+        let mut result = fetch
+            .instrument(tracing::debug_span!(parent: &span, "fetch"))
+            .await;
+        let elapsed = started.elapsed();
+        span.record("elapsed_ms", elapsed.as_millis() as u64);
+        let unchanged = match (&mut result, cached) {
+            (Ok(page), Some(cached)) if page.status == StatusCode::NOT_MODIFIED.as_u16() => {
+                log::debug!("{} is unchanged, reusing its links", url);
+                Some(self.unchanged_page(&url, page, cached))
+            }
+            _ => None,
+        };
+        // An unchanged page was checked for errors when it was cached
+        let fresh = unchanged.is_none();
+        if let (true, Ok(page)) = (fresh, &result) {
+            if let Some(message) = unfollowed_redirect(page.status) {
+                log::debug!("{} returned {}: {}", url, page.status, message);
+                result = Err(FetchError::Redirect {
+                    status: page.status,
+                    message,
+                });
+            }
+        }
+        if let (
+            false,
+            false,
+            true,
+            Ok(
+                page @ FetchedPage {
+                    oversized: false, ..
+                },
+            ),
+        ) = (external, resource, fresh, &result)
+        {
+            if let Some(message) = self.body_error(&url, page) {
+                log::debug!(
+                    "{} returned {} with an error: {}",
+                    url,
+                    page.status,
+                    message
+                );
+                result = Err(FetchError::Body {
+                    status: page.status,
+                    message,
+                });
+            }
+        }
+        if let (
+            false,
+            false,
+            true,
+            Ok(
+                page @ FetchedPage {
+                    oversized: false, ..
+                },
+            ),
+        ) = (external, resource, fresh, &result)
+        {
+            if let Some(message) = self.soft_404(&url, page).await {
+                log::debug!("{} returned {}, but {}", url, page.status, message);
+                result = Err(FetchError::Soft404 {
+                    status: page.status,
+                    message,
+                });
+            }
+        }
+        let links = match &mut result {
+            Ok(page) if !external && !resource => {
+                self.links_on_page(&url, page, unchanged)
+                    .instrument(tracing::debug_span!(parent: &span, "parse"))
+                    .await
+            }
+            _ => Some(ExtractedLinks::default()),
+        };
+        if let Some(extracted) = &links {
+            span.record("links", extracted.links.len());
+        }
+        let outcome = PageOutcome {
+            url,
+            referrer,
+            depth,
+            external,
+            resource,
+            elapsed,
+            result,
+            links,
+            span,
+        };
+        outcome.span.record("status", outcome.status());
+        outcome
+    }
+
+    /// `fetch_with_retries` for a crawled page, asking for it only if it changed since `cached`
+    /// was loaded.
+    async fn fetch_if_changed(
+        &self,
+        url: &str,
+        user_agent: &str,
+        cached: Option<&CachedPage>,
+    ) -> Result<FetchedPage, HttpError> {
+        let mut headers = HeaderMap::new();
+        let validators = cached.into_iter().flat_map(|cached| {
+            [
+                (reqwest::header::IF_NONE_MATCH, &cached.etag),
+                (reqwest::header::IF_MODIFIED_SINCE, &cached.last_modified),
+            ]
+        });
+        for (name, value) in validators {
+            if let Some(value) = value.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(name, value);
+            }
+        }
+        self.fetch_with_headers(&Method::GET, url, user_agent, &headers)
+            .await
+    }
+
+    /// Turns the `304 Not Modified` answer for `url` into the page `cached` stands for, keeping
+    /// it in the page cache, and returns the links found on it.
+    fn unchanged_page(
+        &self,
+        url: &str,
+        page: &mut FetchedPage,
+        cached: CachedPage,
+    ) -> ExtractedLinks {
+        *page = FetchedPage {
+            status: cached.status,
+            content_type: cached.content_type.clone(),
+            robots: cached.robots,
+            link_header: cached.link_header.clone(),
+            body: Vec::new(),
+            body_len: cached.body_len,
+            redirected_to: cached.redirected_to.clone(),
+            elapsed: page.elapsed,
+            oversized: false,
+            // A 304 may come with new validators for the same page
+            etag: page.etag.take().or_else(|| cached.etag.clone()),
+            last_modified: page
+                .last_modified
+                .take()
+                .or_else(|| cached.last_modified.clone()),
+        };
+        let links = cached.links.clone();
+        if let Some(cache) = &self.page_cache {
+            cache.insert(
+                self.normalizer.dedup_key(url),
+                CachedPage {
+                    etag: page.etag.clone(),
+                    last_modified: page.last_modified.clone(),
+                    ..cached
+                },
+            );
+        }
+        links
+    }
+
+    /// Records `page`, loaded from `url`, and the links found on it in the page cache, if there
+    /// is one and the response came with validators to ask for the page again with.
+    fn cache_page(&self, url: &str, page: &FetchedPage, links: &ExtractedLinks) {
+        let Some(cache) = &self.page_cache else {
+            return;
+        };
+        if page.etag.is_none() && page.last_modified.is_none() {
+            return;
+        }
+        cache.insert(
+            self.normalizer.dedup_key(url),
+            CachedPage {
+                etag: page.etag.clone(),
+                last_modified: page.last_modified.clone(),
+                status: page.status,
+                content_type: page.content_type.clone(),
+                robots: page.robots,
+                link_header: page.link_header.clone(),
+                body_len: page.body_len,
+                redirected_to: page.redirected_to.clone(),
+                sources: self.html_link_sources(),
+                links: links.clone(),
+            },
+        );
+    }
+
+    /// What's extracted from HTML pages.
+    fn html_link_sources(&self) -> LinkSources {
+        LinkSources {
+            // A POST endpoint could trigger an action, like the links --safe-only avoids
+            htmx_post: self.link_sources.htmx && self.unsafe_urls.is_none(),
+            ..self.link_sources
+        }
+    }
+
+    /// Links to follow from a fetched page; `None` if parsing timed out. `unchanged` holds the
+    /// links found on the page the last time, if the server said it's unchanged since.
+    async fn links_on_page(
+        self: &Arc<Self>,
+        url: &str,
+        page: &mut FetchedPage,
+        unchanged: Option<ExtractedLinks>,
+    ) -> Option<ExtractedLinks> {
+        if page.oversized {
+            return Some(ExtractedLinks::default());
+        }
+        let nofollow_header = self.respect_robots_header && page.robots.nofollow;
+        if nofollow_header {
+            log::info!(
+                "{} is marked nofollow by X-Robots-Tag, not following its links",
+                url
+            );
+            if !self.report_skipped {
+                return Some(ExtractedLinks::default());
+            }
+        }
+        // The body isn't needed once the links are out of it
+        let body = std::mem::take(&mut page.body);
+        let mut extracted = if let Some(links) = unchanged {
+            links
+        } else if page.is_pdf() || page.is_json() {
+            let (crawler, url, is_pdf) = (Arc::clone(self), url.to_string(), page.is_pdf());
+            let links = extract_blocking(move || {
+                if is_pdf {
+                    crawler.find_pdf_links(&url, &body)
+                } else {
+                    crawler.find_json_links(&url, &body)
+                }
+            })
+            .await;
+            ExtractedLinks {
+                links,
+                ..ExtractedLinks::default()
+            }
+        } else if !page.is_crawlable() {
+            log::debug!("{} is not HTML, not crawling it", url);
+            ExtractedLinks::default()
+        } else {
+            let html = String::from_utf8_lossy(&body).into_owned();
+            let links =
+                find_links_with_timeout(html, self.parse_timeout, self.html_link_sources()).await?;
+            self.cache_page(url, page, &links);
+            links
+        };
+        if self.follow_link_header {
+            extracted
+                .links
+                .extend(std::mem::take(&mut page.link_header));
+        }
+        // The meta tag is only known once the page is parsed
+        let nofollow_meta =
+            !nofollow_header && self.respect_robots_meta && extracted.robots.nofollow;
+        if nofollow_meta {
+            log::info!(
+                "{} is marked nofollow by <meta name=\"robots\">, not following its links",
+                url
+            );
+        }
+        if nofollow_header || nofollow_meta {
+            let links = std::mem::take(&mut extracted.links);
+            let htmx = std::mem::take(&mut extracted.htmx);
+            let resources = std::mem::take(&mut extracted.resources);
+            extracted.skipped.extend(
+                links
+                    .into_iter()
+                    .chain(htmx.into_iter().map(|(link, _)| link))
+                    .chain(resources.into_iter().map(|resource| resource.url))
+                    .map(|link| (link, SkipReason::Nofollow)),
+            );
+        }
+        Some(extracted)
+    }
+
+    /// Whether anything is done with `page`'s body: following its links, or looking for an error
+    /// in it.
+    fn wants_body(&self, page: &FetchedPage) -> bool {
+        if page.is_pdf() {
+            return self.reads_pdfs();
+        }
+        if page.is_json() {
+            return self.json_url_paths.is_some() || self.body_error_paths.is_some();
+        }
+        page.is_crawlable()
+    }
+
+    /// Why `page`, fetched from `url`, is a soft 404, if it is one and `soft_404` is on.
+    async fn soft_404(&self, url: &str, page: &FetchedPage) -> Option<String> {
+        let detector = self.soft_404.clone()?;
+        let error_page = self.error_page.get();
+        if !page.is_html() || matches!(error_page, Some(ErrorPage::Status)) {
+            return None;
+        }
+        let html = String::from_utf8_lossy(&page.body).into_owned();
+        let text = tokio::task::spawn_blocking(move || PageText::from_html(&html))
+            .await
+            .ok()?;
+        let pattern = detector.matching_pattern(&text)?;
+        match error_page {
+            Some(ErrorPage::Page {
+                text: error_text,
+                path: error_path,
+            }) => {
+                let path = Url::parse(url).ok()?.path().to_string();
+                text.looks_like(&path, error_text, error_path).then(|| {
+                    format!(
+                        "soft 404: looks like the site's error page and matches \"{}\"",
+                        pattern
+                    )
+                })
+            }
+            _ => Some(format!("soft 404: the page matches \"{}\"", pattern)),
+        }
+    }
+
+    /// Requests a page that can't exist, to see what the site's error page looks like, for
+    /// soft 404 detection with [`Soft404Detector::probe`].
+    async fn probe_error_page(&self) {
+        let path = format!("/find-broken-links-probe-{:016x}", rand::random::<u64>());
+        let Ok(url) = self.root_url.join(&path) else {
+            return;
+        };
+        log::info!("Probing {} to see what a missing page looks like", url);
+        let error_page = match self
+            .fetch_with_retries(&Method::GET, url.as_str(), self.user_agents.pick(&url))
+            .await
+        {
+            Ok(page) if page.is_html() => {
+                let html = String::from_utf8_lossy(&page.body).into_owned();
+                log::info!(
+                    "{} answers a missing page with {}; reporting pages that look like it",
+                    self.root_url,
+                    page.status
+                );
+                ErrorPage::Page {
+                    text: PageText::from_html(&html),
+                    path,
+                }
+            }
+            Ok(page) => {
+                log::warn!(
+                    "The soft 404 probe got {} ({}), checking pages against the patterns alone",
+                    page.status,
+                    page.content_type.as_deref().unwrap_or("no content type")
+                );
+                return;
+            }
+            Err(e) => match e.status() {
+                Some(status) => {
+                    log::info!(
+                        "{} answers a missing page with {}, so no page is a soft 404",
+                        self.root_url,
+                        status
+                    );
+                    ErrorPage::Status
+                }
+                None => {
+                    log::warn!(
+                        "The soft 404 probe failed ({}), checking pages against the patterns alone",
+                        e
+                    );
+                    return;
+                }
+            },
+        };
+        let _ = self.error_page.set(error_page);
+    }
+
+    /// The first non-null value at one of `body_error_paths` in a JSON response, as text.
+    fn body_error(&self, url: &str, page: &FetchedPage) -> Option<String> {
+        let paths = self.body_error_paths.as_ref()?;
+        if !page.is_json() {
+            return None;
+        }
+        let document: serde_json::Value = match serde_json::from_slice(&page.body) {
+            Ok(document) => document,
+            Err(e) => {
+                log::debug!("Not checking {} for a body error: {}", url, e);
+                return None;
+            }
+        };
+        paths
+            .iter()
+            .flat_map(|path| path.select(&document))
+            .find(|value| !value.is_null())
+            .map(|value| match value {
+                serde_json::Value::String(message) => message.clone(),
+                other => other.to_string(),
+            })
+    }
+
+    fn find_json_links(&self, url: &str, body: &[u8]) -> Vec<String> {
+        let Some(paths) = &self.json_url_paths else {
+            return Vec::new();
+        };
+        match serde_json::from_slice(body) {
+            Ok(document) => find_json_links(&document, paths),
+            Err(e) => {
+                log::warn!("Failed to parse JSON from {}: {}", url, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// The page URLs in the root URL's `/sitemap.xml` and the sitemaps it indexes, in order, each
+    /// with the sitemap listing it.
+    async fn sitemap_urls(&self) -> Vec<(Url, String)> {
+        let mut pending: Vec<Url> = self.root_url.join("/sitemap.xml").into_iter().collect();
+        let mut fetched = HashSet::new();
+        let mut urls = Vec::new();
+        while let Some(sitemap_url) = pending.pop() {
+            if !fetched.insert(sitemap_url.clone()) {
+                continue;
+            }
+            if fetched.len() > MAX_SITEMAPS {
+                log::warn!(
+                    "Not reading {} or the sitemaps after it: read {} already",
+                    sitemap_url,
+                    MAX_SITEMAPS
+                );
+                break;
+            }
+            let sitemap = match self.fetch_sitemap(&sitemap_url).await {
+                Ok(sitemap) => sitemap,
+                Err(e) => {
+                    log::warn!("Not using sitemap {}: {}", sitemap_url, e);
+                    continue;
+                }
+            };
+            let (locs, index) = match sitemap {
+                Sitemap::Urls(locs) => (locs, false),
+                Sitemap::Index(locs) => (locs, true),
+            };
+            log::info!(
+                "Read {} {} from {}",
+                locs.len(),
+                if index { "sitemaps" } else { "URLs" },
+                sitemap_url
+            );
+            let locs =
+                locs.into_iter()
+                    .filter_map(|loc| match make_absolute_url(&sitemap_url, &loc) {
+                        Ok(url) => Some(url),
+                        Err(e) => {
+                            log::warn!(
+                                "Skipping unparseable URL {:?} in {}: {}",
+                                loc,
+                                sitemap_url,
+                                e
+                            );
+                            None
+                        }
+                    });
+            if index {
+                // Popped in order
+                let mut locs: Vec<_> = locs.collect();
+                locs.reverse();
+                pending.extend(locs);
+            } else {
+                urls.extend(locs.map(|url| (url, sitemap_url.to_string())));
+            }
+        }
+        urls
+    }
+
+    /// Downloads and parses one sitemap, whatever its content type, without the retries or the
+    /// 403 fallback of the crawl: there's nothing to report if it can't be read.
+    async fn fetch_sitemap(&self, url: &Url) -> Result<Sitemap, String> {
+        if let Some(host) = url.host_str() {
+            self.pacer.wait(host).await;
+        }
+        let _permit = match &self.connections {
+            Some(connections) => Some(connections.acquire().await.expect("never closed")),
+            None => None,
+        };
+        let page = fetch_html(
+            &self.client,
+            &Method::GET,
+            url.as_str(),
+            (!self.custom_client).then_some(self.user_agents.pick(url)),
+            None,
+            None,
+            &self.headers,
+            self.request_timeout,
+            &self.bytes_downloaded,
+            self.max_body_bytes,
+            |_| true,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        if page.oversized {
+            return Err(format!("over the body size limit, {} bytes", page.body_len));
+        }
+        Sitemap::parse(&page.body)
+    }
+
+    /// Slows requests to `host`, another site, down to `external_delay`, unless they're paced
+    /// slower already.
+    fn pace_external_host(&self, host: &str) {
+        if self.external_delay > self.pacer.delay_for(host) {
+            self.pacer.set_delay(host, self.external_delay);
+        }
+    }
+
+    /// The canonical URL `url` is a duplicate of, if it has a query string and another query
+    /// string on the same URL was found to be a duplicate of it; see
+    /// [`CrawlerBuilder::follow_canonical`].
+    fn canonical_stem(&self, url: &Url, canonical_stems: &HashSet<String>) -> Option<Url> {
+        if canonical_stems.is_empty() || url.query().is_none() {
+            return None;
+        }
+        let stem = query_stem(url);
+        canonical_stems
+            .contains(&self.normalizer.dedup_key(stem.as_str()))
+            .then_some(stem)
+    }
+
+    /// Sends a [`Finding::NotFound`] through the channel, after passing it to `on_broken_link`.
+    async fn send_broken(&self, tx: &DebugSender<CrawlMessage>, finding: Finding) {
+        if let Some(on_broken_link) = &self.on_broken_link {
+            if let Some(error) = NotFoundError::from_finding(finding.clone()) {
+                on_broken_link(&error);
+            }
+        }
+        if let Err(send_err) = tx.send(CrawlMessage::Finding(finding)).await {
+            log::error!(
+                "Failed to send broken URL through the channel: {}",
+                send_err
+            );
+        }
+    }
+
+    /// Whether `url` is followed: it's on the root URL's domain or one matching the fuzzy match
+    /// string, or with explicit scope entries, matches one of them. `should_crawl` overrides both.
+    fn in_scope(&self, url: &Url) -> bool {
+        if let Some(should_crawl) = &self.should_crawl {
+            return should_crawl(url);
+        }
+        if !self.scope.is_empty() {
+            return self.scope.iter().any(|entry| entry.matches(url));
+        }
+        // The domain, unlike the authority, leaves out any `user:pass@`
+        let Some(domain) = url.domain() else {
+            return false;
+        };
+        let matches_exact = self.root_url.domain() == Some(domain);
+        let matches_subdomain = self.include_subdomains
+            && self
+                .root_url
+                .domain()
+                .is_some_and(|root| is_subdomain_of(domain, root));
+        let matches_fuzzy = self
+            .fuzzy_match_string
+            .as_ref()
+            .is_some_and(|fuzzy_match_string| fuzzy_matches(domain, fuzzy_match_string));
+        matches_exact || matches_subdomain || matches_fuzzy
+    }
+
+    /// Whether `url`'s path is under `path_prefix`, on a `/` boundary; always true without one.
+    fn in_path_prefix(&self, url: &Url) -> bool {
+        let Some(prefix) = &self.path_prefix else {
+            return true;
+        };
+        url.path()
+            .strip_prefix(prefix.trim_end_matches('/'))
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    async fn crawl_and_collect_404s(
+        self: Arc<Self>,
+        tx: DebugSender<CrawlMessage>,
+    ) -> Result<(), anyhow::Error> {
+        let root_url = &self.root_url;
+        let parse_timeout = self.parse_timeout;
+        log::info!("crawling and collecting 404s");
+        // With explicit scope entries, the root may be an IP address
+        if root_url.domain().is_none() && self.scope.is_empty() {
+            anyhow::bail!("Root URL has no domain");
+        }
+        if self
+            .soft_404
+            .as_ref()
+            .is_some_and(|detector| detector.probes())
+        {
+            self.probe_error_page().await;
+        }
+        let sitemap_urls = if self.use_sitemap {
+            self.sitemap_urls().await
+        } else {
+            Vec::new()
+        };
+        // Each seed paired with the sitemap listing it, if it came from one
+        let seeds: Vec<(&Url, Option<&str>)> = self
+            .seed_urls
+            .iter()
+            .map(|seed| (seed, None))
+            .chain(
+                sitemap_urls
+                    .iter()
+                    // The sitemap lists the whole site, not just the part under `path_prefix`
+                    .filter(|(url, _)| !self.in_scope(url) || self.in_path_prefix(url))
+                    .map(|(url, sitemap)| (url, Some(sitemap.as_str()))),
+            )
+            .collect();
+        // Each queued URL is paired with the page that linked to it and its depth
+        let mut to_visit = Frontier::new(self.breadth_first);
+        // Dedup keys of the URLs waiting in `to_visit` or being fetched, so a page linked from
+        // many others, or listed as a seed too, is queued once rather than once per link
+        let mut queued_keys = HashSet::new();
+        queued_keys.insert(self.normalizer.dedup_key(root_url.as_str()));
+        // Crawled in order, after the root URL
+        let mut starting_urls = vec![(root_url.to_string(), None, 0)];
+        for &(seed, sitemap) in &seeds {
+            let unsafe_pattern = self
+                .unsafe_urls
+                .as_ref()
+                .and_then(|filter| filter.matching_pattern(seed));
+            if let Some(pattern) = unsafe_pattern {
+                let warning = LinkWarning {
+                    url: seed.to_string(),
+                    category: WarningCategory::UnsafeLink,
+                    message: format!(
+                        "potentially unsafe seed URL not checked (matches '{}')",
+                        pattern
+                    ),
+                };
+                send_warning(&tx, warning).await;
+                continue;
+            }
+            if queued_keys.insert(self.normalizer.dedup_key(seed.as_str())) {
+                starting_urls.push((seed.to_string(), sitemap.map(str::to_string), 0));
+            }
+        }
+        to_visit.push_in_order(starting_urls);
+        // Dedup keys of every URL fetched or being fetched, so no page is requested twice
+        let mut visited = HashSet::new();
+        // Dedup key -> the pages linking to it besides the one it was queued from, for the links
+        // in `queued_keys`; sent along if it turns out to be broken
+        let mut other_referrers: HashMap<String, Vec<String>> = HashMap::new();
+        // Dedup key -> URL as reported, for every broken link
+        let mut broken_keys: HashMap<String, String> = HashMap::new();
+        // Dedup keys of the links to other sites queued with `check_external`, which are checked
+        // rather than crawled
+        let mut external_keys = HashSet::new();
+        // Dedup keys of the resources pages load, like images and stylesheets, which are also
+        // checked rather than crawled
+        let mut resource_keys = HashSet::new();
+        // Dedup keys of the links on the site outside `path_prefix`, checked rather than crawled
+        // with `check_out_of_scope`
+        let mut outside_prefix_keys = HashSet::new();
+        // Hosts of the links in `external_keys`, against `max_distinct_hosts`, and the hosts
+        // skipped because it was reached
+        let mut external_hosts = HashSet::new();
+        let mut capped_hosts = HashSet::new();
+        // Links to other sites waiting for a slot of their own, with `external_concurrency`, and
+        // how many of the requests in flight are to other sites
+        let mut external_to_visit = VecDeque::new();
+        let mut external_in_flight = 0;
+        // Seeds on other sites are only checked, like the links to them
+        for &(seed, _) in &seeds {
+            if !self.in_scope(seed) {
+                external_keys.insert(self.normalizer.dedup_key(seed.as_str()));
+                let host = seed.host_str().unwrap_or_default();
+                if external_hosts.insert(host.to_string()) {
+                    self.pace_external_host(host);
+                }
+            }
+        }
+        // Lowercased URL -> URL as fetched, for every page that loaded successfully. Only filled in
+        // with `suggest_fixes`, to spot 404s that differ from a good URL only by case.
+        let mut valid_by_lowercase = HashMap::new();
+        // Encoded URL -> (href as written, page it was found on) for links that had to be
+        // percent-encoded. Only filled in with `suggest_fixes`.
+        let mut unencoded_links: HashMap<String, (String, String)> = HashMap::new();
+        // URL -> method for URLs queued from HTMX attributes, to report them as such
+        let mut htmx_urls: HashMap<String, HtmxMethod> = HashMap::new();
+        // (dedup key of the page, link, page it's on) for in-scope links with a fragment, in the
+        // order found, and dedup key -> anchors for every HTML page that loaded. Only filled in
+        // with `check_fragments`, to check the former against the latter once the crawl is done.
+        let mut fragment_links: Vec<(String, String, String)> = Vec::new();
+        let mut page_anchors: HashMap<String, Vec<String>> = HashMap::new();
+        // Dedup keys of canonical URLs named by the same URL with a query string, with
+        // `follow_canonical`: any other query string on them is a duplicate too
+        let mut canonical_stems = HashSet::new();
+        let mut in_flight = JoinSet::new();
+        let mut limiter = match self.max_auto_concurrency {
+            Some(max) => ConcurrencyLimit::Auto(AimdController::new(1, max)),
+            None if self.backoff_on_errors => {
+                ConcurrencyLimit::Backoff(ErrorBackoff::new(self.concurrency))
+            }
+            None => ConcurrencyLimit::Fixed(self.concurrency),
+        };
+        self.effective_concurrency
+            .store(limiter.limit(), Ordering::Relaxed);
+        if let Some(connections) = &self.connections {
+            log::info!(
+                "Up to {} connections at once",
+                connections.available_permits()
+            );
+        }
+        // Set once cancelled or out of budget: let the requests in flight finish, start no more
+        let mut stopping = false;
+        let mut broken_found = 0;
+        let mut requests_started = 0;
+        let mut last_request_at: Option<Instant> = None;
+
+        if self.dns_prefetch {
+            let frontier: Vec<Url> = to_visit
+                .iter()
+                .filter_map(|(url, _, _)| Url::parse(url).ok())
+                .collect();
+            let resolved = crate::dns::prefetch(&frontier).await;
+            log::info!("DNS prefetch resolved {} hosts", resolved);
+        }
+
+        loop {
+            let limit = limiter.limit();
+            while !stopping {
+                // Links to other sites in their own queue have their own limit; the rest share
+                // the crawl's
+                let external_slot = self
+                    .external_concurrency
+                    .is_some_and(|max| external_in_flight < max);
+                let next = match external_to_visit.pop_front() {
+                    Some(next) if external_slot => Some(next),
+                    Some(next) => {
+                        external_to_visit.push_front(next);
+                        None
+                    }
+                    None => None,
+                };
+                let next = next.or_else(|| {
+                    (in_flight.len() - external_in_flight < limit)
+                        .then(|| to_visit.pop())
+                        .flatten()
+                });
+                let Some((url, referrer, depth)) = next else {
+                    break;
+                };
+                let key = self.normalizer.dedup_key(&url);
+                if visited.contains(&key) {
+                    continue;
+                }
+                // Queued before another query string on it turned out to be a duplicate
+                let alias_of = Url::parse(&url)
+                    .ok()
+                    .and_then(|url| self.canonical_stem(&url, &canonical_stems));
+                if let Some(canonical) = alias_of {
+                    queued_keys.remove(&key);
+                    other_referrers.remove(&key);
+                    log::info!("{} is a duplicate of {}, not fetching it", url, canonical);
+                    if let (true, Some(referrer)) = (self.report_skipped, &referrer) {
+                        send_skipped(&tx, root_url, url, referrer, SkipReason::CanonicalAlias)
+                            .await;
+                    }
+                    continue;
+                }
+                if self.cancel.is_cancelled() {
+                    log::info!(
+                        "Crawl cancelled, stopping with {} URLs left unvisited",
+                        to_visit.len() + external_to_visit.len() + 1
+                    );
+                    stopping = true;
+                    break;
+                }
+                if let Some(max_bytes) = self.max_bytes {
+                    let downloaded = self.bytes_downloaded.load(Ordering::Relaxed);
+                    if downloaded >= max_bytes {
+                        log::warn!(
+                            "Byte budget of {} exhausted ({} downloaded), stopping with {} URLs left unvisited",
+                            max_bytes,
+                            downloaded,
+                            to_visit.len() + external_to_visit.len() + 1
+                        );
+                        stopping = true;
+                        break;
+                    }
+                }
+                if let Some(max_pages) = self.max_pages {
+                    if requests_started >= max_pages {
+                        log::warn!(
+                            "Page budget of {} reached, stopping with {} URLs left unvisited",
+                            max_pages,
+                            to_visit.len() + external_to_visit.len() + 1
+                        );
+                        stopping = true;
+                        break;
+                    }
+                }
+                if let (Some(slow_start), Some(last_request_at)) =
+                    (&self.slow_start, last_request_at)
+                {
+                    let delay = slow_start.delay_before(requests_started, Duration::ZERO);
+                    tokio::select! {
+                        _ = tokio::time::sleep_until((last_request_at + delay).into()) => {},
+                        _ = self.cancel.cancelled() => {
+                            log::info!(
+                                "Crawl cancelled, stopping with {} URLs left unvisited",
+                                to_visit.len() + external_to_visit.len() + 1
+                            );
+                            stopping = true;
+                            break;
+                        },
+                    }
+                }
+                requests_started += 1;
+                last_request_at = Some(Instant::now());
+                let external = external_keys.contains(&key);
+                let resource = resource_keys.contains(&key);
+                let outside_prefix = outside_prefix_keys.contains(&key);
+                if external {
+                    log::info!("checking external {}", url);
+                } else if resource {
+                    log::info!("checking resource {}", url);
+                } else if outside_prefix {
+                    log::info!("checking {} outside the path prefix", url);
+                } else if limiter.is_adjustable() {
+                    log::info!("crawling {} (concurrency {})", url, limit);
+                } else {
+                    log::info!("crawling {}", url);
+                }
+                visited.insert(key);
+                if external && self.external_concurrency.is_some() {
+                    external_in_flight += 1;
+                }
+                let span = tracing::debug_span!(
+                    "page",
+                    %url,
+                    depth,
+                    status = Empty,
+                    elapsed_ms = Empty,
+                    links = Empty
+                );
+                in_flight.spawn(
+                    Arc::clone(&self)
+                        .fetch_page(
+                            url,
+                            referrer,
+                            depth,
+                            external,
+                            // Only checked, like a resource
+                            resource || outside_prefix,
+                            span.clone(),
+                        )
+                        .instrument(span),
+                );
+            }
+
+            self.progress
+                .set_queued(to_visit.len() + external_to_visit.len());
+            let Some(joined) = in_flight.join_next().await else {
+                break;
+            };
+            let outcome = joined?;
+            if outcome.external && self.external_concurrency.is_some() {
+                external_in_flight -= 1;
+            }
+            self.progress.record_page();
+            let status = outcome.status();
+            let previous = limiter.limit();
+            let limit = limiter.record(outcome.elapsed, status);
+            if limit != previous {
+                log::info!(
+                    "Concurrency {} -> {} (last response: {:?} in {:?})",
+                    previous,
+                    limit,
+                    status,
+                    outcome.elapsed
+                );
+                self.effective_concurrency.store(limit, Ordering::Relaxed);
+            }
+
+            let PageOutcome {
+                url,
+                referrer,
+                depth,
+                external,
+                resource,
+                elapsed,
+                result,
+                links,
+                span,
+            } = outcome;
+            let key = self.normalizer.dedup_key(&url);
+            queued_keys.remove(&key);
+            let also_linked_from = other_referrers.remove(&key).unwrap_or_default();
+            if self.report_checked {
+                let checked = CheckedUrl {
+                    url: url.clone(),
+                    status,
+                    elapsed,
+                    content_type: result
+                        .as_ref()
+                        .ok()
+                        .and_then(|page| page.content_type.clone()),
+                    referrer: referrer.clone(),
+                    checked_at: SystemTime::now(),
+                };
+                if let Err(send_err) = tx
+                    .send(CrawlMessage::Finding(Finding::Checked(checked)))
+                    .await
+                {
+                    log::error!(
+                        "Failed to send checked URL through the channel: {}",
+                        send_err
+                    );
+                }
+            }
+            if let (true, Ok(page)) = (self.flag_redirects, &result) {
+                if let Some(target) = &page.redirected_to {
+                    let message = match &referrer {
+                        Some(referrer) => {
+                            format!("redirects to {}, linked from {}", target, referrer)
+                        }
+                        None => format!("redirects to {}", target),
+                    };
+                    let warning = LinkWarning {
+                        url: url.clone(),
+                        category: WarningCategory::Redirect,
+                        message,
+                    };
+                    send_warning(&tx, warning).await;
+                }
+            }
+            match result {
+                // Nothing to follow on another site, or in an image or stylesheet
+                Ok(_) if external || resource => {}
+                Ok(page) => {
+                    let enqueue = tracing::debug_span!(parent: &span, "enqueue", queued = Empty);
+                    let mut queued = 0;
+                    async {
+                        if self.respect_robots_header && page.robots.noindex {
+                            let warning = LinkWarning {
+                                url: url.clone(),
+                                category: WarningCategory::Noindex,
+                                message: "page is marked noindex by X-Robots-Tag".to_string(),
+                            };
+                            send_warning(&tx, warning).await;
+                        }
+                        if let Some(min) = self.min_content_length {
+                            if page.is_html() && page.body_len < min {
+                                let warning = LinkWarning {
+                                    url: url.clone(),
+                                    category: WarningCategory::ThinContent,
+                                    message: format!(
+                                        "thin content: body is {} bytes, below the minimum of {}",
+                                        page.body_len, min
+                                    ),
+                                };
+                                send_warning(&tx, warning).await;
+                            }
+                        }
+                        if let (true, Some(max)) = (page.oversized, self.max_body_bytes) {
+                            let warning = LinkWarning {
+                                url: url.clone(),
+                                category: WarningCategory::Oversized,
+                                message: format!(
+                                    "too large: body is over the limit of {} bytes, its links weren't followed",
+                                    max
+                                ),
+                            };
+                            send_warning(&tx, warning).await;
+                        }
+                        if let Some(threshold) = self.slow_page_threshold {
+                            if page.elapsed > threshold {
+                                let warning = LinkWarning {
+                                    url: url.clone(),
+                                    category: WarningCategory::SlowPage,
+                                    message: format!(
+                                        "slow: took {}ms to load, over the threshold of {}ms",
+                                        page.elapsed.as_millis(),
+                                        threshold.as_millis()
+                                    ),
+                                };
+                                send_warning(&tx, warning).await;
+                            }
+                        }
+                        if self.suggest_fixes {
+                            valid_by_lowercase.insert(url.to_lowercase(), url.clone());
+                            if let Some((link, found_on)) = unencoded_links.get(&url) {
+                                let warning = LinkWarning {
+                                    url: url.clone(),
+                                    category: WarningCategory::NeedsUrlEncoding,
+                                    message: format!(
+                                        "needs URL encoding: '{}' on {} only works encoded, use '{}'",
+                                        link, found_on, url
+                                    ),
+                                };
+                                send_warning(&tx, warning).await;
+                            }
+                        }
+                        // TODO: save the url
+                        let timed_out = links.is_none();
+                        let ExtractedLinks {
+                            links,
+                            skipped,
+                            htmx,
+                            resources,
+                            base,
+                            canonical,
+                            anchors,
+                            robots,
+                        } = match links {
+                            Some(extracted) => extracted,
+                            None => {
+                                let warning = LinkWarning {
+                                    url: url.clone(),
+                                    category: WarningCategory::Unparseable,
+                                    message: format!(
+                                        "unparseable (too large): parsing took over {}ms",
+                                        parse_timeout.as_millis()
+                                    ),
+                                };
+                                send_warning(&tx, warning).await;
+                                ExtractedLinks::default()
+                            }
+                        };
+                        if self.respect_robots_meta && robots.noindex {
+                            let warning = LinkWarning {
+                                url: url.clone(),
+                                category: WarningCategory::Noindex,
+                                message: "page is marked noindex by <meta name=\"robots\">"
+                                    .to_string(),
+                            };
+                            send_warning(&tx, warning).await;
+                        }
+                        if self.link_sources.anchors && page.is_crawlable() && !timed_out {
+                            page_anchors.insert(key.clone(), anchors);
+                        }
+                        if self.report_skipped {
+                            for (link, reason) in skipped {
+                                send_skipped(&tx, root_url, link, &url, reason).await;
+                            }
+                        }
+                        let page_url = Url::parse(&url);
+                        let page_is_https = page_url.as_ref().is_ok_and(|p| p.scheme() == "https");
+                        // Relative links resolve against the page they're on, or its `<base href>`
+                        let page_base = page_url.as_ref().ok().map(|page_url| {
+                            base.as_deref()
+                                .and_then(|base| make_absolute_url(page_url, base).ok())
+                                .filter(|base| !base.cannot_be_a_base())
+                                .unwrap_or_else(|| page_url.clone())
+                        });
+                        let base_url = page_base.as_ref().unwrap_or(root_url);
+                        // A duplicate has the same links as its canonical URL, which is queued
+                        // instead, to follow them from there
+                        let canonical = canonical
+                            .filter(|_| self.follow_canonical)
+                            .and_then(|canonical| make_absolute_url(base_url, &canonical).ok())
+                            .filter(|canonical| {
+                                self.in_scope(canonical)
+                                    && self.in_path_prefix(canonical)
+                                    && self.normalizer.dedup_key(canonical.as_str()) != key
+                            });
+                        let (links, htmx, resources) = match (canonical, &page_url) {
+                            (Some(canonical), Ok(page_url)) => {
+                                log::info!(
+                                    "{} is a duplicate of {}, following its links there",
+                                    url,
+                                    canonical
+                                );
+                                let stem = query_stem(page_url);
+                                if page_url.query().is_some()
+                                    && self.normalizer.dedup_key(stem.as_str())
+                                        == self.normalizer.dedup_key(canonical.as_str())
+                                {
+                                    canonical_stems.insert(self.normalizer.dedup_key(stem.as_str()));
+                                }
+                                (vec![canonical.to_string()], Vec::new(), Vec::new())
+                            }
+                            _ => (links, htmx, resources),
+                        };
+                        let links = links
+                            .into_iter()
+                            .map(|link| (link, None, false))
+                            .chain(
+                                htmx.into_iter()
+                                    .map(|(link, method)| (link, Some(method), false)),
+                            )
+                            .chain(
+                                resources
+                                    .into_iter()
+                                    .map(|resource| (resource.url, None, true)),
+                            );
+                        for (i, (link, htmx_method, resource)) in links.enumerate() {
+                            if i > 0 && i % LINKS_PER_YIELD == 0 {
+                                tokio::task::yield_now().await;
+                            }
+                            let mut absolute_link = match make_absolute_url(base_url, &link) {
+                                Ok(absolute_link) => absolute_link,
+                                Err(e) => {
+                                    log::warn!(
+                                        "Skipping unparseable link {:?} on {}: {}",
+                                        link,
+                                        url,
+                                        e
+                                    );
+                                    if self.report_skipped {
+                                        send_skipped(
+                                            &tx,
+                                            root_url,
+                                            link,
+                                            &url,
+                                            SkipReason::InvalidUrl,
+                                        )
+                                        .await;
+                                    }
+                                    continue;
+                                }
+                            };
+                            if let Some(rewritten) = self.normalizer.rewrite(&absolute_link) {
+                                let warning = LinkWarning {
+                                    url: absolute_link.to_string(),
+                                    category: WarningCategory::DoubleSlash,
+                                    message: format!(
+                                        "double slash in path on {}, checked as {}",
+                                        url, rewritten
+                                    ),
+                                };
+                                send_warning(&tx, warning).await;
+                                absolute_link = rewritten;
+                            }
+                            if self.suggest_fixes && needs_percent_encoding(&link) {
+                                unencoded_links
+                                    .entry(absolute_link.to_string())
+                                    .or_insert_with(|| (link.clone(), url.clone()));
+                            }
+                            if page_is_https && absolute_link.scheme() == "http" {
+                                let warning = LinkWarning {
+                                    url: absolute_link.to_string(),
+                                    category: WarningCategory::InsecureScheme,
+                                    message: format!("insecure http:// link on {}", url),
+                                };
+                                send_warning(&tx, warning).await;
+                            }
+                            if !absolute_link.username().is_empty()
+                                || absolute_link.password().is_some()
+                            {
+                                // Don't repeat the password in the report
+                                let mut shown = absolute_link.clone();
+                                let _ = shown.set_password(None);
+                                let warning = LinkWarning {
+                                    url: shown.to_string(),
+                                    category: WarningCategory::EmbeddedCredentials,
+                                    message: format!("link with embedded credentials on {}", url),
+                                };
+                                send_warning(&tx, warning).await;
+                            }
+                            if self.scope.is_empty() && absolute_link.domain().is_none() {
+                                log::warn!("Link '{}' has no domain, skipping...", absolute_link);
+                                if self.report_skipped {
+                                    let absolute_link = absolute_link.to_string();
+                                    send_skipped(
+                                        &tx,
+                                        root_url,
+                                        absolute_link,
+                                        &url,
+                                        SkipReason::NoDomain,
+                                    )
+                                    .await;
+                                }
+                                continue;
+                            }
+                            let in_scope = self.in_scope(&absolute_link);
+                            let outside_prefix =
+                                in_scope && !resource && !self.in_path_prefix(&absolute_link);
+                            if outside_prefix && !self.check_out_of_scope {
+                                log::debug!("Skipping {}, outside the path prefix", absolute_link);
+                                if self.report_skipped {
+                                    let absolute_link = absolute_link.to_string();
+                                    send_skipped(
+                                        &tx,
+                                        root_url,
+                                        absolute_link,
+                                        &url,
+                                        SkipReason::OutsidePathPrefix,
+                                    )
+                                    .await;
+                                }
+                                continue;
+                            }
+                            if !in_scope && !self.check_external {
+                                if self.report_skipped {
+                                    let absolute_link = absolute_link.to_string();
+                                    send_skipped(
+                                        &tx,
+                                        root_url,
+                                        absolute_link,
+                                        &url,
+                                        SkipReason::OutOfDomain,
+                                    )
+                                    .await;
+                                }
+                            } else {
+                                let key = self.normalizer.dedup_key(absolute_link.as_str());
+                                if self.link_sources.anchors
+                                    && in_scope
+                                    && !resource
+                                    && absolute_link.fragment().is_some()
+                                {
+                                    let link = (key.clone(), absolute_link.to_string(), url.clone());
+                                    fragment_links.push(link);
+                                }
+                                if queued_keys.contains(&key) {
+                                    // Still an HTMX endpoint if an earlier page linked it plainly
+                                    if let Some(method) = htmx_method {
+                                        htmx_urls.entry(absolute_link.to_string()).or_insert(method);
+                                    }
+                                    // Crawled after all if a page links to it, not just loads it
+                                    if !resource {
+                                        resource_keys.remove(&key);
+                                    }
+                                    if outside_prefix {
+                                        outside_prefix_keys.insert(key.clone());
+                                    }
+                                    other_referrers.entry(key).or_default().push(url.clone());
+                                    continue;
+                                }
+                                if visited.contains(&key) {
+                                    if let Some(broken_url) = broken_keys.get(&key) {
+                                        send_also_linked_from(&tx, broken_url, &url).await;
+                                    }
+                                    continue;
+                                }
+                                let alias_of = (in_scope && !resource)
+                                    .then(|| self.canonical_stem(&absolute_link, &canonical_stems))
+                                    .flatten();
+                                if let Some(canonical) = alias_of {
+                                    log::debug!(
+                                        "Skipping {}, a duplicate of {}",
+                                        absolute_link,
+                                        canonical
+                                    );
+                                    if self.report_skipped {
+                                        let absolute_link = absolute_link.to_string();
+                                        send_skipped(
+                                            &tx,
+                                            root_url,
+                                            absolute_link,
+                                            &url,
+                                            SkipReason::CanonicalAlias,
+                                        )
+                                        .await;
+                                    }
+                                    continue;
+                                }
+                                if !self.url_filter.allows(absolute_link.as_str()) {
+                                    log::debug!("Skipping excluded {}", absolute_link);
+                                    if self.report_skipped {
+                                        let absolute_link = absolute_link.to_string();
+                                        send_skipped(
+                                            &tx,
+                                            root_url,
+                                            absolute_link,
+                                            &url,
+                                            SkipReason::Excluded,
+                                        )
+                                        .await;
+                                    }
+                                    continue;
+                                }
+                                let unsafe_pattern = self
+                                    .unsafe_urls
+                                    .as_ref()
+                                    .and_then(|filter| filter.matching_pattern(&absolute_link));
+                                if let Some(pattern) = unsafe_pattern {
+                                    let absolute_link = absolute_link.to_string();
+                                    let warning = LinkWarning {
+                                        url: absolute_link.clone(),
+                                        category: WarningCategory::UnsafeLink,
+                                        message: format!(
+                                            "potentially unsafe link not checked (matches '{}') on {}",
+                                            pattern, url
+                                        ),
+                                    };
+                                    send_warning(&tx, warning).await;
+                                    if self.report_skipped {
+                                        send_skipped(
+                                            &tx,
+                                            root_url,
+                                            absolute_link,
+                                            &url,
+                                            SkipReason::Unsafe,
+                                        )
+                                        .await;
+                                    }
+                                    continue;
+                                }
+                                if !in_scope {
+                                    let host = absolute_link.host_str().unwrap_or_default();
+                                    let cap = self.max_distinct_hosts.filter(|&max| {
+                                        !external_hosts.contains(host)
+                                            && external_hosts.len() >= max
+                                    });
+                                    if let Some(max) = cap {
+                                        if capped_hosts.insert(host.to_string()) {
+                                            log::warn!(
+                                                "Skipping links to {}: --max-distinct-hosts {} reached",
+                                                host,
+                                                max
+                                            );
+                                        }
+                                        if self.report_skipped {
+                                            let absolute_link = absolute_link.to_string();
+                                            send_skipped(
+                                                &tx,
+                                                root_url,
+                                                absolute_link,
+                                                &url,
+                                                SkipReason::TooManyHosts,
+                                            )
+                                            .await;
+                                        }
+                                        continue;
+                                    }
+                                    if external_hosts.insert(host.to_string()) {
+                                        self.pace_external_host(host);
+                                    }
+                                    external_keys.insert(key.clone());
+                                }
+                                if resource {
+                                    resource_keys.insert(key.clone());
+                                }
+                                if outside_prefix {
+                                    outside_prefix_keys.insert(key.clone());
+                                }
+                                queued_keys.insert(key);
+                                if self.dry_run {
+                                    let finding = Finding::WouldFetch {
+                                        url: absolute_link.to_string(),
+                                        referrer: url.clone(),
+                                        check_only: !in_scope || resource || outside_prefix,
+                                    };
+                                    if let Err(send_err) = tx.send(CrawlMessage::Finding(finding)).await {
+                                        log::error!(
+                                            "Failed to send a link through the channel: {}",
+                                            send_err
+                                        );
+                                    }
+                                    continue;
+                                }
+                                let next = (absolute_link.to_string(), Some(url.clone()), depth + 1);
+                                if !in_scope && self.external_concurrency.is_some() {
+                                    external_to_visit.push_back(next);
+                                } else {
+                                    to_visit.push(next);
+                                }
+                                if let Some(method) = htmx_method {
+                                    htmx_urls.entry(absolute_link.to_string()).or_insert(method);
+                                }
+                                queued += 1;
+                            }
+                        }
+                        Ok::<_, anyhow::Error>(())
+                    }
+                    .instrument(enqueue.clone())
+                    .await?;
+                    enqueue.record("queued", queued);
+                    self.progress.record_links(queued);
+                }
+                // The endpoint exists, it just doesn't answer GET requests
+                Err(e)
+                    if e.status() == Some(405)
+                        && htmx_urls.get(&url) == Some(&HtmxMethod::Post) =>
+                {
+                    log::debug!(
+                        "hx-post endpoint {} doesn't allow GET, counting it as found",
+                        url
+                    );
+                }
+                // Another site that doesn't answer is as broken as one answering with an error
+                Err(e) if e.is_broken_link(self.only_404) || (external && e.status().is_none()) => {
+                    if let Some(pattern) = self.ignore_broken.matching_pattern(&url) {
+                        log::debug!("Not reporting {} ({}), it matches {}", url, e, pattern);
+                        continue;
+                    }
+                    if self.max_errors.is_some_and(|max| broken_found >= max) {
+                        log::debug!("Dropping {}, the error cap was already reached", url);
+                        self.progress.record_stopped_at_max_errors();
+                        stopping = true;
+                        continue;
+                    }
+                    let suggestion = valid_by_lowercase
+                        .get(&url.to_lowercase())
+                        .filter(|valid| **valid != url)
+                        .map(|valid| format!("case mismatch, did you mean {}?", valid));
+                    let kind = if htmx_urls.contains_key(&url) {
+                        LinkKind::Htmx
+                    } else if resource_keys.contains(&key) {
+                        LinkKind::Resource
+                    } else {
+                        LinkKind::Link
+                    };
+                    // 0 stands in for the status when no response came back
+                    let status = e.status().unwrap_or(0);
+                    let redirected_to = match &e {
+                        FetchError::Http(e) => e.redirected_to(&url),
+                        _ => None,
+                    };
+                    let soft_404 = matches!(e, FetchError::Soft404 { .. });
+                    let (title, error) = match e {
+                        FetchError::Http(e) if e.status().is_none() => {
+                            let message = if e.error.is_timeout() {
+                                "no response: timed out".to_string()
+                            } else if e.is_dns() {
+                                let host = Url::parse(&url)
+                                    .ok()
+                                    .and_then(|url| url.host_str().map(str::to_string));
+                                format!(
+                                    "no response: dns lookup failed for {}",
+                                    host.unwrap_or_default()
+                                )
+                            } else if e.is_refused() {
+                                "no response: connection refused".to_string()
+                            } else if e.is_tls() {
+                                format!("tls error: {}", e)
+                            } else if e.error.is_decode() {
+                                // The innermost error says what's wrong with the body
+                                let reason = e.sources().last().map(|e| e.to_string());
+                                format!(
+                                    "unreadable body: {}",
+                                    reason.unwrap_or_else(|| e.to_string())
+                                )
+                            } else if let Some(reason) = std::error::Error::source(&e.error)
+                                .filter(|_| e.error.is_redirect())
+                            {
+                                format!("broken redirect: {}", reason)
+                            } else {
+                                format!("no response: {}", e)
+                            };
+                            (None, Some(message))
+                        }
+                        FetchError::Http(e) => (e.title, None),
+                        FetchError::Body { message, .. } | FetchError::Soft404 { message, .. } => {
+                            (None, Some(message))
+                        }
+                        e @ FetchError::Redirect { .. } => (None, Some(e.to_string())),
+                        FetchError::File(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                            (None, Some(format!("unreadable file: {}", e)))
+                        }
+                        FetchError::File(_) => (None, None),
+                    };
+                    let class = Url::parse(&url).ok().map(|u| LinkClass::of(&u, root_url));
+                    let finding = Finding::NotFound {
+                        url: url.clone(),
+                        title,
+                        referrer,
+                        suggestion,
+                        kind,
+                        error,
+                        class,
+                        status: Some(status),
+                        redirected_to,
+                        soft_404,
+                    };
+                    self.send_broken(&tx, finding).await;
+                    for other in also_linked_from {
+                        send_also_linked_from(&tx, &url, &other).await;
+                    }
+                    broken_keys.insert(key, url);
+                    broken_found += 1;
+                    self.progress.record_broken(status);
+                    if self.max_errors.is_some_and(|max| broken_found >= max) {
+                        let unvisited = to_visit.len() + external_to_visit.len();
+                        log::warn!(
+                            "Found {} broken links, stopping with {} URLs left unvisited",
+                            broken_found,
+                            unvisited
+                        );
+                        // Whatever is still in flight is checked, but not reported if broken
+                        if unvisited + in_flight.len() > 0 {
+                            self.progress.record_stopped_at_max_errors();
+                        }
+                        stopping = true;
+                    }
+                }
+                // With `only_404`, other error statuses on other sites aren't reported, but they
+                // don't fail the crawl either
+                Err(e) if external => log::info!("Not reporting {}: {}", url, e),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        // Every page that can be is loaded by now, and a fragment is reported once, however many
+        // pages link to it
+        let mut missing_fragments: HashMap<String, String> = HashMap::new();
+        for (key, link, referrer) in fragment_links {
+            let Some(anchors) = page_anchors.get(&key) else {
+                // Broken, not HTML, or never fetched
+                continue;
+            };
+            let fragment = link.rsplit_once('#').map_or("", |(_, fragment)| fragment);
+            if fragment_exists(fragment, anchors) {
+                continue;
+            }
+            if let Some(first_referrer) = missing_fragments.get(&link) {
+                if *first_referrer != referrer {
+                    send_also_linked_from(&tx, &link, &referrer).await;
+                }
+                continue;
+            }
+            if let Some(pattern) = self.ignore_broken.matching_pattern(&link) {
+                log::debug!(
+                    "Not reporting {} (missing fragment), it matches {}",
+                    link,
+                    pattern
+                );
+                continue;
+            }
+            if self.max_errors.is_some_and(|max| broken_found >= max) {
+                self.progress.record_stopped_at_max_errors();
+                break;
+            }
+            let class = Url::parse(&link).ok().map(|u| LinkClass::of(&u, root_url));
+            let finding = Finding::NotFound {
+                url: link.clone(),
+                title: None,
+                referrer: Some(referrer.clone()),
+                suggestion: None,
+                kind: LinkKind::Fragment,
+                error: Some(format!(
+                    "missing fragment: nothing on the page has id=\"{}\"",
+                    fragment
+                )),
+                class,
+                status: Some(200),
+                redirected_to: None,
+                soft_404: false,
+            };
+            self.send_broken(&tx, finding).await;
+            missing_fragments.insert(link, referrer);
+            broken_found += 1;
+            self.progress.record_broken(200);
+        }
+        log::info!("Done crawling...");
+        if let Err(send_err) = tx.send(CrawlMessage::Complete).await {
+            log::error!(
+                "Failed to signal completion through the channel: {}",
+                send_err
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use std::time::{Duration, SystemTime};
+
+/// Environment variable with a deadline for the crawl, for orchestrators (Kubernetes jobs, CI)
+/// that would rather set one than build the command line.
+pub const DEADLINE_ENV: &str = "FBL_DEADLINE";
+
+/// How long there is until the deadline in `value`: an RFC 3339 time in UTC, like
+/// `2026-10-14T09:30:00Z`, or a number of seconds from `now`. A deadline already past leaves
+/// zero.
+pub fn time_left(value: &str, now: SystemTime) -> Result<Duration, String> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+    let deadline = humantime::parse_rfc3339(value).map_err(|_| {
+        format!(
+            "{} should be seconds from now or an RFC 3339 time in UTC like \
+             2026-10-14T09:30:00Z, got '{}'",
+            DEADLINE_ENV, value
+        )
+    })?;
+    Ok(deadline.duration_since(now).unwrap_or_default())
+}
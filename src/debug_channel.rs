@@ -1,44 +1,197 @@
+use std::collections::VecDeque;
 use std::marker::PhantomData;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+/// How long sends have to keep finding the channel full before it's logged as backpressure.
+pub const DEFAULT_PINNED_WARNING: Duration = Duration::from_secs(10);
+
+/// Tracks how long the channel has been full, shared between the senders.
+#[derive(Debug)]
+struct Pinned {
+    warn_after: Duration,
+    /// When a send first found the channel full, if every send since has too
+    since: Option<Instant>,
+    /// Whether this stretch of being full was already warned about
+    warned: bool,
+    /// How many stretches were warned about
+    count: usize,
+}
+
+impl Pinned {
+    /// Called around each send with whether the channel was full. Returns how long it's been
+    /// full the first time that's longer than `warn_after`, so it's warned about once per
+    /// stretch.
+    fn note(&mut self, full: bool) -> Option<Duration> {
+        if !full {
+            self.since = None;
+            self.warned = false;
+            return None;
+        }
+        let pinned_for = self.since.get_or_insert_with(Instant::now).elapsed();
+        if self.warned || pinned_for < self.warn_after {
+            return None;
+        }
+        self.warned = true;
+        self.count += 1;
+        Some(pinned_for)
+    }
+}
+
+/// How a [`DebugChannel`] has been used, from [`DebugChannel::stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// Values on their way into the channel right now: waiting on `send`, or spilled by
+    /// `try_send`
+    pub current_usage: usize,
+    /// The most that ever were at once
+    pub max_usage: usize,
+    /// Values that made it into the channel
+    pub total_sent: u64,
+}
+
+/// The sending half of a [`DebugChannel`], counting the values on their way in.
+///
+/// When to use which send:
+///
+/// - [`DebugSender::send`] waits for room in the channel, so the channel's size limits how far
+///   the sender gets ahead of the receiver. Use it by default.
+/// - [`DebugSender::try_send`] never waits: without room, the value goes into an overflow queue
+///   and is sent ahead of the next `send`. Use it where waiting on the receiver would stall
+///   other work, like a fetch worker reporting a 404. The queue has no limit, so it's for
+///   places that send a bounded number of values.
+///
+/// The overflow queue is only emptied by `send` and [`DebugSender::flush`], so finish with one of
+/// them, like the crawl's final [`CrawlMessage::Complete`], or the last values are never
+/// delivered.
+///
+/// [`CrawlMessage::Complete`]: crate::crawler::CrawlMessage::Complete
 pub struct DebugSender<T> {
     sender: mpsc::Sender<T>,
     counter: Arc<AtomicUsize>, // Use Arc to share this atomic between multiple senders
     max_size: Arc<AtomicUsize>, // Add this field to DebugSender
+    total_sent: Arc<AtomicU64>,
+    pinned: Arc<Mutex<Pinned>>,
+    /// Values `try_send` found no room for, sent ahead of the next `send`
+    overflow: Arc<Mutex<VecDeque<T>>>,
 }
 
 impl<T> DebugSender<T> {
     pub async fn send(&self, value: T) -> Result<(), mpsc::error::SendError<T>> {
-        // Increase the count immediately when `.send` is called
-        let current_size = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+        // Whatever `try_send` had no room for goes first, to keep the order values were sent in
+        self.flush().await?;
+        self.count_pending();
 
-        // Check and update max_size if necessary
-        let mut max_size = self.max_size.load(Ordering::SeqCst);
-        while current_size > max_size {
-            match self.max_size.compare_exchange(max_size, current_size, Ordering::SeqCst, Ordering::Relaxed) {
-                Ok(_) => break, // Successfully updated max_size
-                Err(current) => max_size = current, // max_size was changed by another thread, retry with new value
-            }
-        }
+        // A send that has to wait means the receiver is behind; checked again once it's done,
+        // so a single sender stuck for a long time is noticed too
+        let full = self.sender.capacity() == 0;
+        self.warn_if_pinned(full);
 
         // Perform the send operation
         let send_result = self.sender.send(value).await;
+        if full {
+            self.warn_if_pinned(true);
+        }
 
         // Decrease the count when the send message finishes, regardless of success or failure
         self.counter.fetch_sub(1, Ordering::SeqCst);
+        self.count_sent(&send_result);
 
         // Return the result of the send operation
         send_result
     }
+
+    /// Sends `value` without waiting. If the channel is full, or values spilled earlier are still
+    /// waiting, it's added to the overflow queue instead and still counts as pending, against
+    /// the max buffer size too, until it's sent. Only fails if the receiver is gone.
+    pub fn try_send(&self, value: T) -> Result<(), mpsc::error::SendError<T>> {
+        self.count_pending();
+        let mut overflow = self.overflow.lock().unwrap();
+        let value = if overflow.is_empty() {
+            match self.sender.try_send(value) {
+                Ok(()) => {
+                    self.counter.fetch_sub(1, Ordering::SeqCst);
+                    self.total_sent.fetch_add(1, Ordering::SeqCst);
+                    self.warn_if_pinned(false);
+                    return Ok(());
+                }
+                Err(mpsc::error::TrySendError::Closed(value)) => {
+                    self.counter.fetch_sub(1, Ordering::SeqCst);
+                    return Err(mpsc::error::SendError(value));
+                }
+                Err(mpsc::error::TrySendError::Full(value)) => value,
+            }
+        } else {
+            value
+        };
+        overflow.push_back(value);
+        drop(overflow);
+        self.warn_if_pinned(true);
+        Ok(())
+    }
+
+    /// Sends the values [`DebugSender::try_send`] spilled to the overflow queue, in order, waiting
+    /// for room like `send`.
+    pub async fn flush(&self) -> Result<(), mpsc::error::SendError<T>> {
+        loop {
+            let Some(value) = self.overflow.lock().unwrap().pop_front() else {
+                return Ok(());
+            };
+            let send_result = self.sender.send(value).await;
+            self.counter.fetch_sub(1, Ordering::SeqCst);
+            self.count_sent(&send_result);
+            send_result?;
+        }
+    }
+
+    fn count_sent<E>(&self, send_result: &Result<(), E>) {
+        if send_result.is_ok() {
+            self.total_sent.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Counts one more value on its way into the channel, for the max buffer size.
+    fn count_pending(&self) {
+        // Increase the count immediately when a send is called
+        let current_size = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+
+        // Check and update max_size if necessary
+        let mut max_size = self.max_size.load(Ordering::SeqCst);
+        while current_size > max_size {
+            match self.max_size.compare_exchange(
+                max_size,
+                current_size,
+                Ordering::SeqCst,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,                     // Successfully updated max_size
+                Err(current) => max_size = current, // max_size was changed by another thread, retry with new value
+            }
+        }
+    }
+
+    fn warn_if_pinned(&self, full: bool) {
+        let pinned_for = self.pinned.lock().unwrap().note(full);
+        if let Some(pinned_for) = pinned_for {
+            log::warn!(
+                "The findings channel has been full for {:.1}s, so the crawl is waiting on \
+                 whatever reads it; a bigger --channel-buffer may help",
+                pinned_for.as_secs_f64()
+            );
+        }
+    }
 }
 
 pub struct DebugChannel<T> {
     sender: mpsc::Sender<T>,
     receiver: mpsc::Receiver<T>,
     counter: Arc<AtomicUsize>,
-    max_size: Arc<AtomicUsize>,   // Tracks the maximum size the buffer has reached
+    max_size: Arc<AtomicUsize>, // Tracks the maximum size the buffer has reached
+    total_sent: Arc<AtomicU64>,
+    pinned: Arc<Mutex<Pinned>>,
+    overflow: Arc<Mutex<VecDeque<T>>>,
     _marker: PhantomData<T>, // This is used to associate the generic type T with the struct without storing it
 }
 
@@ -50,16 +203,33 @@ impl<T> DebugChannel<T> {
             receiver,
             counter: Arc::new(AtomicUsize::new(0)),
             max_size: Arc::new(AtomicUsize::new(0)),
+            total_sent: Arc::new(AtomicU64::new(0)),
+            pinned: Arc::new(Mutex::new(Pinned {
+                warn_after: DEFAULT_PINNED_WARNING,
+                since: None,
+                warned: false,
+                count: 0,
+            })),
+            overflow: Arc::new(Mutex::new(VecDeque::new())),
             _marker: PhantomData,
         }
     }
 
+    /// Log a warning once sends have kept finding the channel full for `warn_after`, rather
+    /// than [`DEFAULT_PINNED_WARNING`]: the receiver is the bottleneck.
+    pub fn warn_when_pinned_for(self, warn_after: Duration) -> Self {
+        self.pinned.lock().unwrap().warn_after = warn_after;
+        self
+    }
+
     pub fn sender(&self) -> DebugSender<T> {
         DebugSender {
             sender: self.sender.clone(),
             counter: self.counter.clone(), // Clone the Arc to share the counter
             max_size: self.max_size.clone(), // Make sure to clone max_size as well
-
+            total_sent: self.total_sent.clone(),
+            pinned: self.pinned.clone(),
+            overflow: self.overflow.clone(),
         }
     }
 
@@ -67,14 +237,32 @@ impl<T> DebugChannel<T> {
         self.receiver.recv().await
     }
 
-    // // You can use this method for debugging to get the current number of items in the channel
-    // // Method to get current buffer usage
-    // pub fn get_current_buffer_usage(&self) -> usize {
-    //     self.counter.load(Ordering::SeqCst)
-    // }
+    // Method to get current buffer usage, the number of values on their way into the channel
+    pub fn get_current_buffer_usage(&self) -> usize {
+        self.counter.load(Ordering::SeqCst)
+    }
 
     // Method to get max buffer size reached (useful after processing to see how full the buffer gets)
     pub fn get_max_buffer_size(&self) -> usize {
         self.max_size.load(Ordering::SeqCst)
     }
+
+    /// Values that made it into the channel so far, by any of the senders.
+    pub fn get_total_sent(&self) -> u64 {
+        self.total_sent.load(Ordering::SeqCst)
+    }
+
+    /// Current and max usage and total sent at once, for reporting.
+    pub fn stats(&self) -> ChannelStats {
+        ChannelStats {
+            current_usage: self.get_current_buffer_usage(),
+            max_usage: self.get_max_buffer_size(),
+            total_sent: self.get_total_sent(),
+        }
+    }
+
+    /// How many times the channel stayed full long enough to be warned about.
+    pub fn get_pinned_count(&self) -> usize {
+        self.pinned.lock().unwrap().count
+    }
 }
@@ -0,0 +1,51 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use url::Url;
+
+/// How long to wait for any one lookup before giving up on it.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Resolves the distinct hosts of `urls` concurrently and returns how many resolved.
+///
+/// This only warms whatever caches sit behind the system resolver (nscd, systemd-resolved, the
+/// local DNS server), so the first request to each host doesn't wait on a cold lookup. It's
+/// best-effort: a failed or slow lookup is logged and otherwise ignored, and the request to that
+/// host will simply resolve it again.
+pub async fn prefetch<'a>(urls: impl IntoIterator<Item = &'a Url>) -> usize {
+    let hosts: HashSet<(String, u16)> = urls
+        .into_iter()
+        .filter(|url| url.domain().is_some())
+        .filter_map(|url| Some((url.host_str()?.to_string(), url.port_or_known_default()?)))
+        .collect();
+    if hosts.is_empty() {
+        return 0;
+    }
+    log::info!("Prefetching DNS for {} hosts", hosts.len());
+
+    let mut lookups = JoinSet::new();
+    for (host, port) in hosts {
+        lookups.spawn(async move {
+            let lookup = tokio::net::lookup_host((host.as_str(), port));
+            match tokio::time::timeout(LOOKUP_TIMEOUT, lookup).await {
+                Ok(Ok(_)) => true,
+                Ok(Err(e)) => {
+                    log::debug!("DNS prefetch for {} failed: {}", host, e);
+                    false
+                }
+                Err(_) => {
+                    log::debug!("DNS prefetch for {} timed out", host);
+                    false
+                }
+            }
+        });
+    }
+
+    let mut resolved = 0;
+    while let Some(result) = lookups.join_next().await {
+        if result.unwrap_or(false) {
+            resolved += 1;
+        }
+    }
+    resolved
+}
@@ -0,0 +1,109 @@
+use crate::crawler::Finding;
+use crate::progress::CrawlSummary;
+use crate::report::NotFoundError;
+use crate::skipped::SkippedLink;
+use crate::warnings::LinkWarning;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+/// Format of the live event stream written to stdout by `--events`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EventFormat {
+    /// One JSON object per line, each with a `type` and a `timestamp`
+    Ndjson,
+}
+
+/// Something that happened during a crawl, for dashboards following it live.
+///
+/// Serialized with its kind as `type`, in snake_case, next to the fields of that kind.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CrawlEvent {
+    /// A URL was fetched, broken or not
+    PageChecked {
+        url: String,
+        /// `None` if no response was received
+        status: Option<u16>,
+        elapsed_ms: u64,
+        content_type: Option<String>,
+        /// `None` for the root URL
+        referrer: Option<String>,
+    },
+    /// A broken link, in the report's format
+    BrokenLink(NotFoundError),
+    /// Another page links to a URL already sent as a `broken_link`
+    AlsoLinkedFrom {
+        url: String,
+        referrer: String,
+    },
+    Warning(LinkWarning),
+    /// A link deliberately not checked
+    Skipped(SkippedLink),
+    /// The crawl is over; always the last event
+    Done {
+        summary: CrawlSummary,
+    },
+}
+
+impl CrawlEvent {
+    /// The event for a finding sent by the crawler; `None` for [`Finding::WouldFetch`], which
+    /// only `dry_run` sends.
+    pub fn from_finding(finding: Finding) -> Option<Self> {
+        let event = match finding {
+            Finding::Checked(checked) => CrawlEvent::PageChecked {
+                url: checked.url,
+                status: checked.status,
+                elapsed_ms: checked.elapsed.as_millis() as u64,
+                content_type: checked.content_type,
+                referrer: checked.referrer,
+            },
+            Finding::AlsoLinkedFrom { url, referrer } => {
+                CrawlEvent::AlsoLinkedFrom { url, referrer }
+            }
+            Finding::Warning(warning) => CrawlEvent::Warning(warning),
+            Finding::Skipped(skipped) => CrawlEvent::Skipped(skipped),
+            Finding::WouldFetch { .. } => return None,
+            finding => CrawlEvent::BrokenLink(NotFoundError::from_finding(finding)?),
+        };
+        Some(event)
+    }
+}
+
+#[derive(Serialize)]
+struct TimestampedEvent<'a> {
+    #[serde(flatten)]
+    event: &'a CrawlEvent,
+    /// RFC 3339 in UTC, when the event was written
+    timestamp: String,
+}
+
+/// Writes crawl events as they happen, for `--events ndjson`: one JSON object per line,
+/// flushed straight away so a reader on the other end of a pipe sees each one as it comes.
+pub struct EventWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> EventWriter<W> {
+    pub fn new(out: W) -> Self {
+        EventWriter { out }
+    }
+
+    pub fn record(&mut self, event: &CrawlEvent) -> io::Result<()> {
+        let line = TimestampedEvent {
+            event,
+            timestamp: humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+        };
+        serde_json::to_writer(&mut self.out, &line)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()
+    }
+
+    /// Writes the event for `finding`, if it has one.
+    pub fn record_finding(&mut self, finding: &Finding) -> io::Result<()> {
+        match CrawlEvent::from_finding(finding.clone()) {
+            Some(event) => self.record(&event),
+            None => Ok(()),
+        }
+    }
+}
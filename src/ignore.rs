@@ -0,0 +1,104 @@
+use crate::safety::glob_matches;
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+use url::Url;
+
+/// One `--ignore-url` pattern, matched against the whole absolute URL of a broken link.
+#[derive(Debug, Clone)]
+pub enum IgnorePattern {
+    /// A URL, matched exactly once both are parsed, so `https://example.com` matches
+    /// `https://example.com/`
+    Exact(String),
+    /// Has a `*`, which matches any run of characters; the rest matches literally
+    Glob(String),
+    /// Written between slashes, like `/^https://api\.example\.com/v1/`; matches anywhere in
+    /// the URL unless anchored
+    Regex(Regex),
+}
+
+impl IgnorePattern {
+    pub fn matches(&self, url: &str) -> bool {
+        match self {
+            IgnorePattern::Exact(exact) => *exact == normalized(url),
+            IgnorePattern::Glob(glob) => glob_matches(glob, url),
+            IgnorePattern::Regex(regex) => regex.is_match(url),
+        }
+    }
+}
+
+/// `url` as [`Url`] writes it, or as given if it doesn't parse.
+fn normalized(url: &str) -> String {
+    Url::parse(url).map_or_else(|_| url.to_string(), String::from)
+}
+
+impl FromStr for IgnorePattern {
+    type Err = regex::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(regex) = s
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+            .filter(|regex| !regex.is_empty())
+        {
+            Ok(IgnorePattern::Regex(Regex::new(regex)?))
+        } else if s.contains('*') {
+            Ok(IgnorePattern::Glob(s.to_string()))
+        } else {
+            Ok(IgnorePattern::Exact(normalized(s)))
+        }
+    }
+}
+
+/// The pattern as written, for logging which one a link matched.
+impl fmt::Display for IgnorePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IgnorePattern::Exact(text) | IgnorePattern::Glob(text) => f.write_str(text),
+            IgnorePattern::Regex(regex) => write!(f, "/{}/", regex),
+        }
+    }
+}
+
+/// Known-broken URLs to leave out of the report, like login-gated pages or rate-limited APIs
+/// that fail every time, so they don't drown out new breakage or fail a CI gate.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreList {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreList {
+    pub fn new(patterns: Vec<IgnorePattern>) -> Self {
+        IgnoreList { patterns }
+    }
+
+    /// Parses an ignore file: one pattern per line, leaving out blank lines and `#` comments.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let patterns = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(number, line)| {
+                line.parse()
+                    .map_err(|e| format!("line {}: invalid pattern {:?}: {}", number, line, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(IgnoreList { patterns })
+    }
+
+    /// Adds the patterns of `other` after these.
+    pub fn extend(&mut self, other: IgnoreList) {
+        self.patterns.extend(other.patterns);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// The first pattern that `url` matches, if any.
+    pub fn matching_pattern(&self, url: &str) -> Option<&IgnorePattern> {
+        self.patterns.iter().find(|pattern| pattern.matches(url))
+    }
+}
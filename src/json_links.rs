@@ -0,0 +1,162 @@
+use serde_json::Value;
+use std::str::FromStr;
+use url::Url;
+
+/// A small subset of JSONPath for pointing at the URL fields of a JSON document.
+///
+/// Supported: `$` (the root, optional), `.key`, `['key']`, `[N]` (array index), `.*` / `[*]`
+/// (every child) and `..key` (`key` at any depth). For example `$.items[*].url` or `$..href`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    AnyChild,
+    Descendant(String),
+}
+
+impl FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = s.trim().strip_prefix('$').unwrap_or(s.trim());
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                let (name, after) = take_name(after);
+                if name.is_empty() || name == "*" {
+                    return Err(format!("expected a key after '..' in '{}'", s));
+                }
+                segments.push(Segment::Descendant(name.to_string()));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let (name, after) = take_name(after);
+                segments.push(match name {
+                    "" => return Err(format!("expected a key after '.' in '{}'", s)),
+                    "*" => Segment::AnyChild,
+                    name => Segment::Key(name.to_string()),
+                });
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let (inner, after) = after
+                    .split_once(']')
+                    .ok_or_else(|| format!("unclosed '[' in '{}'", s))?;
+                let inner = inner.trim();
+                segments.push(if inner == "*" {
+                    Segment::AnyChild
+                } else if let Ok(index) = inner.parse() {
+                    Segment::Index(index)
+                } else if let Some(key) = quoted(inner) {
+                    Segment::Key(key.to_string())
+                } else {
+                    return Err(format!("unsupported selector '[{}]' in '{}'", inner, s));
+                });
+                rest = after;
+            } else if segments.is_empty() {
+                // Allow leaving out the leading `$.`: `items[*].url`
+                let (name, after) = take_name(rest);
+                segments.push(Segment::Key(name.to_string()));
+                rest = after;
+            } else {
+                return Err(format!("unexpected '{}' in '{}'", rest, s));
+            }
+        }
+        Ok(JsonPath { segments })
+    }
+}
+
+/// Splits a bare key (up to the next `.` or `[`) off the front of `s`.
+fn take_name(s: &str) -> (&str, &str) {
+    let end = s.find(['.', '[']).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn quoted(s: &str) -> Option<&str> {
+    s.strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .or_else(|| s.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+}
+
+impl JsonPath {
+    /// Every value in `document` the path points at.
+    pub fn select<'a>(&self, document: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![document];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for value in current {
+                match segment {
+                    Segment::Key(key) => next.extend(value.get(key)),
+                    Segment::Index(index) => next.extend(value.get(index)),
+                    Segment::AnyChild => next.extend(children(value)),
+                    Segment::Descendant(key) => collect_descendants(value, key, &mut next),
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(fields) => fields.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn collect_descendants<'a>(value: &'a Value, key: &str, found: &mut Vec<&'a Value>) {
+    if let Some(matched) = value.get(key) {
+        found.push(matched);
+    }
+    for child in children(value) {
+        collect_descendants(child, key, found);
+    }
+}
+
+/// Extracts the links from a JSON response.
+///
+/// With `paths`, every string they select is a link, relative or not. Without, only string
+/// values that are complete `http://` or `https://` URLs count: JSON is full of strings, and
+/// guessing which relative-looking ones are paths would mostly produce noise.
+pub fn find_json_links(document: &Value, paths: &[JsonPath]) -> Vec<String> {
+    let mut links = Vec::new();
+    if paths.is_empty() {
+        collect_absolute_urls(document, &mut links);
+    } else {
+        for path in paths {
+            links.extend(
+                path.select(document)
+                    .into_iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string),
+            );
+        }
+    }
+    for link in &links {
+        log::debug!("Adding JSON link: {}", link);
+    }
+    links
+}
+
+fn collect_absolute_urls(value: &Value, links: &mut Vec<String>) {
+    match value {
+        Value::String(s) if looks_like_url(s) => links.push(s.clone()),
+        Value::Array(_) | Value::Object(_) => {
+            for child in children(value) {
+                collect_absolute_urls(child, links);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn looks_like_url(s: &str) -> bool {
+    !s.contains(char::is_whitespace)
+        && Url::parse(s)
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+}
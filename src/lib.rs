@@ -0,0 +1,37 @@
+pub mod accept;
+pub mod access_log;
+pub mod concurrency;
+pub mod config;
+pub mod connections;
+pub mod crawler;
+pub mod deadline;
+pub mod debug_channel;
+pub mod dns;
+pub mod events;
+pub mod ignore;
+pub mod json_links;
+#[cfg(feature = "fast-parser")]
+pub mod link_tokenizer;
+pub mod links;
+pub mod local_dir;
+pub mod login;
+pub mod normalize;
+pub mod pacing;
+pub mod page_cache;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod progress;
+pub mod recheck;
+pub mod report;
+pub mod retry;
+pub mod robots;
+pub mod safety;
+pub mod scope;
+pub mod sitemap;
+pub mod skipped;
+pub mod soft_404;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod url_filter;
+pub mod user_agent;
+pub mod warnings;
@@ -0,0 +1,228 @@
+//! Link extraction straight from html5ever's tokenizer, without building a document tree.
+//!
+//! Most of the time spent parsing a page with [`select`] goes into tree construction, which link
+//! extraction doesn't need: every link is a start tag and its attributes. This module reads the
+//! tags as they're tokenized and emulates the few tree builder rules that decide which tags
+//! count, so [`document_links`] returns what [`crate::links::document_links`] does:
+//!
+//! - `<script>`, `<style>`, `<textarea>`, `<title>` and the like hold text, not markup;
+//! - `<template>` content is left out;
+//! - `<noscript>` content is raw text, parsed again as a fallback document whose links are
+//!   listed after the page's own, and whose `<base>`, `<link rel="canonical">` and
+//!   `<meta name="robots">` don't count.
+//!
+//! Malformed markup the tree builder repairs can still differ: it clones an `<a>` left open
+//! across misnested tags (finding its `href` twice), moves elements out of tables (changing the
+//! order of links) and drops tags where they aren't allowed, like an `<a>` inside `<select>`.
+
+use crate::links::{
+    has_rel, is_robots_meta, names_an_origin, push_anchor, push_htmx, push_resource,
+    ExtractedLinks, LinkSources, HTMX_ATTRIBUTES, RESOURCE_ATTRIBUTES,
+};
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{
+    BufferQueue, Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+
+/// Same as [`crate::links::document_links`], two to three times faster on large pages.
+pub fn document_links(html: &str, sources: LinkSources) -> ExtractedLinks {
+    let page = tokenize(html, LinkSink::new(sources, true));
+    let mut extracted = page.extracted;
+    for fallback in page.noscript_fallbacks {
+        extracted.extend(tokenize(&fallback, LinkSink::new(sources, false)).extracted);
+    }
+    extracted
+}
+
+fn tokenize(html: &str, sink: LinkSink) -> LinkSink {
+    let mut input = BufferQueue::new();
+    input.push_back(StrTendril::from_slice(html));
+    let mut tokenizer = Tokenizer::new(sink, TokenizerOpts::default());
+    // The sink never asks to run a script, so this doesn't stop before the end of the input
+    let _ = tokenizer.feed(&mut input);
+    tokenizer.end();
+    let mut sink = tokenizer.sink;
+    sink.finish();
+    sink
+}
+
+struct LinkSink {
+    sources: LinkSources,
+    extracted: ExtractedLinks,
+    /// Values of each of the [`HTMX_ATTRIBUTES`]; the tree version lists them attribute by
+    /// attribute
+    htmx: [Vec<String>; HTMX_ATTRIBUTES.len()],
+    /// URLs of each of the [`RESOURCE_ATTRIBUTES`], listed element by element like `htmx`
+    resources: [Vec<String>; RESOURCE_ATTRIBUTES.len()],
+    /// Whether to keep `<noscript>` content; a fallback document's own `<noscript>` is ignored
+    keep_noscript: bool,
+    noscript_fallbacks: Vec<String>,
+    /// Raw text of the `<noscript>` element being read
+    noscript: Option<String>,
+    template_depth: usize,
+    /// Depth inside `<svg>` and `<math>`, where tags like `<script>` and `<title>` hold markup
+    foreign_depth: usize,
+}
+
+impl LinkSink {
+    fn new(sources: LinkSources, keep_noscript: bool) -> Self {
+        LinkSink {
+            sources,
+            extracted: ExtractedLinks::default(),
+            htmx: Default::default(),
+            resources: Default::default(),
+            keep_noscript,
+            noscript_fallbacks: Vec::new(),
+            noscript: None,
+            template_depth: 0,
+            foreign_depth: 0,
+        }
+    }
+
+    fn finish(&mut self) {
+        for ((_, _, element), urls) in RESOURCE_ATTRIBUTES.iter().zip(&self.resources) {
+            for url in urls {
+                push_resource(url, *element, &mut self.extracted);
+            }
+        }
+        for ((attribute, method), urls) in HTMX_ATTRIBUTES.iter().zip(&self.htmx) {
+            for url in urls {
+                push_htmx(attribute, url, *method, self.sources, &mut self.extracted);
+            }
+        }
+    }
+
+    fn start_tag(&mut self, tag: Tag) -> TokenSinkResult<()> {
+        let foreign = self.foreign_depth > 0;
+        if self.template_depth == 0 {
+            self.collect(&tag, foreign);
+        }
+
+        let name = &*tag.name;
+        if foreign {
+            if matches!(name, "svg" | "math") && !tag.self_closing {
+                self.foreign_depth += 1;
+            }
+            return TokenSinkResult::Continue;
+        }
+        // Self-closing syntax is ignored on these, so they all start raw text
+        match name {
+            "svg" | "math" if !tag.self_closing => self.foreign_depth += 1,
+            "template" => self.template_depth += 1,
+            "script" => return TokenSinkResult::RawData(RawKind::ScriptData),
+            "style" | "xmp" | "iframe" | "noembed" | "noframes" => {
+                return TokenSinkResult::RawData(RawKind::Rawtext)
+            }
+            "noscript" => {
+                self.noscript = Some(String::new());
+                return TokenSinkResult::RawData(RawKind::Rawtext);
+            }
+            "textarea" | "title" => return TokenSinkResult::RawData(RawKind::Rcdata),
+            "plaintext" => return TokenSinkResult::Plaintext,
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+
+    fn end_tag(&mut self, tag: Tag) {
+        let name = &*tag.name;
+        if self.foreign_depth > 0 {
+            if matches!(name, "svg" | "math") {
+                self.foreign_depth -= 1;
+            }
+        } else if name == "template" && self.template_depth > 0 {
+            self.template_depth -= 1;
+        }
+    }
+
+    fn collect(&mut self, tag: &Tag, foreign: bool) {
+        let attr = |wanted: &str| {
+            tag.attrs.iter().find_map(|attr| {
+                let name = &*attr.name.local;
+                // The tree builder stores SVG's `xlink:href` as a namespaced `href`
+                let name = match name.strip_prefix("xlink:") {
+                    Some(local) if foreign => local,
+                    _ => name,
+                };
+                (name == wanted).then_some(&*attr.value)
+            })
+        };
+        if &*tag.name == "a" {
+            if let Some(link) = attr("href") {
+                push_anchor(link, attr("rel"), self.sources, &mut self.extracted);
+            }
+        }
+        // A fallback document's `<base>` is raw text to the tree builder, not an element
+        if &*tag.name == "base" && self.keep_noscript && self.extracted.base.is_none() {
+            self.extracted.base = attr("href").map(str::to_string);
+        }
+        if &*tag.name == "link"
+            && self.keep_noscript
+            && self.extracted.canonical.is_none()
+            && has_rel(attr("rel"), "canonical")
+        {
+            self.extracted.canonical = attr("href").map(str::to_string);
+        }
+        if &*tag.name == "meta" && self.keep_noscript && is_robots_meta(attr("name")) {
+            if let Some(content) = attr("content") {
+                self.extracted.robots.apply(content);
+            }
+        }
+        for ((name, attribute, _), urls) in RESOURCE_ATTRIBUTES.iter().zip(&mut self.resources) {
+            if &*tag.name != *name || (*name == "link" && names_an_origin(attr("rel"))) {
+                continue;
+            }
+            if let Some(url) = attr(attribute) {
+                urls.push(url.to_string());
+            }
+        }
+        if self.sources.htmx {
+            for ((attribute, _), urls) in HTMX_ATTRIBUTES.iter().zip(&mut self.htmx) {
+                if let Some(url) = attr(attribute) {
+                    urls.push(url.to_string());
+                }
+            }
+        }
+    }
+}
+
+impl TokenSink for LinkSink {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            // In raw text, the only tag the tokenizer emits is the one that ends it
+            Token::TagToken(_) if self.noscript.is_some() => {
+                let fallback = self.noscript.take().unwrap_or_default();
+                if self.keep_noscript && self.template_depth == 0 {
+                    self.noscript_fallbacks.push(fallback);
+                }
+            }
+            Token::TagToken(tag) => match tag.kind {
+                TagKind::StartTag => return self.start_tag(tag),
+                TagKind::EndTag => self.end_tag(tag),
+            },
+            Token::CharacterTokens(text) => {
+                if let Some(noscript) = &mut self.noscript {
+                    noscript.push_str(&text);
+                }
+            }
+            Token::EOFToken => {
+                // An unclosed <noscript> runs to the end of the document
+                if let Some(fallback) = self.noscript.take() {
+                    if self.keep_noscript && self.template_depth == 0 {
+                        self.noscript_fallbacks.push(fallback);
+                    }
+                }
+            }
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+
+    fn adjusted_current_node_present_but_not_in_html_namespace(&self) -> bool {
+        // `<![CDATA[...]]>` is text in SVG and MathML, a bogus comment elsewhere
+        self.foreign_depth > 0
+    }
+}
@@ -0,0 +1,636 @@
+use crate::robots::RobotsDirectives;
+use crate::skipped::SkipReason;
+use serde::{Deserialize, Serialize};
+use url::{ParseError, Url};
+
+/// `link` resolved against `base_url`, the page it's on or its `<base href>`, as a browser
+/// would. A protocol-relative link like `//cdn.example.com/app.js` keeps `base_url`'s scheme.
+pub fn make_absolute_url(base_url: &Url, link: &str) -> Result<Url, ParseError> {
+    base_url.join(link) // This resolves the relative URL 'link' against the base URL 'base_url'
+}
+
+/// Schemes of links no HTTP client can check: contact links, FTP, and `data:` and `blob:` URLs,
+/// which hold or point to content in the browser rather than on a server.
+const DENIED_PROTOCOLS: [&str; 5] = ["mailto:", "ftp:", "tel:", "data:", "blob:"];
+
+/// Whether `link` has one of `schemes`, ignoring case and leading whitespace like a browser.
+fn has_scheme(link: &str, schemes: &[&str]) -> bool {
+    let link = link.trim_start();
+    schemes.iter().any(|scheme| {
+        link.get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// Target of a redirect: the `Location` header resolved against the URL that was requested
+/// (not the root URL), per RFC 3986 reference resolution.
+///
+/// A relative `Location` replaces the query of the request URL if it has one of its own and
+/// drops it otherwise; a fragment-only `Location` keeps the request's path and query. A
+/// `Location` without a fragment inherits the request URL's fragment (RFC 7231, section 7.1.2).
+///
+/// The crawler uses it for the `redirected_to` of a link and the target in `--flag-redirects`
+/// warnings.
+pub fn resolve_redirect(request_url: &Url, location: &str) -> Result<Url, ParseError> {
+    let mut target = make_absolute_url(request_url, location.trim())?;
+    if target.fragment().is_none() {
+        target.set_fragment(request_url.fragment());
+    }
+    Ok(target)
+}
+
+/// Whether an `href` contains characters that must be percent-encoded in a URL (spaces, quotes,
+/// angle brackets, ...).
+///
+/// Browsers and `make_absolute_url` quietly encode these, but stricter tools and servers don't,
+/// so they're worth fixing in the source.
+pub fn needs_percent_encoding(link: &str) -> bool {
+    link.chars().any(|c| {
+        c.is_ascii_control()
+            || matches!(
+                c,
+                ' ' | '"' | '<' | '>' | '`' | '{' | '}' | '|' | '\\' | '^'
+            )
+    })
+}
+
+/// Links found on a page: the ones to check, and the ones left out on purpose.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ExtractedLinks {
+    pub links: Vec<String>,
+    /// Hrefs as written, with the reason they were left out
+    pub skipped: Vec<(String, SkipReason)>,
+    /// URLs of HTMX `hx-get`/`hx-post` attributes, with [`LinkSources::htmx`]
+    pub htmx: Vec<(String, HtmxMethod)>,
+    /// What the page loads with `<img src>`, `<script src>`, `<link href>` and `<iframe src>`:
+    /// checked, but not crawled
+    pub resources: Vec<FoundLink>,
+    /// The document's first `<base href>`, as written: relative links resolve against it rather
+    /// than the page's URL
+    pub base: Option<String>,
+    /// The href of the document's first `<link rel="canonical">`, as written: the URL the page
+    /// says it's a copy of, if it's a different one
+    pub canonical: Option<String>,
+    /// With [`LinkSources::anchors`], what a `#fragment` in a link to the page can point to; see
+    /// [`page_anchors`]
+    pub anchors: Vec<String>,
+    /// Directives of the document's `<meta name="robots">` tags
+    pub robots: RobotsDirectives,
+}
+
+impl ExtractedLinks {
+    /// Adds the links of `other`, a fragment of the same page like `<noscript>` or `<template>`
+    /// content. The page's own `base` applies to them, so `other.base` is ignored.
+    pub(crate) fn extend(&mut self, other: ExtractedLinks) {
+        self.links.extend(other.links);
+        self.skipped.extend(other.skipped);
+        self.htmx.extend(other.htmx);
+        self.resources.extend(other.resources);
+    }
+}
+
+/// Element a link was found on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkElement {
+    /// `<a href>`
+    Anchor,
+    /// `<img src>`
+    Img,
+    /// `<script src>`
+    Script,
+    /// `<link href>`, like a stylesheet or icon
+    Link,
+    /// `<iframe src>`
+    Iframe,
+}
+
+impl LinkElement {
+    /// Whether the links on what this element points to are followed. Only anchors lead to
+    /// other pages; the rest point to something the page loads, which is checked but not
+    /// crawled.
+    pub fn is_crawlable(&self) -> bool {
+        *self == LinkElement::Anchor
+    }
+}
+
+/// A link found on a page, and the element it was on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FoundLink {
+    pub url: String,
+    pub element: LinkElement,
+}
+
+/// Which kinds of links to extract besides `<a href>`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkSources {
+    /// Links inside `<template>` elements; see [`extract_template_links`]
+    pub templates: bool,
+    /// URLs in HTMX `hx-get` (or `data-hx-get`) attributes, on any element
+    pub htmx: bool,
+    /// With `htmx`, `hx-post` URLs too; they're skipped as [`SkipReason::HtmxPost`] otherwise
+    pub htmx_post: bool,
+    /// Leave out `<a rel="nofollow">` links, as [`SkipReason::Nofollow`]
+    pub respect_nofollow: bool,
+    /// The page's [anchors](ExtractedLinks::anchors) too, for checking fragments
+    pub anchors: bool,
+}
+
+/// HTTP method an HTMX attribute requests its URL with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmxMethod {
+    Get,
+    Post,
+}
+
+/// What kind of reference a broken link was, for reporting it in its own category.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkKind {
+    /// A regular link, like `<a href>`
+    #[default]
+    Link,
+    /// An HTMX endpoint from an `hx-get` or `hx-post` attribute
+    Htmx,
+    /// Something a page loads, from `<img src>`, `<script src>`, `<link href>` or
+    /// `<iframe src>`
+    Resource,
+    /// A link to a `#fragment` that nothing on the page it points to has as its `id`
+    Fragment,
+}
+
+impl LinkKind {
+    pub fn is_link(&self) -> bool {
+        *self == LinkKind::Link
+    }
+}
+
+/// Where a link points, relative to the site being crawled.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Destination {
+    /// The root URL's host
+    Internal,
+    /// Another host on the root URL's domain, e.g. `docs.example.com` for `example.com` or
+    /// `www.example.com` (and `example.com` itself for the latter)
+    Subdomain,
+    /// Any other host
+    External,
+    /// `mailto:` and `tel:` links, which are never checked
+    Contact,
+}
+
+/// What a link is, for filtering and summarizing results: where it points, and whether it's a
+/// static asset (an image, stylesheet, script or font) rather than a page.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinkClass {
+    pub destination: Destination,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub asset: bool,
+}
+
+impl LinkClass {
+    /// Classifies `url` relative to the crawl's `root` URL. Ports and schemes don't matter, only
+    /// hosts do.
+    pub fn of(url: &Url, root: &Url) -> Self {
+        if matches!(url.scheme(), "mailto" | "tel") {
+            return LinkClass {
+                destination: Destination::Contact,
+                asset: false,
+            };
+        }
+        let destination = match (url.host_str(), root.host_str()) {
+            (Some(host), Some(root_host)) if host == root_host => Destination::Internal,
+            (Some(host), Some(root_host)) => {
+                let site = root_host.strip_prefix("www.").unwrap_or(root_host);
+                let under_site = host
+                    .strip_suffix(site)
+                    .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'));
+                if under_site {
+                    Destination::Subdomain
+                } else {
+                    Destination::External
+                }
+            }
+            _ => Destination::External,
+        };
+        let asset = matches!(
+            file_extension(url).as_deref(),
+            Some(
+                "png"
+                    | "jpg"
+                    | "jpeg"
+                    | "gif"
+                    | "webp"
+                    | "avif"
+                    | "svg"
+                    | "ico"
+                    | "bmp"
+                    | "css"
+                    | "js"
+                    | "mjs"
+                    | "woff"
+                    | "woff2"
+                    | "ttf"
+                    | "otf"
+                    | "eot"
+            )
+        );
+        LinkClass { destination, asset }
+    }
+}
+
+/// Lowercased extension of the last path segment, e.g. `png` for `/img/Logo.PNG?v=2`.
+pub(crate) fn file_extension(url: &Url) -> Option<String> {
+    url.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .and_then(|file| file.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+}
+
+/// The links on a page: its `<a href>` links, then the [resources](ExtractedLinks::resources)
+/// it loads.
+pub fn find_links(html: &str) -> Vec<FoundLink> {
+    let extracted = extract_links(html);
+    extracted
+        .links
+        .into_iter()
+        .map(|url| FoundLink {
+            url,
+            element: LinkElement::Anchor,
+        })
+        .chain(extracted.resources)
+        .collect()
+}
+
+/// Like [`find_links`], but also returns the links that were filtered out and why.
+pub fn extract_links(html: &str) -> ExtractedLinks {
+    extract_links_from(html, LinkSources::default())
+}
+
+/// Like [`extract_links`], also extracting the optional kinds of links in `sources`.
+pub fn extract_links_from(html: &str, sources: LinkSources) -> ExtractedLinks {
+    #[cfg(feature = "fast-parser")]
+    let mut extracted = crate::link_tokenizer::document_links(html, sources);
+    #[cfg(not(feature = "fast-parser"))]
+    let mut extracted = document_links(html, sources);
+
+    if sources.templates {
+        // Template content isn't rendered, so a fragment can't point into it
+        let sources = LinkSources {
+            anchors: false,
+            ..sources
+        };
+        for content in template_contents(html) {
+            extracted.extend(extract_links_from(content, sources));
+        }
+    }
+    if sources.anchors {
+        extracted.anchors = page_anchors(html);
+    }
+
+    extracted
+}
+
+/// Links in the document tree [`select`] builds, followed by the links in each `<noscript>`
+/// element's fallback markup. Template content isn't part of the tree, so it's left out.
+///
+/// This is what [`extract_links_from`] uses unless the `fast-parser` feature swaps in
+/// `link_tokenizer::document_links`.
+pub fn document_links(html: &str, sources: LinkSources) -> ExtractedLinks {
+    let document = select::document::Document::from(html);
+    let mut extracted = ExtractedLinks {
+        base: document
+            .find(select::predicate::Name("base"))
+            .find_map(|node| node.attr("href"))
+            .map(str::to_string),
+        canonical: document
+            .find(select::predicate::Name("link"))
+            .filter(|node| has_rel(node.attr("rel"), "canonical"))
+            .find_map(|node| node.attr("href"))
+            .map(str::to_string),
+        robots: RobotsDirectives::from_meta_robots(
+            document
+                .find(select::predicate::Name("meta"))
+                .filter(|node| is_robots_meta(node.attr("name")))
+                .filter_map(|node| node.attr("content")),
+        ),
+        ..ExtractedLinks::default()
+    };
+    collect_links(&document, sources, &mut extracted);
+
+    // The parser treats <noscript> content as raw text (it assumes scripting is enabled), but we
+    // are exactly the non-JS client that fallback markup is meant for, so parse it separately.
+    for node in document.find(select::predicate::Name("noscript")) {
+        let fallback = select::document::Document::from(node.text().as_str());
+        collect_links(&fallback, sources, &mut extracted);
+    }
+
+    extracted
+}
+
+/// Links inside `<template>` elements, which [`extract_links`] doesn't see: the parser keeps
+/// template content out of the document tree. Includes templates nested in templates.
+///
+/// Template content is found in the raw HTML, so a `<template>` tag inside a comment or script
+/// counts too.
+pub fn extract_template_links(html: &str) -> ExtractedLinks {
+    let sources = LinkSources {
+        templates: true,
+        ..LinkSources::default()
+    };
+    let mut extracted = ExtractedLinks::default();
+    for content in template_contents(html) {
+        extracted.extend(extract_links_from(content, sources));
+    }
+    extracted
+}
+
+/// What a `#fragment` can point to on a page: the `id` of every element, and the `name` of
+/// every `<a>`, in document order.
+pub fn page_anchors(html: &str) -> Vec<String> {
+    let document = select::document::Document::from(html);
+    let ids = document
+        .find(select::predicate::Attr("id", ()))
+        .filter_map(|node| node.attr("id"));
+    let names = document
+        .find(select::predicate::Name("a"))
+        .filter_map(|node| node.attr("name"));
+    ids.chain(names)
+        .filter(|anchor| !anchor.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether a link to `fragment` on a page with `anchors` lands somewhere. `fragment` is as in
+/// the URL, so it's percent-decoded first. An empty fragment and `top` always go to the top of
+/// the page, and a text directive (`#:~:text=...`) isn't part of the anchor.
+pub fn fragment_exists(fragment: &str, anchors: &[String]) -> bool {
+    let anchor = fragment.split(":~:").next().unwrap_or_default();
+    let decoded = percent_encoding::percent_decode_str(anchor).decode_utf8_lossy();
+    decoded.is_empty()
+        || decoded.eq_ignore_ascii_case("top")
+        || anchors.iter().any(|anchor| *anchor == decoded)
+}
+
+/// Text of the document's `<title>`, with whitespace collapsed; `None` without one, or if
+/// it's blank.
+pub fn page_title(html: &str) -> Option<String> {
+    let document = select::document::Document::from(html);
+    let title = document
+        .find(select::predicate::Name("title"))
+        .next()?
+        .text();
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Inner HTML of each outermost `<template>` element, in document order. An unclosed template
+/// runs to the end of the document.
+fn template_contents(html: &str) -> Vec<&str> {
+    // ASCII lowercasing keeps byte offsets, so positions found here index into `html`
+    let lower = html.to_ascii_lowercase();
+    let tag_at = |pos: usize, tag: &str| {
+        lower[pos..].starts_with(tag)
+            && lower[pos + tag.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| c == '>' || c == '/' || c.is_ascii_whitespace())
+    };
+    let mut contents = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..].find('<') {
+        pos += offset;
+        if tag_at(pos, "<template") {
+            let Some(end) = lower[pos..].find('>') else {
+                break;
+            };
+            pos += end + 1;
+            if depth == 0 {
+                start = pos;
+            }
+            depth += 1;
+        } else if depth > 0 && tag_at(pos, "</template") {
+            depth -= 1;
+            if depth == 0 {
+                contents.push(&html[start..pos]);
+            }
+            pos += 1;
+        } else {
+            pos += 1;
+        }
+    }
+    if depth > 0 {
+        contents.push(&html[start..]);
+    }
+    contents
+}
+
+fn collect_links(
+    document: &select::document::Document,
+    sources: LinkSources,
+    extracted: &mut ExtractedLinks,
+) {
+    for node in document.find(select::predicate::Name("a")) {
+        if let Some(link) = node.attr("href") {
+            push_anchor(link, node.attr("rel"), sources, extracted);
+        }
+    }
+    for (tag, attribute, element) in RESOURCE_ATTRIBUTES {
+        for node in document.find(select::predicate::Name(tag)) {
+            if tag == "link" && names_an_origin(node.attr("rel")) {
+                continue;
+            }
+            if let Some(url) = node.attr(attribute) {
+                push_resource(url, element, extracted);
+            }
+        }
+    }
+
+    if !sources.htmx {
+        return;
+    }
+    for (attribute, method) in HTMX_ATTRIBUTES {
+        for node in document.find(select::predicate::Attr(attribute, ())) {
+            if let Some(url) = node.attr(attribute) {
+                push_htmx(attribute, url, method, sources, extracted);
+            }
+        }
+    }
+}
+
+/// HTMX attributes holding an endpoint URL, in the order their links are listed. HTMX accepts
+/// its attributes with a `data-` prefix too.
+pub(crate) const HTMX_ATTRIBUTES: [(&str, HtmxMethod); 4] = [
+    ("hx-get", HtmxMethod::Get),
+    ("data-hx-get", HtmxMethod::Get),
+    ("hx-post", HtmxMethod::Post),
+    ("data-hx-post", HtmxMethod::Post),
+];
+
+/// Elements and attributes that load a resource into the page, in the order their links are
+/// listed.
+pub(crate) const RESOURCE_ATTRIBUTES: [(&str, &str, LinkElement); 4] = [
+    ("img", "src", LinkElement::Img),
+    ("script", "src", LinkElement::Script),
+    ("link", "href", LinkElement::Link),
+    ("iframe", "src", LinkElement::Iframe),
+];
+
+/// Whether a `<meta>` tag's `name` addresses every crawler, `robots` in any case; directives for
+/// one bot, like `<meta name="googlebot">`, aren't addressed to us.
+pub(crate) fn is_robots_meta(name: Option<&str>) -> bool {
+    name.is_some_and(|name| name.trim().eq_ignore_ascii_case("robots"))
+}
+
+/// Whether a space-separated `rel` attribute lists `wanted`, ignoring case.
+pub(crate) fn has_rel(rel: Option<&str>, wanted: &str) -> bool {
+    rel.unwrap_or_default()
+        .split_ascii_whitespace()
+        .any(|rel| rel.eq_ignore_ascii_case(wanted))
+}
+
+/// Whether a `<link>` with this `rel` names an origin to connect to early, rather than a
+/// resource: there's nothing to check at `https://fonts.gstatic.com`.
+pub(crate) fn names_an_origin(rel: Option<&str>) -> bool {
+    has_rel(rel, "preconnect") || has_rel(rel, "dns-prefetch")
+}
+
+/// Adds an `<a href>` with the given `rel` like [`push_href`], unless it's `nofollow` and
+/// [`LinkSources::respect_nofollow`] is set.
+pub(crate) fn push_anchor(
+    link: &str,
+    rel: Option<&str>,
+    sources: LinkSources,
+    extracted: &mut ExtractedLinks,
+) {
+    if sources.respect_nofollow && has_rel(rel, "nofollow") {
+        log::debug!("Skipping rel=nofollow link: {}", link);
+        extracted
+            .skipped
+            .push((link.to_string(), SkipReason::Nofollow));
+    } else {
+        push_href(link, extracted);
+    }
+}
+
+/// Adds the URL of one of the [`RESOURCE_ATTRIBUTES`] to the resources to check, or to the
+/// skipped links if it's inline `data:` or another of the [`DENIED_PROTOCOLS`].
+pub(crate) fn push_resource(url: &str, element: LinkElement, extracted: &mut ExtractedLinks) {
+    if url.is_empty() {
+        return;
+    }
+    if has_scheme(url, &DENIED_PROTOCOLS) || has_scheme(url, &["javascript:"]) {
+        log::debug!("Skipping {:?} with denied protocol: {}", element, url);
+        extracted
+            .skipped
+            .push((url.to_string(), SkipReason::DeniedProtocol));
+    } else {
+        log::debug!("Adding {:?} resource: {}", element, url);
+        extracted.resources.push(FoundLink {
+            url: url.to_string(),
+            element,
+        });
+    }
+}
+
+/// Adds an `<a href>` value to the links to check, or to the skipped ones: the
+/// [`DENIED_PROTOCOLS`], and placeholders like `#` and any `javascript:` link, which runs a
+/// script rather than leading anywhere.
+pub(crate) fn push_href(link: &str, extracted: &mut ExtractedLinks) {
+    if has_scheme(link, &DENIED_PROTOCOLS) {
+        log::debug!("Skipping link with denied protocol: {}", link);
+        extracted
+            .skipped
+            .push((link.to_string(), SkipReason::DeniedProtocol));
+    } else if link == "#" || has_scheme(link, &["javascript:"]) {
+        log::debug!("Skipping denied link: {}", link);
+        extracted
+            .skipped
+            .push((link.to_string(), SkipReason::DeniedLink));
+    } else {
+        log::debug!("Adding link: {}", link);
+        extracted.links.push(link.to_string());
+    }
+}
+
+/// Adds the URL of one of the [`HTMX_ATTRIBUTES`] to the endpoints to check, or to the
+/// skipped links.
+pub(crate) fn push_htmx(
+    attribute: &str,
+    url: &str,
+    method: HtmxMethod,
+    sources: LinkSources,
+    extracted: &mut ExtractedLinks,
+) {
+    // An empty value means the current page
+    if url.is_empty() {
+        return;
+    }
+    if method == HtmxMethod::Post && !sources.htmx_post {
+        log::debug!("Skipping hx-post endpoint: {}", url);
+        extracted
+            .skipped
+            .push((url.to_string(), SkipReason::HtmxPost));
+    } else {
+        log::debug!("Adding {} endpoint: {}", attribute, url);
+        extracted.htmx.push((url.to_string(), method));
+    }
+}
+
+/// Targets of the `Link` response header entries whose `rel` is one of `rels`, e.g.
+/// `</page/2>; rel="next"`.
+///
+/// `rel` values are matched case-insensitively and may list several relations
+/// (`rel="next last"`). Targets are returned as written, to be resolved against the page URL
+/// like any other link.
+pub fn link_header_targets<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+    rels: &[&str],
+) -> Vec<String> {
+    let mut targets = Vec::new();
+    for value in values {
+        let mut rest = value;
+        // Each entry is `<target>` followed by `;`-separated params, up to the next `,` that
+        // isn't inside a quoted string
+        while let Some(start) = rest.find('<') {
+            let Some(end) = rest[start..].find('>') else {
+                break;
+            };
+            let target = &rest[start + 1..start + end];
+            rest = &rest[start + end + 1..];
+
+            let mut in_quotes = false;
+            let params_end = rest
+                .char_indices()
+                .find(|&(_, c)| {
+                    if c == '"' {
+                        in_quotes = !in_quotes;
+                    }
+                    c == ',' && !in_quotes
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            let params = &rest[..params_end];
+            rest = &rest[params_end..];
+
+            let matches = params.split(';').any(|param| {
+                let Some((name, value)) = param.split_once('=') else {
+                    return false;
+                };
+                name.trim().eq_ignore_ascii_case("rel")
+                    && value
+                        .trim()
+                        .trim_matches('"')
+                        .split_ascii_whitespace()
+                        .any(|rel| rels.iter().any(|r| rel.eq_ignore_ascii_case(r)))
+            });
+            if matches {
+                targets.push(target.trim().to_string());
+            }
+        }
+    }
+    targets
+}
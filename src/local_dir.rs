@@ -0,0 +1,43 @@
+use std::path::{Component, Path, PathBuf};
+use url::Url;
+
+/// Index file served for a URL that names a directory, like a static file server would.
+pub const INDEX_FILE: &str = "index.html";
+
+/// The file under `dir` that a static file server rooted at `dir` would serve for `url`.
+///
+/// Only the URL's path is used: `/docs/intro.html` maps to `dir/docs/intro.html`, and `/docs/`
+/// (or `/docs`, if `docs` is a directory) to `dir/docs/index.html`. Percent-encoded segments are
+/// decoded. Returns `None` for paths that would escape `dir`.
+pub fn file_for_url(dir: &Path, url: &Url) -> Option<PathBuf> {
+    let mut path = dir.to_path_buf();
+    for segment in url.path_segments()? {
+        let segment = percent_encoding::percent_decode_str(segment)
+            .decode_utf8()
+            .ok()?;
+        // A decoded segment may itself contain separators (`%2F`), so check what it adds
+        for component in Path::new(segment.as_ref()).components() {
+            match component {
+                Component::Normal(name) => path.push(name),
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+    }
+    if url.path().ends_with('/') || path.is_dir() {
+        path.push(INDEX_FILE);
+    }
+    Some(path)
+}
+
+/// Content type a static file server would send for `path`, for the types links are extracted
+/// from. Other files are only checked for existence.
+pub fn content_type_for(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => Some("text/html"),
+        "json" => Some("application/json"),
+        "pdf" => Some("application/pdf"),
+        _ => None,
+    }
+}
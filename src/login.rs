@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Context};
+use url::Url;
+
+/// A login form to submit before crawling, so the crawl runs with the session it sets up.
+///
+/// The form is posted with the client the crawl will use, which must have its cookie store
+/// enabled to keep the session cookie.
+pub struct LoginForm {
+    pub url: Url,
+    pub fields: Vec<(String, String)>,
+    /// Name of a hidden input on the login page holding a CSRF token. When set, the login page is
+    /// fetched first and the token is posted along with `fields`.
+    pub csrf_field: Option<String>,
+}
+
+impl LoginForm {
+    /// Posts the form. Only the HTTP status is checked: a site that answers a failed login with
+    /// `200 OK` and an error message can't be told apart from a successful one.
+    pub async fn submit(&self, client: &reqwest::Client) -> Result<(), anyhow::Error> {
+        let mut fields = self.fields.clone();
+        if let Some(name) = &self.csrf_field {
+            let page = client
+                .get(self.url.clone())
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            let token = find_input_value(&page, name)
+                .ok_or_else(|| anyhow!("no input named '{}' on {}", name, self.url))?;
+            fields.push((name.clone(), token));
+        }
+
+        client
+            .post(self.url.clone())
+            .form(&fields)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("logging in at {}", self.url))?;
+        Ok(())
+    }
+}
+
+/// The value of the first `<input>` with the given name, e.g. a hidden CSRF token field.
+pub fn find_input_value(html: &str, name: &str) -> Option<String> {
+    let document = select::document::Document::from(html);
+    let value = document
+        .find(select::predicate::Name("input"))
+        .find(|node| node.attr("name") == Some(name))?
+        .attr("value")
+        .unwrap_or_default()
+        .to_string();
+    Some(value)
+}
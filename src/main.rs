@@ -1,64 +1,1224 @@
-use serde::{Deserialize, Serialize};
-use serde_json;
-use std::env;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser};
+use find_broken_links::accept::AcceptHeader;
+use find_broken_links::access_log::AccessLogParser;
+use find_broken_links::config::CrawlConfig;
+use find_broken_links::connections;
+use find_broken_links::crawler::{
+    basic_auth, redirect_policy, CrawlMessage, Crawler, Finding, DEFAULT_MAX_BODY_BYTES,
+};
+use find_broken_links::deadline::{self, DEADLINE_ENV};
+use find_broken_links::debug_channel;
+use find_broken_links::events::{CrawlEvent, EventFormat, EventWriter};
+use find_broken_links::ignore::{IgnoreList, IgnorePattern};
+use find_broken_links::json_links::JsonPath;
+use find_broken_links::login::LoginForm;
+use find_broken_links::normalize::{UrlNormalizer, DEFAULT_INDEX_FILES};
+use find_broken_links::pacing::SlowStart;
+use find_broken_links::page_cache::PageCache;
+use find_broken_links::progress::{Periodic, ProgressLog};
+use find_broken_links::recheck::{self, RecheckReport, StatusFilter};
+use find_broken_links::report::{
+    add_finding, copy_report, csv_report, group_by_referrer, save_json, save_text, text_line,
+    text_report, url_report, CheckedCsvWriter, GroupBy, JsonlWriter, LycheeStats, NotFoundError,
+    OutputFormat, Report, ReportHeader, ResultsDir,
+};
+use find_broken_links::retry::RetryPolicy;
+use find_broken_links::safety::UnsafeUrlFilter;
+use find_broken_links::scope::ScopeEntry;
+use find_broken_links::soft_404::Soft404Detector;
+use find_broken_links::url_filter::UrlPatternFilter;
+use find_broken_links::user_agent::UserAgentPicker;
+use find_broken_links::warnings::{Strictness, WarningCategory};
+use regex::Regex;
+use reqwest::cookie::Jar;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use serde::Serialize;
 use std::fs::{self, File};
-use std::io::Write;
-use std::path::Path;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::signal;
-use url::{ParseError, Url};
-mod debug_channel;
+use tokio_util::sync::CancellationToken;
+use url::Url;
+mod check;
+mod syslog_sink;
+
+/// Exit code used when `--strict` is on and errors (or promoted warnings) were found.
+const EXIT_STRICT_FAILURE: i32 = 2;
+
+/// Exit code used when `--max-duration` or `FBL_DEADLINE` cut the crawl short.
+const EXIT_DEADLINE: i32 = 4;
+
+/// Exit code used when `--fail-on-broken` is on and more than `--fail-threshold` broken links
+/// were found.
+const EXIT_BROKEN_LINKS: i32 = 5;
+
+/// Root URL for `--local-dir` when none is given.
+const LOCAL_DIR_ROOT_URL: &str = "http://localhost/";
+
+/// Crawl a site and collect the URLs that return an error status, like 404 or 500.
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// URL to start crawling from; with --local-dir, the URL the directory will be served at
+    #[arg(
+        required_unless_present_any = ["local_dir", "recheck_from", "seed", "config"],
+        value_parser = parse_url
+    )]
+    root_url: Option<Url>,
+
+    /// Read settings from this JSON file; options on the command line override it
+    #[arg(long, value_name = "PATH")]
+    config: Option<std::path::PathBuf>,
+
+    /// Also follow links whose domain matches this string: a label like `shop`, or a domain
+    /// like `example.com` and its subdomains
+    fuzzy_match_string: Option<String>,
+
+    /// Same as the positional fuzzy match string
+    #[arg(
+        long = "fuzzy",
+        value_name = "STRING",
+        conflicts_with_all = ["fuzzy_match_string", "scope"]
+    )]
+    fuzzy: Option<String>,
+
+    /// Also follow links to subdomains of the root URL's domain (blog.example.com for
+    /// example.com)
+    #[arg(long, conflicts_with = "scope")]
+    include_subdomains: bool,
+
+    /// Write the report here instead of ./results/<hostname>.json (or .txt for --format text)
+    #[arg(long, value_name = "PATH")]
+    output: Option<std::path::PathBuf>,
+
+    /// Directory for the report, warnings and --report-all files
+    #[arg(long, value_name = "DIR", default_value = "./results")]
+    output_dir: PathBuf,
+
+    /// Put the crawl's start time after the hostname in file names
+    /// (./results/<hostname>-<unix time>.json) to keep earlier reports, and copy the report to
+    /// <hostname>-latest.json
+    #[arg(long, conflicts_with = "output")]
+    timestamped: bool,
+
+    /// Only follow links on this host, and scheme and port if given, instead of the root URL's
+    /// domain; pass several times to crawl several services
+    #[arg(
+        long,
+        value_name = "[SCHEME://]HOST[:PORT]",
+        conflicts_with = "fuzzy_match_string"
+    )]
+    scope: Vec<ScopeEntry>,
+
+    /// Only follow links on the site whose path starts with this prefix, e.g. /docs; without a
+    /// value, the root URL's path
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        num_args = 0..=1,
+        default_missing_value = ""
+    )]
+    path_prefix: Option<String>,
+
+    /// With --path-prefix, still check the links elsewhere on the site, without following them
+    #[arg(long, requires = "path_prefix")]
+    check_out_of_scope: bool,
+
+    /// Only check URLs matching this regex (anywhere in the absolute URL); repeatable
+    #[arg(long, value_name = "REGEX")]
+    include: Vec<Regex>,
+
+    /// Don't check URLs matching this regex, even if they match --include; repeatable, e.g.
+    /// `--exclude '/search\?'`
+    #[arg(long, value_name = "REGEX")]
+    exclude: Vec<Regex>,
+
+    /// User-Agent header to send; pass several times to rotate between them
+    #[arg(long = "user-agent", value_name = "UA")]
+    user_agents: Vec<String>,
+
+    /// Send every request through this proxy (http://, https:// or socks5://, with any
+    /// USER:PASSWORD@ in it) instead of HTTP_PROXY / HTTPS_PROXY
+    #[arg(long, value_name = "URL", value_parser = parse_proxy)]
+    proxy: Option<reqwest::Proxy>,
+
+    /// Accept invalid TLS certificates (self-signed, expired, for another host), as on a
+    /// staging site; otherwise their links are reported broken with a "tls error"
+    #[arg(long)]
+    insecure: bool,
+
+    /// When rotating user agents, pick one per host and reuse it for every request to that host
+    #[arg(long)]
+    ua_per_host: bool,
+
+    /// Treat every warning as an error and exit with status 2 if any errors were found
+    #[arg(long)]
+    strict: bool,
+
+    /// Only promote the given warning categories to errors (comma-separated)
+    #[arg(long, value_delimiter = ',', value_name = "CATEGORIES")]
+    strict_categories: Vec<WarningCategory>,
+
+    /// Exit with status 5 if any broken links were found, e.g. to fail a CI job
+    #[arg(long)]
+    fail_on_broken: bool,
+
+    /// With --fail-on-broken, only fail if more than this many broken links were found
+    #[arg(
+        long,
+        default_value_t = 0,
+        value_name = "N",
+        requires = "fail_on_broken"
+    )]
+    fail_threshold: usize,
+
+    /// Don't report broken links matching this: a URL, a glob with `*`, or a regex between
+    /// slashes (/regex/); repeatable
+    #[arg(long = "ignore-url", value_name = "PATTERN")]
+    ignore_urls: Vec<IgnorePattern>,
+
+    /// Read more --ignore-url patterns from this file, one per line; # starts a comment
+    #[arg(long, value_name = "PATH")]
+    ignore_file: Option<PathBuf>,
+
+    /// Shape of the broken-links report: one entry per broken URL, or per page that links to them
+    #[arg(long, value_enum, default_value_t = GroupBy::Url)]
+    group_by: GroupBy,
+
+    /// Same as --group-by referrer: one entry per page, listing the broken links on it
+    #[arg(long, conflicts_with = "group_by")]
+    group_by_source: bool,
+
+    /// In the report grouped by URL, give a broken URL an entry per page it's on instead of one
+    /// listing them all
+    #[arg(long)]
+    no_dedupe: bool,
+
+    /// Save the broken links as a bare JSON array, as before reports had a version, the crawled
+    /// URL, a timestamp and a summary
+    #[arg(long)]
+    legacy_output: bool,
+
+    /// Format of the broken-links report; lychee writes lychee's JSON stats schema instead
+    #[arg(long, value_enum, default_value_t = OutputFormat::Native)]
+    format: OutputFormat,
+
+    /// Suggest likely fixes for broken links (e.g. a known-good URL differing only by case)
+    #[arg(long)]
+    suggest_fixes: bool,
+
+    /// Append a line of crawl progress (pages, queue size, broken links, rate) to this file
+    #[arg(long, value_name = "PATH")]
+    progress_log: Option<std::path::PathBuf>,
+
+    /// Don't print a progress line to stderr every --progress-interval
+    #[arg(long)]
+    quiet: bool,
+
+    /// How often to write a --progress-log line, and print one to stderr
+    #[arg(long, default_value = "10s", value_parser = humantime::parse_duration, value_name = "DURATION")]
+    progress_interval: Duration,
+
+    /// Also write a --progress-log line, and print one to stderr, every N pages crawled
+    #[arg(long, value_name = "N")]
+    progress_every_pages: Option<u64>,
+
+    /// Save the results file every DURATION during the crawl rather than only at the end, so a
+    /// crash or kill leaves a recent snapshot
+    #[arg(long, value_parser = humantime::parse_duration, value_name = "DURATION")]
+    flush_interval: Option<Duration>,
+
+    /// Also save the results file every N pages crawled
+    #[arg(long, value_name = "N")]
+    flush_every_pages: Option<u64>,
+
+    /// Also send broken links and the final summary to the system log (Unix only)
+    #[arg(long)]
+    syslog: bool,
+
+    /// Syslog facility to log under
+    #[arg(long, default_value = "user", value_name = "FACILITY")]
+    syslog_facility: String,
+
+    /// Syslog tag (process name) to log under
+    #[arg(long, default_value = "find-broken-links", value_name = "TAG")]
+    syslog_tag: String,
+
+    /// Give up extracting links from a page after this many milliseconds (0 = no limit)
+    #[arg(long, default_value_t = 5000, value_name = "MS")]
+    parse_timeout_ms: u64,
+
+    /// Give up on a request after this many seconds and report the link as broken (0 = no limit)
+    #[arg(long, default_value_t = 30, value_name = "SECS")]
+    timeout_secs: u64,
+
+    /// Stop crawling once this many broken links have been found
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    max_errors: Option<usize>,
+
+    /// Stop crawling once this many response body bytes have been downloaded in total
+    #[arg(long, value_name = "BYTES")]
+    max_bytes: Option<u64>,
+
+    /// Stop downloading a page once its body is over this many bytes and warn about it instead
+    /// of following its links; 0 for no limit
+    #[arg(long, default_value_t = DEFAULT_MAX_BODY_BYTES, value_name = "BYTES")]
+    max_body_bytes: u64,
+
+    /// Stop crawling once this many URLs have been requested
+    #[arg(long, value_name = "N")]
+    max_pages: Option<u64>,
+
+    /// Crawl breadth-first, shallow pages before deep ones, instead of depth-first
+    #[arg(long)]
+    bfs: bool,
+
+    /// Retries per HTTP status, e.g. 429:5,503:3,500:1 (replaces the built-in policy)
+    #[arg(long, value_name = "STATUS:RETRIES,...")]
+    retry_status: Option<RetryPolicy>,
+
+    /// Retries for requests that get no response (refused or dropped connections, timeouts) and
+    /// for 5xx statuses --retry-status doesn't list
+    #[arg(long, default_value_t = 0, value_name = "N")]
+    max_retries: u32,
+
+    /// Delay before the first retry, doubled for each one after it, plus some jitter
+    #[arg(long, default_value = "500ms", value_parser = humantime::parse_duration, value_name = "DURATION")]
+    retry_delay: Duration,
+
+    /// Stop the crawl after this long and save what was found so far, exiting with code 4. The
+    /// FBL_DEADLINE environment variable sets one too; the earlier of the two applies
+    #[arg(long, value_parser = humantime::parse_duration, value_name = "DURATION")]
+    max_duration: Option<Duration>,
+
+    /// --max-duration in seconds, for schedulers that pass a plain number
+    #[arg(long, value_name = "SECONDS", conflicts_with = "max_duration")]
+    max_duration_secs: Option<u64>,
+
+    /// After CTRL+C, how long to wait for in-flight requests before exiting anyway
+    #[arg(long, default_value = "10s", value_parser = humantime::parse_duration, value_name = "DURATION")]
+    shutdown_timeout: Duration,
+
+    /// Honor X-Robots-Tag headers: don't follow links on nofollow pages, report noindex pages
+    #[arg(long)]
+    respect_robots_header: bool,
+
+    /// Honor <meta name="robots"> tags: don't follow links on nofollow pages, report noindex
+    /// pages
+    #[arg(long)]
+    respect_robots_meta: bool,
+
+    /// Warn about every link that only works through a redirect, with where it ends up
+    #[arg(long)]
+    flag_redirects: bool,
+
+    /// Also crawl rel="next" and rel="prev" targets of Link response headers (header-based pagination)
+    #[arg(long)]
+    follow_link_header: bool,
+
+    /// Don't follow links on a page whose <link rel="canonical"> names another URL on the site;
+    /// crawl that URL instead, and skip other query strings on it once one was a duplicate
+    #[arg(long)]
+    follow_canonical: bool,
+
+    /// Also print each broken link to stdout as it's found, as `STATUS URL (from REFERRER)`
+    #[arg(long)]
+    stdout: bool,
+
+    /// Stream crawl events (pages checked, broken links, warnings, skipped links, the end of the
+    /// crawl) to stdout as they happen, for live dashboards
+    #[arg(long, value_name = "FORMAT", conflicts_with_all = ["stdout", "dry_run"])]
+    events: Option<EventFormat>,
+
+    /// Also write every checked URL, broken or not, to ./results/<hostname>-all.csv
+    #[arg(long)]
+    report_all: bool,
+
+    /// Write every link that was deliberately not checked, with the reason, to this JSON Lines file
+    #[arg(long, value_name = "PATH")]
+    skipped_out: Option<std::path::PathBuf>,
+
+    /// Also write the end-of-crawl summary (pages, links, broken links by status, time) to this
+    /// JSON file
+    #[arg(long, value_name = "PATH")]
+    summary_json: Option<std::path::PathBuf>,
+
+    #[command(flatten)]
+    headers: HeaderArgs,
+
+    #[command(flatten)]
+    login: LoginArgs,
+
+    /// Don't request links that look like they trigger an action (delete, logout, ...); report them
+    #[arg(long)]
+    safe_only: bool,
+
+    /// Glob matched against URL path and query for --safe-only, e.g. '*delete*'; pass several
+    /// times for more (replaces the built-in list)
+    #[arg(
+        long = "unsafe-pattern",
+        value_name = "PATTERN",
+        requires = "safe_only"
+    )]
+    unsafe_patterns: Vec<String>,
+
+    /// When a page returns 403, retry it once with a full set of browser headers before reporting it
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, value_name = "BOOL")]
+    ua_fallback: bool,
+
+    /// Only report 404s as broken; any other error status stops the crawl with an error
+    #[arg(long)]
+    only_404: bool,
+
+    /// Also check links to other sites, with one HEAD request each (GET if HEAD isn't allowed),
+    /// without crawling them
+    #[arg(long)]
+    check_external: bool,
+
+    /// With --check-external, contact at most this many other sites; links to further hosts are
+    /// skipped and logged
+    #[arg(long, value_name = "N", requires = "check_external")]
+    max_distinct_hosts: Option<usize>,
+
+    /// With --check-external, requests to other sites in flight at once, on top of
+    /// --concurrency; they're checked alongside the crawl
+    #[arg(long, default_value_t = 4, value_name = "N")]
+    external_concurrency: usize,
+
+    /// With --check-external, milliseconds to leave between the starts of requests to each
+    /// other site
+    #[arg(long, default_value_t = 500, value_name = "N")]
+    external_delay_ms: u64,
+
+    /// Also check links in JSON responses: every string value that is a full http(s) URL
+    #[arg(long)]
+    json_links: bool,
+
+    /// Check the strings at this JSONPath in JSON responses instead (e.g. '$.items[*].url');
+    /// pass several times for more. Implies --json-links
+    #[arg(long, value_name = "JSONPATH")]
+    json_url_paths: Vec<JsonPath>,
+
+    /// Report a JSON response as broken, even with a 2xx status, when this JSONPath selects a
+    /// non-null value (e.g. '$.error'); pass several times for more
+    #[arg(long, value_name = "JSONPATH")]
+    body_error_jsonpath: Vec<JsonPath>,
+
+    /// Report HTML pages that load with a 2xx but read like an error page ("Page not found") as
+    /// broken
+    #[arg(long)]
+    soft_404: bool,
+
+    /// Regex that marks a page's title or text as a soft 404, case-insensitively, instead of
+    /// the defaults; pass several times for more
+    #[arg(long = "soft-404-pattern", value_name = "REGEX", requires = "soft_404")]
+    soft_404_patterns: Vec<String>,
+
+    /// Fetch a page that can't exist first, and only report pages that look like it
+    #[arg(long, requires = "soft_404")]
+    soft_404_probe: bool,
+
+    /// Treat /dir/index.html and /dir/ as the same page, crawling it only once
+    #[arg(long)]
+    collapse_index: bool,
+
+    /// Index file names for --collapse-index (comma-separated)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_values = DEFAULT_INDEX_FILES,
+        value_name = "NAMES",
+        requires = "collapse_index"
+    )]
+    index_files: Vec<String>,
+
+    /// Collapse repeated slashes in link paths (/foo//bar -> /foo/bar) and warn about each one
+    #[arg(long)]
+    collapse_slashes: bool,
+
+    /// Treat URLs that differ only in their query string as the same page, crawling it only once
+    #[arg(long, alias = "strip-all-query")]
+    strip_query: bool,
+
+    /// Query parameters to leave out when telling pages apart, like tracking parameters
+    /// (comma-separated, e.g. utm_source,utm_medium)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "NAMES",
+        conflicts_with = "strip_query"
+    )]
+    ignore_query_params: Vec<String>,
+
+    /// Treat /page and /page/ as the same page, crawling it only once
+    #[arg(long)]
+    ignore_trailing_slash: bool,
+
+    /// Treat queries with the same parameters in another order as the same page
+    #[arg(long, conflicts_with = "strip_query")]
+    sort_query: bool,
+
+    /// Start slowly: space out the first requests and ramp up to full speed
+    #[arg(long)]
+    slow_start: bool,
+
+    /// Gap between the first two requests with --slow-start
+    #[arg(long, default_value = "2s", value_parser = humantime::parse_duration, value_name = "DURATION", requires = "slow_start")]
+    slow_start_delay: Duration,
+
+    /// Number of requests over which --slow-start ramps up to full speed
+    #[arg(long, default_value_t = 20, value_name = "N", requires = "slow_start")]
+    slow_start_requests: u32,
+
+    /// Milliseconds to leave between the starts of requests to the same host; other hosts
+    /// aren't held up
+    #[arg(long, default_value_t = 0, value_name = "N")]
+    delay_ms: u64,
+
+    /// Accept header to send, e.g. 'application/json', or 'auto' to pick one per link from its
+    /// file extension (text/html for pages, image/* for images, ...)
+    #[arg(long, value_name = "VALUE")]
+    accept: Option<AcceptHeader>,
+
+    /// Warn about HTML pages whose body is shorter than this many bytes (e.g. a broken render)
+    #[arg(long, value_name = "BYTES")]
+    min_content_length: Option<usize>,
+
+    /// Warn about pages that take longer than this many milliseconds to load, request and body
+    #[arg(long, value_name = "MS")]
+    report_slow_ms: Option<u64>,
+
+    /// Also check URLs in HTMX hx-get attributes, and hx-post ones unless --safe-only is on
+    #[arg(long)]
+    htmx_links: bool,
+
+    /// Don't check or follow <a rel="nofollow"> links
+    #[arg(long)]
+    respect_nofollow: bool,
+
+    /// Report links whose #fragment isn't the id of anything on the page they point to
+    #[arg(long)]
+    check_fragments: bool,
+
+    /// Also check links inside <template> elements (client-side markup that may never be rendered)
+    #[arg(long)]
+    scan_templates: bool,
+
+    /// Resolve the hosts of the starting URLs in parallel before crawling (best-effort)
+    #[arg(long)]
+    dns_prefetch: bool,
+
+    /// Number of requests in flight at once
+    #[arg(
+        long,
+        default_value_t = 8,
+        value_name = "N",
+        conflicts_with = "auto_concurrency"
+    )]
+    concurrency: usize,
+
+    /// Halve --concurrency while many recent responses are server errors, restore it as they subside
+    #[arg(long, conflicts_with = "auto_concurrency")]
+    backoff_on_errors: bool,
+
+    /// Adjust the number of parallel requests to the server's latency and error rate
+    #[arg(long)]
+    auto_concurrency: bool,
+
+    /// Upper bound for --auto-concurrency
+    #[arg(
+        long,
+        default_value_t = 16,
+        value_name = "N",
+        requires = "auto_concurrency"
+    )]
+    max_concurrency: usize,
+
+    /// Most requests in flight at once, whatever the concurrency, with idle connections kept to
+    /// a share of it; 0 for no cap. Defaults to what fits under the open file limit (`ulimit -n`)
+    #[arg(long, value_name = "N")]
+    max_connections: Option<usize>,
+
+    /// Also start crawling from this URL, for pages nothing else links to; repeatable. Without a
+    /// root URL the first seed is the root. Seeds on other domains are only checked
+    #[arg(long, value_name = "URL", value_parser = parse_url)]
+    seed: Vec<Url>,
+
+    /// Findings the crawl can get ahead of the report writer by before it waits; a warning is
+    /// logged if it keeps waiting
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    channel_buffer: usize,
+
+    /// Only fetch the root URL and seeds, and print the links on them that would be fetched
+    /// and the ones that would be skipped, with why; nothing else is fetched and no report is
+    /// written
+    #[arg(long, conflicts_with_all = ["recheck_from", "report_all"])]
+    dry_run: bool,
+
+    /// Also crawl the URLs in the site's /sitemap.xml, following sitemap index files
+    #[arg(long)]
+    use_sitemap: bool,
+
+    /// Also crawl every same-host path requested in this access log (Common or Combined format)
+    #[arg(long, value_name = "PATH")]
+    access_log: Option<std::path::PathBuf>,
+
+    /// Regex for --access-log lines, with the requested path in a (?P<path>...) group
+    #[arg(long, value_name = "REGEX", value_parser = AccessLogParser::new, requires = "access_log")]
+    access_log_pattern: Option<AccessLogParser>,
+
+    /// Check a directory of built HTML files instead of a live site, e.g. before deploying it
+    #[arg(long, value_name = "DIR")]
+    local_dir: Option<std::path::PathBuf>,
+
+    /// Keep each page's ETag or Last-Modified and links in a file per site in this directory, and
+    /// on the next crawl ask for pages only if they changed, reusing the links of unchanged ones
+    #[arg(long, value_name = "DIR", conflicts_with = "local_dir")]
+    cache_dir: Option<std::path::PathBuf>,
+
+    /// Neither read nor update the --cache-dir cache, fetching every page in full
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Instead of crawling, check the broken links in this results file again and report which
+    /// are fixed
+    #[arg(long, value_name = "PATH", conflicts_with = "local_dir")]
+    recheck_from: Option<std::path::PathBuf>,
+
+    /// Only recheck links that had this status or status class, e.g. 404 or 4xx; pass several
+    /// times for more
+    #[arg(long, value_name = "STATUS", requires = "recheck_from")]
+    recheck_status: Vec<StatusFilter>,
+
+    /// Extract hyperlinks from PDF documents and check them too
+    #[cfg(feature = "pdf")]
+    #[arg(long)]
+    check_pdf_links: bool,
+
+    /// Also write every checked URL (status, timing, content type, referrer) to this SQLite database
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "PATH")]
+    db: Option<std::path::PathBuf>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    Check(check::CheckArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct HeaderArgs {
+    /// Header to send with every request, as 'NAME: VALUE'; pass several times for more headers
+    #[arg(long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// Send HTTP Basic Auth credentials with every request, as USER:PASSWORD
+    #[arg(long, value_name = "USER:PASSWORD")]
+    basic_auth: Option<String>,
+
+    /// Cookie to start the crawl with on the root URL's host, as NAME=VALUE; pass several times
+    /// for more cookies
+    #[arg(long = "cookie", value_name = "NAME=VALUE", value_parser = parse_form_field)]
+    cookies: Vec<(String, String)>,
+}
+
+impl HeaderArgs {
+    /// The headers to send, with the Basic Auth credentials marked sensitive so they're never
+    /// logged.
+    fn header_map(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            headers.append(name.clone(), value.clone());
+        }
+        if let Some(credentials) = &self.basic_auth {
+            let (user, password) = credentials.split_once(':').unwrap_or((credentials, ""));
+            headers.insert(AUTHORIZATION, basic_auth(user, password));
+        }
+        headers
+    }
+
+    /// A cookie jar holding the `--cookie` cookies, for every path on `url`'s host.
+    fn cookie_jar(&self, url: &Url) -> Arc<Jar> {
+        let jar = Jar::default();
+        for (name, value) in &self.cookies {
+            jar.add_cookie_str(&format!("{}={}; Path=/", name, value), url);
+        }
+        Arc::new(jar)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct LoginArgs {
+    /// Submit a login form at this URL first, and send the session cookie it sets from then on
+    #[arg(long, value_name = "URL")]
+    login_url: Option<Url>,
+
+    /// Form field to submit with --login-url, as NAME=VALUE; pass several times for more fields
+    #[arg(long, value_name = "NAME=VALUE", value_parser = parse_form_field, requires = "login_url")]
+    login_fields: Vec<(String, String)>,
+
+    /// Fetch the login page first and submit the value of this hidden input (e.g. a CSRF token)
+    #[arg(long, value_name = "NAME", requires = "login_url")]
+    login_csrf_field: Option<String>,
+}
+
+impl LoginArgs {
+    /// Submits the login form if one was configured, returning the logged-in client.
+    ///
+    /// A logged-in session needs one client for everything so the session cookie is kept. It
+    /// sends a single user agent, so --user-agent rotation doesn't apply. `headers` are sent
+    /// with the login requests too, to get past the same gate as the crawl, and they go through
+    /// `proxy` and accept invalid certificates with `insecure` like the crawl's. The session
+    /// cookie goes into `cookies`, next to any set up front.
+    async fn log_in(
+        self,
+        user_agent: &str,
+        headers: HeaderMap,
+        cookies: Arc<Jar>,
+        proxy: Option<reqwest::Proxy>,
+        insecure: bool,
+    ) -> Result<Option<reqwest::Client>, anyhow::Error> {
+        let Some(login_url) = self.login_url else {
+            return Ok(None);
+        };
+        let mut builder = reqwest::Client::builder()
+            .cookie_provider(cookies)
+            .user_agent(user_agent)
+            .default_headers(headers)
+            .redirect(redirect_policy())
+            .danger_accept_invalid_certs(insecure);
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy);
+        }
+        let client = builder.build()?;
+        let form = LoginForm {
+            url: login_url,
+            fields: self.login_fields,
+            csrf_field: self.login_csrf_field,
+        };
+        log::info!("Logging in at {}", form.url);
+        form.submit(&client).await?;
+        Ok(Some(client))
+    }
+}
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
-    // Collect command line arguments
-    let args: Vec<String> = env::args().collect();
+async fn main() -> ExitCode {
+    // Logs go to stderr, so --stdout output can be piped on its own
+    env_logger::Builder::from_default_env()
+        .target(env_logger::Target::Stderr)
+        .init();
+    // Printed with Display, not the Debug of returning it from main, so a typo isn't a wall of
+    // quotes and struct names
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    // Expect at least one argument for the domain
-    if args.len() < 2 {
-        return Err("Usage: find-broken-links <domain> [<fuzzy_match_string>]".into());
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches)?;
+    if let Some(path) = &args.config {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("can't read config file {}: {}", path.display(), e))?;
+        let config = CrawlConfig::from_json(&json)
+            .map_err(|e| format!("invalid config file {}: {}", path.display(), e))?;
+        apply_config(&mut args, config, &matches)?;
+    }
+    // A zero-size channel can't hold anything; checked after the config file, which can set it
+    if args.channel_buffer == 0 {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--channel-buffer must be at least 1",
+            )
+            .exit();
+    }
+    if args.group_by_source {
+        args.group_by = GroupBy::Referrer;
+    }
+    if let Some(Command::Check(check_args)) = args.command {
+        std::process::exit(check::run(check_args).await?);
     }
 
-    let root_url = args[1].clone();
+    // Counted from startup, so whatever runs before the crawl (logging in, ...) counts too
+    let env_deadline = match std::env::var(DEADLINE_ENV) {
+        Ok(value) => Some(deadline::time_left(&value, SystemTime::now())?),
+        Err(_) => None,
+    };
+    let max_duration = args
+        .max_duration
+        .or(args.max_duration_secs.map(Duration::from_secs))
+        .into_iter()
+        .chain(env_deadline)
+        .min();
+    let crawl_deadline = max_duration.map(|limit| tokio::time::Instant::now() + limit);
+
+    let recheck_links = match &args.recheck_from {
+        Some(path) => {
+            let previous = recheck::load_report(&fs::read_to_string(path)?)?;
+            let wanted = |error: &NotFoundError| {
+                let status = error.response_status();
+                args.recheck_status.is_empty()
+                    || args
+                        .recheck_status
+                        .iter()
+                        .any(|filter| filter.matches(status))
+            };
+            Some(previous.into_iter().filter(wanted).collect::<Vec<_>>())
+        }
+        None => None,
+    };
+
+    let root_url = match (args.root_url, &args.local_dir, &recheck_links) {
+        (Some(root_url), _, _) => root_url,
+        (None, Some(_), _) => Url::parse(LOCAL_DIR_ROOT_URL)?,
+        (None, None, _) if !args.seed.is_empty() => args.seed.remove(0),
+        // Only used for the user agent and such, nothing is crawled
+        (None, None, Some(links)) => match links.first() {
+            Some(link) => Url::parse(&link.url)
+                .map_err(|e| format!("invalid URL '{}' in the results file: {}", link.url, e))?,
+            None => {
+                log::info!("No broken links to recheck");
+                return Ok(());
+            }
+        },
+        // Only possible with a config file, clap catches the rest
+        (None, None, None) => Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "no root URL given: pass one, a --seed, or a root_url in the --config file",
+            )
+            .exit(),
+    };
+    let local_dir = match &args.local_dir {
+        Some(dir) if !dir.is_dir() => {
+            return Err(format!("{} is not a directory", dir.display()).into())
+        }
+        Some(dir) => Some(fs::canonicalize(dir)?),
+        None => None,
+    };
+    let page_cache = match &args.cache_dir {
+        Some(dir) if !args.no_cache => {
+            let host = root_url.host_str().unwrap_or("local");
+            Some(Arc::new(PageCache::open(
+                dir.join(format!("{}.json", host)),
+            )))
+        }
+        _ => None,
+    };
     // This is the fuzzy match string
-    let fuzzy_match_string = match args.get(2).cloned() {
-        Some(s) => Some(s),
+    let fuzzy_match_string = args.fuzzy.or(args.fuzzy_match_string);
+    let user_agents = UserAgentPicker::new(args.user_agents, args.ua_per_host);
+    let strictness = Strictness::new(args.strict, args.strict_categories);
+    let mut syslog = if args.syslog {
+        match syslog_sink::SyslogSink::connect(&args.syslog_facility, &args.syslog_tag) {
+            Ok(sink) => Some(sink),
+            Err(e) => {
+                log::warn!("Syslog output disabled: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut skipped_out = match &args.skipped_out {
+        Some(path) => Some(BufWriter::new(File::create(path)?)),
         None => None,
     };
 
-    // Validate the URL format
-    let parsed_url = Url::parse(&root_url).expect("Invalid URL format provided");
+    let parsed_url = root_url.clone();
 
     // Extract the hostname from the parsed URL
     let hostname = parsed_url.host_str().ok_or("Invalid hostname")?.to_string();
+    let results = ResultsDir::new(
+        args.output_dir.clone(),
+        &hostname,
+        args.timestamped.then(SystemTime::now),
+    );
 
-    log::info!("Starting to crawl: {}", parsed_url.to_string());
+    if recheck_links.is_none() {
+        log::info!("Starting to crawl: {}", parsed_url.to_string());
+    }
 
     // send/receive channels for urls that are found to be emitted.
-    let mut debug_channel = debug_channel::DebugChannel::<Option<String>>::new(5);
+    let mut debug_channel = debug_channel::DebugChannel::<CrawlMessage>::new(args.channel_buffer);
 
     // Clone the sender to move into the async block
     let debug_sender = debug_channel.sender(); // This is a DebugSender with tracking
                                                // Spawn the crawler task
-    tokio::spawn(async move {
-        if let Err(e) = crawl_and_collect_404s(parsed_url, debug_sender, fuzzy_match_string).await {
+    let soft_404 = match (args.soft_404, args.soft_404_patterns.is_empty()) {
+        (false, _) => None,
+        (true, true) => Some(Soft404Detector::default()),
+        (true, false) => Some(
+            Soft404Detector::new(&args.soft_404_patterns)
+                .map_err(|e| format!("invalid --soft-404-pattern: {}", e))?,
+        ),
+    }
+    .map(|detector| detector.probe(args.soft_404_probe));
+    let headers = args.headers.header_map();
+    let cookies = args.headers.cookie_jar(&parsed_url);
+    let client = args
+        .login
+        .log_in(
+            user_agents.pick(&parsed_url),
+            headers.clone(),
+            Arc::clone(&cookies),
+            args.proxy.clone(),
+            args.insecure,
+        )
+        .await?;
+
+    let mut seed_urls = args.seed;
+    if let Some(path) = &args.access_log {
+        let parser = args.access_log_pattern.unwrap_or_default();
+        let seeds = parser.seed_urls(BufReader::new(File::open(path)?), &parsed_url)?;
+        log::info!("Seeding {} paths from {}", seeds.len(), path.display());
+        seed_urls.extend(seeds);
+    }
+
+    let mut ignore_broken = IgnoreList::new(args.ignore_urls);
+    if let Some(path) = &args.ignore_file {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("can't read ignore file {}: {}", path.display(), e))?;
+        let patterns = IgnoreList::parse(&text)
+            .map_err(|e| format!("invalid ignore file {}: {}", path.display(), e))?;
+        ignore_broken.extend(patterns);
+    }
+
+    let mut normalizer = UrlNormalizer::default()
+        .collapse_slashes(args.collapse_slashes)
+        .strip_query(args.strip_query)
+        .ignore_query_params(args.ignore_query_params)
+        .ignore_trailing_slash(args.ignore_trailing_slash)
+        .sort_query(args.sort_query);
+    if args.collapse_index {
+        normalizer = normalizer.collapse_index(args.index_files);
+    }
+
+    let open_file_limit = connections::open_file_limit();
+    let max_connections = match args.max_connections {
+        Some(0) => None,
+        Some(max) => Some(max),
+        None => connections::default_max_connections(open_file_limit),
+    };
+    let concurrency = if args.auto_concurrency {
+        args.max_concurrency
+    } else {
+        args.concurrency
+    };
+    if let Some(warning) =
+        connections::concurrency_warning(concurrency, max_connections, open_file_limit)
+    {
+        log::warn!("{}", warning);
+    }
+
+    let path_prefix = args.path_prefix.map(|prefix| match prefix.is_empty() {
+        true => parsed_url.path().to_string(),
+        false => prefix,
+    });
+    let mut crawler = Crawler::builder(parsed_url)
+        .fuzzy_match_string(fuzzy_match_string)
+        .include_subdomains(args.include_subdomains)
+        .scope(args.scope)
+        .path_prefix(path_prefix)
+        .check_out_of_scope(args.check_out_of_scope)
+        .url_filter(UrlPatternFilter::new(args.include, args.exclude))
+        .user_agents(user_agents)
+        .suggest_fixes(args.suggest_fixes)
+        .parse_timeout(Duration::from_millis(args.parse_timeout_ms))
+        .request_timeout((args.timeout_secs > 0).then(|| Duration::from_secs(args.timeout_secs)))
+        .max_bytes(args.max_bytes)
+        .max_body_bytes((args.max_body_bytes > 0).then_some(args.max_body_bytes))
+        .max_pages(args.max_pages)
+        .breadth_first(args.bfs)
+        .retry_policy(
+            args.retry_status
+                .unwrap_or_default()
+                .max_retries(args.max_retries)
+                .base_delay(args.retry_delay),
+        )
+        .respect_robots_header(args.respect_robots_header)
+        .respect_robots_meta(args.respect_robots_meta)
+        .flag_redirects(args.flag_redirects)
+        .follow_link_header(args.follow_link_header)
+        .follow_canonical(args.follow_canonical)
+        .scan_templates(args.scan_templates)
+        .htmx_links(args.htmx_links)
+        .respect_nofollow(args.respect_nofollow)
+        .check_fragments(args.check_fragments)
+        .min_content_length(args.min_content_length)
+        .report_slow(args.report_slow_ms.map(Duration::from_millis))
+        .accept(args.accept)
+        .headers(headers)
+        .report_skipped(
+            args.skipped_out.is_some()
+                || args.format == OutputFormat::Lychee
+                || args.dry_run
+                || args.events.is_some(),
+        )
+        .dns_prefetch(args.dns_prefetch)
+        .ua_fallback(args.ua_fallback)
+        .only_404(args.only_404)
+        .check_external(args.check_external)
+        .external_concurrency(Some(args.external_concurrency))
+        .external_delay(Duration::from_millis(args.external_delay_ms))
+        .max_distinct_hosts(args.max_distinct_hosts)
+        .max_errors(args.max_errors)
+        .ignore_broken(ignore_broken)
+        .url_normalizer(normalizer)
+        .slow_start(args.slow_start.then_some(SlowStart {
+            initial_delay: args.slow_start_delay,
+            warmup_requests: args.slow_start_requests,
+        }))
+        .request_delay(Duration::from_millis(args.delay_ms))
+        .json_links(
+            (args.json_links || !args.json_url_paths.is_empty()).then_some(args.json_url_paths),
+        )
+        .body_error_paths(
+            (!args.body_error_jsonpath.is_empty()).then_some(args.body_error_jsonpath),
+        )
+        .soft_404(soft_404)
+        .safe_only(args.safe_only.then(|| {
+            if args.unsafe_patterns.is_empty() {
+                UnsafeUrlFilter::default()
+            } else {
+                UnsafeUrlFilter::new(args.unsafe_patterns)
+            }
+        }))
+        .local_dir(local_dir)
+        .page_cache(page_cache.clone())
+        .seed_urls(seed_urls)
+        .use_sitemap(args.use_sitemap)
+        .dry_run(args.dry_run)
+        .concurrency(args.concurrency)
+        .backoff_on_errors(args.backoff_on_errors)
+        .auto_concurrency(args.auto_concurrency.then_some(args.max_concurrency))
+        .max_connections(max_connections)
+        .proxy(args.proxy)
+        .accept_invalid_certs(args.insecure)
+        .cookie_jar(cookies);
+    if let Some(client) = client {
+        crawler = crawler.client(client);
+    }
+    #[cfg(feature = "pdf")]
+    let crawler = crawler.check_pdf_links(args.check_pdf_links);
+    if let (Some(links), Some(previous)) = (recheck_links, &args.recheck_from) {
+        let report = run_recheck(crawler.build(), &links, previous, concurrency).await?;
+        if strictness.enabled() && !report.still_broken.is_empty() {
+            std::process::exit(EXIT_STRICT_FAILURE);
+        }
+        if args.fail_on_broken && report.still_broken.len() > args.fail_threshold {
+            std::process::exit(EXIT_BROKEN_LINKS);
+        }
+        return Ok(());
+    }
+    #[cfg(feature = "sqlite")]
+    let mut db = match &args.db {
+        Some(path) => Some(find_broken_links::sqlite::SqliteWriter::create(path)?),
+        None => None,
+    };
+    let mut all_csv = if args.report_all {
+        let file_name = results.file("-all", "csv");
+        fs::create_dir_all(&args.output_dir)?;
+        Some(CheckedCsvWriter::new(BufWriter::new(File::create(
+            file_name,
+        )?))?)
+    } else {
+        None
+    };
+    let count_checked = args.format == OutputFormat::Lychee || args.events.is_some();
+    #[cfg(feature = "sqlite")]
+    let crawler = crawler.report_checked(count_checked || all_csv.is_some() || db.is_some());
+    #[cfg(not(feature = "sqlite"))]
+    let crawler = crawler.report_checked(count_checked || all_csv.is_some());
+    let crawler = crawler.build();
+    let bytes_downloaded = crawler.bytes_downloaded();
+    let crawl_progress = crawler.progress();
+    let cancel = crawler.cancellation_token();
+    let progress_done = CancellationToken::new();
+    let (interval, every_pages) = (args.progress_interval, args.progress_every_pages);
+    let mut progress_tasks = Vec::new();
+    if let Some(path) = &args.progress_log {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        let progress_log = ProgressLog::new(file, crawler.progress());
+        progress_tasks.push(spawn_progress_log(
+            progress_log,
+            interval,
+            every_pages,
+            progress_done.clone(),
+        ));
+    }
+    // Logs are off by default, so without this a long crawl would look stuck
+    if !args.quiet {
+        let progress_log = ProgressLog::new(std::io::stderr(), crawler.progress());
+        progress_tasks.push(spawn_progress_log(
+            progress_log,
+            interval,
+            every_pages,
+            progress_done.clone(),
+        ));
+    }
+    let crawl_started = Instant::now();
+    let crawl_started_at = SystemTime::now();
+    // What the report says about the crawl; a report saved while it runs has the summary so far
+    let report_header = |debug_channel: &debug_channel::DebugChannel<CrawlMessage>| {
+        (!args.legacy_output).then(|| {
+            let summary = crawl_progress.summary(crawl_started.elapsed(), debug_channel.stats());
+            ReportHeader::new(root_url.as_str(), crawl_started_at, summary)
+        })
+    };
+    let complete = debug_channel.sender();
+    let crawl_task = tokio::spawn(async move {
+        let result = crawler.run(debug_sender).await;
+        if let Err(e) = &result {
             log::error!("Crawler error: {}", e);
+            // The crawl only ends with `Complete` when it succeeds, and the loop below waits for
+            // one: the channel holds a sender of its own, so it never closes
+            let _ = complete.send(CrawlMessage::Complete).await;
         }
-        log::info!("tokio::spawn block is done...")
+        log::info!("tokio::spawn block is done...");
+        result
     });
 
-    // Wait for either CTRL+C or the crawler task to finish
-    let mut not_found_urls = Vec::new();
+    // Wait for either CTRL+C or the crawler task to finish. After CTRL+C, keep collecting
+    // results until the crawler drains or the shutdown timeout runs out.
+    let mut shutdown_deadline: Option<tokio::time::Instant> = None;
+    if let Some(limit) = max_duration {
+        log::info!("Stopping the crawl after {:?} at most", limit);
+    }
+    let mut deadline_reached = false;
+    let mut completed = false;
+    let mut not_found_errors = Vec::new();
+    let mut link_warnings = Vec::new();
+    // For the lychee report's counters
+    let (mut checked_count, mut successful_count, mut skipped_count) = (0, 0, 0);
+
+    let extension = match args.format {
+        OutputFormat::Text => "txt",
+        OutputFormat::Jsonl => "jsonl",
+        OutputFormat::Csv => "csv",
+        OutputFormat::Native | OutputFormat::Lychee => "json",
+    };
+    let file_path = match &args.output {
+        Some(path) => path.clone(),
+        None => results.file("", extension),
+    };
+    let file_path = file_path.as_path();
+    // With jsonl, broken links go straight to the file instead of `not_found_errors`
+    let mut jsonl = match args.format {
+        OutputFormat::Jsonl if !args.dry_run => {
+            fs::create_dir_all(file_path.parent().unwrap_or(Path::new(".")))?;
+            Some(JsonlWriter::new(BufWriter::new(File::create(file_path)?)))
+        }
+        _ => None,
+    };
+    let mut events = args
+        .events
+        .map(|EventFormat::Ndjson| EventWriter::new(std::io::stdout()));
+    let mut broken_count = 0;
+    // Every line of a jsonl file is already saved
+    let mut flush = (jsonl.is_none()
+        && !args.dry_run
+        && (args.flush_interval.is_some() || args.flush_every_pages.is_some()))
+    .then(|| Periodic::new(args.flush_interval, args.flush_every_pages));
+    let mut flush_tick = tokio::time::interval(Duration::from_millis(250));
+    // What the last snapshot held, to skip rewriting it unchanged
+    let mut flushed = (0, 0);
     loop {
         log::debug!("Waiting for messages or completion signal...");
         tokio::select! {
             message = debug_channel.recv() => {
+                if let (Some(events), Some(CrawlMessage::Finding(finding))) = (events.as_mut(), &message) {
+                    events.record_finding(finding)?;
+                }
                 match message {
-                    Some(Some(url)) => {
-                        not_found_urls.push(url);
+                    Some(CrawlMessage::Finding(finding @ (Finding::NotFound { .. } | Finding::AlsoLinkedFrom { .. }))) => {
+                        if let Finding::NotFound { url, referrer, suggestion, error, status, .. } = &finding {
+                            if args.stdout {
+                                let status = status.unwrap_or_default();
+                                println!("{}", text_line(status, url, referrer.as_deref()));
+                            }
+                            if let Some(suggestion) = suggestion {
+                                log::info!("{}: {}", url, suggestion);
+                            }
+                            if let Some(error) = error {
+                                log::info!("{}: error in body: {}", url, error);
+                            }
+                            if let Some(syslog) = syslog.as_mut() {
+                                syslog.broken_link(url, suggestion.as_deref());
+                            }
+                        }
+                        if matches!(finding, Finding::NotFound { .. }) {
+                            broken_count += 1;
+                        }
+                        match jsonl.as_mut() {
+                            Some(jsonl) => jsonl.record(finding)?,
+                            None => add_finding(&mut not_found_errors, finding),
+                        }
+                    },
+                    Some(CrawlMessage::Finding(Finding::Warning(warning))) => {
+                        log::warn!("{}: {}", warning.url, warning.message);
+                        if let Some(syslog) = syslog.as_mut() {
+                            syslog.warning(&warning);
+                        }
+                        link_warnings.push(warning);
+                    },
+                    Some(CrawlMessage::Finding(Finding::Skipped(skipped))) => {
+                        skipped_count += 1;
+                        if args.dry_run {
+                            println!(
+                                "skip  {} ({}, from {})",
+                                skipped.url, skipped.reason, skipped.referrer
+                            );
+                        }
+                        if let Some(out) = skipped_out.as_mut() {
+                            serde_json::to_writer(&mut *out, &skipped)?;
+                            out.write_all(b"\n")?;
+                        }
                     },
-                Some(None) => { // Completion signal received
+                    Some(CrawlMessage::Finding(Finding::WouldFetch { url, referrer, check_only })) => {
+                        let action = if check_only { "check" } else { "crawl" };
+                        println!("{} {} (from {})", action, url, referrer);
+                    },
+                    Some(CrawlMessage::Finding(Finding::Checked(checked))) => {
+                        checked_count += 1;
+                        if checked.status.is_some_and(|status| (200..300).contains(&status)) {
+                            successful_count += 1;
+                        }
+                        if let Some(csv) = all_csv.as_mut() {
+                            csv.record(&checked)?;
+                        }
+                        #[cfg(feature = "sqlite")]
+                        if let Some(db) = db.as_ref() {
+                            db.record(&checked)?;
+                        }
+                    },
+                Some(CrawlMessage::Complete) => { // Completion signal received
                     log::info!("Crawl complete, ending loop.");
+                    completed = true;
                     break;
                 },
                     None => {
@@ -67,169 +1227,445 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
             },
-            _ = signal::ctrl_c() => {
-                log::info!("Received CTRL+C, shutting down...");
-                break; // Also break the loop on CTRL+C
+            _ = flush_tick.tick(), if flush.is_some() => {
+                let pages = crawl_progress.pages_crawled();
+                let Some(flush) = flush.as_mut().filter(|flush| flush.due(pages)) else {
+                    continue;
+                };
+                flush.done(pages);
+                let snapshot = (not_found_errors.len(), checked_count + skipped_count);
+                if snapshot == flushed
+                    || (matches!(args.format, OutputFormat::Native | OutputFormat::Csv)
+                        && not_found_errors.is_empty())
+                {
+                    continue;
+                }
+                flushed = snapshot;
+                let counts = (checked_count, successful_count, skipped_count);
+                let lychee = (args.format == OutputFormat::Lychee)
+                    .then(|| lychee_stats(root_url.as_str(), &not_found_errors, counts, crawl_started));
+                let saved = save_results(
+                    file_path,
+                    &not_found_errors,
+                    args.format,
+                    args.group_by,
+                    !args.no_dedupe,
+                    lychee.as_ref(),
+                    report_header(&debug_channel),
+                );
+                match saved {
+                    Ok(()) => log::debug!(
+                        "Saved a snapshot with {} 404 urls to {}",
+                        not_found_errors.len(),
+                        file_path.display()
+                    ),
+                    // The final save will try again
+                    Err(e) => log::warn!("Failed to save a snapshot of the results: {}", e),
+                }
+            },
+            _ = tokio::time::sleep_until(crawl_deadline.unwrap_or_else(tokio::time::Instant::now)),
+                if crawl_deadline.is_some() && shutdown_deadline.is_none() => {
+                log::warn!(
+                    "Deadline reached, stopping the crawl (waiting up to {:?} for in-flight requests)...",
+                    args.shutdown_timeout
+                );
+                cancel.cancel();
+                deadline_reached = true;
+                shutdown_deadline = Some(tokio::time::Instant::now() + args.shutdown_timeout);
+            },
+            _ = signal::ctrl_c(), if shutdown_deadline.is_none() => {
+                log::info!(
+                    "Received CTRL+C, shutting down (waiting up to {:?} for in-flight requests)...",
+                    args.shutdown_timeout
+                );
+                cancel.cancel();
+                shutdown_deadline = Some(tokio::time::Instant::now() + args.shutdown_timeout);
+            },
+            _ = tokio::time::sleep_until(shutdown_deadline.unwrap_or_else(tokio::time::Instant::now)),
+                if shutdown_deadline.is_some() => {
+                log::warn!("Shutdown timeout reached, exiting without waiting for the crawler");
+                break;
             },
         }
     }
 
-    // Convert not found URLs into NotFoundError structs
-    let not_found_errors: Vec<NotFoundError> = not_found_urls
-        .into_iter()
-        .map(|url| NotFoundError {
-            url,
-            title: None, // You would extract the title in your actual crawling logic
-        })
-        .collect();
+    // The crawler is done once it sent `Complete`, so its result is ready; otherwise, don't
+    // leave it running in the background (e.g. stuck on a hung request)
+    let crawl_error = if completed {
+        match crawl_task.await {
+            Ok(result) => result.err().map(|e| e.to_string()),
+            Err(e) => Some(e.to_string()),
+        }
+    } else {
+        crawl_task.abort();
+        None
+    };
+
+    progress_done.cancel();
+    for progress_task in progress_tasks {
+        progress_task.await?;
+    }
+
+    if let Some(mut out) = skipped_out.take() {
+        out.flush()?;
+    }
+    if let Some(csv) = all_csv.take() {
+        csv.finish()?;
+    }
+    if let Some(jsonl) = jsonl.take() {
+        jsonl.finish()?;
+    }
 
-    // Construct the file path
-    let file_name = format!("./results/{}.json", hostname);
-    let file_path = Path::new(&file_name);
+    #[cfg(feature = "sqlite")]
+    if let Some(db) = db.take() {
+        db.finish()?;
+    }
+
+    if crawl_progress.stopped_at_max_errors() {
+        log::warn!(
+            "Stopped early after {} broken links (--max-errors), the site may have more",
+            broken_count
+        );
+    }
 
-    if not_found_errors.len() > 0 {
+    if args.dry_run {
+        log::info!("Dry run, not saving a report");
+    } else if args.format == OutputFormat::Lychee {
+        // Dashboards expect a report on every run, so this is written even without 404s
+        log::info!(
+            "Saving lychee report with {} 404 urls...",
+            not_found_errors.len()
+        );
+        let counts = (checked_count, successful_count, skipped_count);
+        let stats = lychee_stats(root_url.as_str(), &not_found_errors, counts, crawl_started);
+        save_results(
+            file_path,
+            &not_found_errors,
+            args.format,
+            args.group_by,
+            !args.no_dedupe,
+            Some(&stats),
+            None,
+        )?;
+    } else if args.format == OutputFormat::Jsonl {
+        log::info!("Saved {} 404 urls to {}", broken_count, file_path.display());
+    } else if args.format == OutputFormat::Text || args.timestamped || !not_found_errors.is_empty()
+    {
+        // An empty text report is written too, so a committed one shows when all are fixed, and
+        // a timestamped one, so the history and the latest copy show clean runs
         log::info!("Saving {} 404 urls...", not_found_errors.len());
-        // Save the not found errors
-        save_not_found_errors(&not_found_errors, file_path)?;
+        save_results(
+            file_path,
+            &not_found_errors,
+            args.format,
+            args.group_by,
+            !args.no_dedupe,
+            None,
+            report_header(&debug_channel),
+        )?;
     } else {
         log::info!("No 404s found")
     }
+    if let Some(latest) = results.latest(extension).filter(|_| !args.dry_run) {
+        copy_report(file_path, &latest)?;
+        log::info!("Copied the report to {}", latest.display());
+    }
 
-    // log how big the mpsc channel buffer got so we can change if needed:
-
-    log::debug!(
-        "mpsc channel got to max size of {}",
-        debug_channel.get_max_buffer_size()
+    log::info!(
+        "Downloaded {} bytes of response bodies",
+        bytes_downloaded.load(Ordering::Relaxed)
     );
+    if let Some(cache) = page_cache.filter(|_| !args.dry_run) {
+        match cache.save() {
+            Ok(()) => log::info!("Saved {} pages to the page cache", cache.len()),
+            Err(e) => log::warn!("Failed to save the page cache: {}", e),
+        }
+    }
 
-    Ok(())
-}
+    if let Some(syslog) = syslog.as_mut() {
+        syslog.summary(root_url.as_str(), broken_count, link_warnings.len());
+    }
 
-fn make_absolute_url(base_url: &Url, link: &str) -> Result<Url, ParseError> {
-    // let base = Url::parse(base_url)?;
-    base_url.join(link) // This resolves the relative URL 'link' against the base URL 'base_url'
-}
+    if !link_warnings.is_empty() && !args.dry_run {
+        log::info!("Saving {} warnings...", link_warnings.len());
+        save_json(&link_warnings, &results.file("-warnings", "json"))?;
+    }
 
-async fn fetch_html(url: &str) -> Result<String, reqwest::Error> {
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/58.0.3029.110 Safari/537.3")
-        .build()?;
+    // Includes how big the mpsc channel buffer got, so its size can be tuned
+    let summary = crawl_progress.summary(crawl_started.elapsed(), debug_channel.stats());
+    log::info!("Crawl summary: {}", summary);
+    if let Some(events) = events.as_mut() {
+        events.record(&CrawlEvent::Done {
+            summary: summary.clone(),
+        })?;
+    }
+    if let Some(path) = &args.summary_json {
+        save_json(&summary, path)?;
+    }
 
-    let resp = client.get(url).send().await?;
+    // Whatever was found before the crawl failed is saved above, but the run still failed
+    if let Some(e) = crawl_error {
+        return Err(format!("the crawl failed: {}", e).into());
+    }
 
-    if resp.status().is_success() {
-        resp.text().await
-    } else {
-        // Directly return the error without constructing a new one
-        Err(resp.error_for_status().unwrap_err())
-    }
-}
-
-// TODO: return the html element along with the link
-fn find_links(html: &str) -> Vec<String> {
-    let document = select::document::Document::from(html);
-    let mut links = Vec::new();
-    let denied_protocols = ["mailto:", "ftp:", "tel:"];
-    let denied_links = ["#", "javascript:void(0)"];
-
-    for node in document.find(select::predicate::Name("a")) {
-        if let Some(link) = node.attr("href") {
-            if !denied_protocols
-                .iter()
-                .any(|&protocol| link.starts_with(protocol))
-            {
-                if !denied_links.iter().any(|&denied| link == denied){
-                    log::debug!("Adding link: {}", link.to_string());
-                    links.push(link.to_string());
-                }
-            }
+    if strictness.enabled() {
+        let promoted_warnings = link_warnings
+            .iter()
+            .filter(|warning| strictness.promotes(warning.category))
+            .count();
+        let error_count = broken_count + promoted_warnings;
+        if error_count > 0 {
+            log::error!(
+                "Strict mode: {} errors ({} promoted from warnings)",
+                error_count,
+                promoted_warnings
+            );
+            std::process::exit(EXIT_STRICT_FAILURE);
         }
     }
 
-    links
+    if args.fail_on_broken && broken_count > args.fail_threshold {
+        log::error!(
+            "Found {} broken links, more than the --fail-threshold of {}",
+            broken_count,
+            args.fail_threshold
+        );
+        std::process::exit(EXIT_BROKEN_LINKS);
+    }
+
+    if deadline_reached {
+        std::process::exit(EXIT_DEADLINE);
+    }
+
+    Ok(())
 }
 
-async fn crawl_and_collect_404s(
-    root_url: Url,
-    tx: debug_channel::DebugSender<Option<String>>,
-    fuzzy_match_string: Option<String>,
-) -> Result<(), anyhow::Error> {
-    log::info!("crawling and collecting 404s");
-    let root_domain = root_url
-        .domain()
-        .ok_or_else(|| anyhow::anyhow!("Root URL has no domain"))?;
-    let mut to_visit = vec![root_url.to_string()];
-    let mut visited = Vec::new();
-
-    while let Some(url) = to_visit.pop() {
-        if visited.contains(&url) {
-            continue;
-        }
-        log::info!("crawling {}", url);
-
-        let html_result = fetch_html(&url).await;
-        match html_result {
-            Ok(html) => {
-                // TODO: save the url
-                let links = find_links(&html);
-                for link in links {
-                    let absolute_link = make_absolute_url(&root_url, &link)?;
-                    let absolute_link_domain = match absolute_link.domain() {
-                        Some(domain) => domain,
-                        None => {
-                            log::warn!("Link '{}' has no domain, skipping...", absolute_link);
-                            continue;
-                        }
-                    };
-                    let matches_exact = root_domain == absolute_link_domain;
-                    let matches_fuzzy = fuzzy_match_string
-                        .as_ref()
-                        .map(|fuzzy_match_string| {
-                            absolute_link_domain
-                                .to_string()
-                                .contains(fuzzy_match_string)
-                        })
-                        .unwrap_or(false);
-                    if (matches_exact || matches_fuzzy)
-                        && !visited.contains(&absolute_link.to_string())
-                    {
-                        to_visit.push(absolute_link.to_string());
+/// Writes a line to `progress_log` whenever `interval` has passed or `every_pages` pages were
+/// crawled since the last one, and a final one with the totals once `done` is cancelled.
+fn spawn_progress_log<W: Write + Send + 'static>(
+    mut progress_log: ProgressLog<W>,
+    interval: Duration,
+    every_pages: Option<u64>,
+    done: CancellationToken,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_millis(250));
+        loop {
+            tokio::select! {
+                _ = tick.tick() => {
+                    if !progress_log.due(interval, every_pages) {
+                        continue;
                     }
-                }
+                },
+                _ = done.cancelled() => break,
             }
-            Err(e) if e.status() == Some(reqwest::StatusCode::NOT_FOUND) => {
-                if let Err(send_err) = tx.send(Some(url.clone())).await {
-                    log::error!("Failed to send 404 URL through the channel: {}", send_err);
-                }
+            if let Err(e) = progress_log.write_line() {
+                log::warn!("Progress log disabled: {}", e);
+                return;
             }
-            Err(e) => return Err(e.into()),
         }
+        // Final line with the totals
+        if let Err(e) = progress_log.write_line() {
+            log::warn!("Failed to write the progress log: {}", e);
+        }
+    })
+}
 
-        visited.push(url);
+/// Fills in `args` from a `--config` file, except for the options given on the command line.
+fn apply_config(
+    args: &mut Args,
+    config: CrawlConfig,
+    matches: &ArgMatches,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let on_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let patterns = |patterns: Vec<String>| -> Result<Vec<Regex>, regex::Error> {
+        patterns.iter().map(|pattern| Regex::new(pattern)).collect()
+    };
+    if !on_command_line("root_url") {
+        if let Some(root_url) = config.root_url {
+            let root_url = parse_url(&root_url).map_err(|e| format!("invalid root_url: {}", e))?;
+            args.root_url = Some(root_url);
+        }
     }
-    log::info!("Done crawling...");
-    if let Err(send_err) = tx.send(None).await {
-        log::error!(
-            "Failed to signal completion through the channel: {}",
-            send_err
-        );
+    if !on_command_line("seed") && !config.seeds.is_empty() {
+        args.seed = config
+            .seeds
+            .iter()
+            .map(|seed| parse_url(seed).map_err(|e| format!("invalid seed: {}", e)))
+            .collect::<Result<_, _>>()?;
+    }
+    if !on_command_line("fuzzy") && !on_command_line("fuzzy_match_string") {
+        args.fuzzy = config.fuzzy;
+    }
+    if !on_command_line("concurrency") {
+        args.concurrency = config.concurrency.unwrap_or(args.concurrency);
+    }
+    if !on_command_line("timeout_secs") {
+        args.timeout_secs = config.timeout_secs.unwrap_or(args.timeout_secs);
+    }
+    if !on_command_line("delay_ms") {
+        args.delay_ms = config.delay_ms.unwrap_or(args.delay_ms);
+    }
+    if !on_command_line("channel_buffer") {
+        args.channel_buffer = config.channel_buffer.unwrap_or(args.channel_buffer);
+    }
+    if !on_command_line("max_pages") {
+        args.max_pages = config.max_pages;
+    }
+    if !on_command_line("soft_404_patterns") && !config.soft_404_patterns.is_empty() {
+        args.soft_404_patterns = config.soft_404_patterns;
+    }
+    if !on_command_line("include") && !config.include.is_empty() {
+        args.include = patterns(config.include)?;
+    }
+    if !on_command_line("exclude") && !config.exclude.is_empty() {
+        args.exclude = patterns(config.exclude)?;
+    }
+    if !on_command_line("headers") && !config.headers.is_empty() {
+        args.headers.headers = config
+            .headers
+            .iter()
+            .map(|(name, value)| parse_header(&format!("{}: {}", name, value)))
+            .collect::<Result<_, _>>()?;
     }
-
     Ok(())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct NotFoundError {
-    url: String,
-    title: Option<String>, // Titles can be optional since some 404 pages might not have a clear title
+/// Like `Url::parse`, with a hint for the usual mistake of leaving out the scheme.
+fn parse_url(s: &str) -> Result<Url, String> {
+    Url::parse(s).map_err(|e| match e {
+        url::ParseError::RelativeUrlWithoutBase => {
+            format!("'{}' has no scheme; try 'https://{}'", s, s)
+        }
+        e => format!("'{}' is not a valid URL: {}", s, e),
+    })
 }
 
-fn save_not_found_errors(errors: &[NotFoundError], file_path: &Path) -> std::io::Result<()> {
-    fs::create_dir_all(file_path.parent().unwrap())?; // Ensure the directory exists
+/// A `--proxy` URL, checked up front so a typo fails before the crawl rather than on every
+/// request.
+fn parse_proxy(s: &str) -> Result<reqwest::Proxy, String> {
+    let url = parse_url(s)?;
+    if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+        return Err(format!(
+            "unsupported proxy scheme '{}'; use http, https, socks5 or socks5h",
+            url.scheme()
+        ));
+    }
+    if url.host_str().is_none() {
+        return Err(format!("'{}' has no host", s));
+    }
+    reqwest::Proxy::all(url.as_str()).map_err(|e| e.to_string())
+}
 
-    let mut file = File::create(file_path)?;
-    let data = serde_json::to_string_pretty(&errors)?;
-    file.write_all(data.as_bytes())?;
+fn parse_header(s: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected 'NAME: VALUE', got '{}'", s))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|e| e.to_string())?;
+    let value = HeaderValue::from_str(value.trim()).map_err(|e| e.to_string())?;
+    Ok((name, value))
+}
 
-    Ok(())
+fn parse_form_field(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", s))?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Checks the links from a previous results file again, for `--recheck-from`, and writes which
+/// are fixed next to it.
+async fn run_recheck(
+    crawler: Crawler,
+    links: &[NotFoundError],
+    previous: &Path,
+    concurrency: usize,
+) -> std::io::Result<RecheckReport> {
+    log::info!(
+        "Rechecking {} broken links from {}",
+        links.len(),
+        previous.display()
+    );
+    let report = RecheckReport::new(recheck::recheck(Arc::new(crawler), links, concurrency).await);
+    for link in &report.fixed {
+        log::info!("Fixed: {} (was {})", link.url, link.previous_status);
+    }
+    for link in &report.still_broken {
+        match (link.status, &link.error) {
+            (_, Some(error)) => log::warn!("Still broken: {}: error in body: {}", link.url, error),
+            (Some(status), None) => log::warn!("Still broken: {} ({})", link.url, status),
+            (None, None) => log::warn!("Still broken: {} (no response)", link.url),
+        }
+    }
+    log::info!(
+        "{} of {} previously broken links are fixed",
+        report.fixed.len(),
+        links.len()
+    );
+    let report_path = recheck::report_path(previous);
+    log::info!("Saving the recheck report to {}", report_path.display());
+    save_json(&report, &report_path)?;
+    Ok(report)
+}
+
+/// Writes the results file: the text or CSV report for those formats, the lychee report if given,
+/// and otherwise the broken links as JSON, by URL (sorted, and with `dedupe` one entry per URL)
+/// or by referrer per `group_by`. The JSON is wrapped in a versioned [`Report`] with `header`,
+/// or saved as a bare array, the legacy shape, without one.
+#[allow(clippy::too_many_arguments)]
+fn save_results(
+    file_path: &Path,
+    not_found_errors: &[NotFoundError],
+    format: OutputFormat,
+    group_by: GroupBy,
+    dedupe: bool,
+    lychee: Option<&LycheeStats>,
+    header: Option<ReportHeader>,
+) -> std::io::Result<()> {
+    match format {
+        OutputFormat::Text => return save_text(&text_report(not_found_errors), file_path),
+        OutputFormat::Csv => return save_text(&csv_report(not_found_errors), file_path),
+        _ => {}
+    }
+    fn save_entries<T: Serialize>(
+        broken_links: Vec<T>,
+        header: Option<ReportHeader>,
+        file_path: &Path,
+    ) -> std::io::Result<()> {
+        match header {
+            Some(header) => save_json(
+                &Report {
+                    header,
+                    broken_links,
+                },
+                file_path,
+            ),
+            None => save_json(&broken_links, file_path),
+        }
+    }
+    match (lychee, group_by) {
+        (Some(stats), _) => save_json(stats, file_path),
+        (None, GroupBy::Url) => {
+            save_entries(url_report(not_found_errors, dedupe), header, file_path)
+        }
+        (None, GroupBy::Referrer) => {
+            save_entries(group_by_referrer(not_found_errors), header, file_path)
+        }
+    }
+}
+
+/// The lychee report, from the broken links and the `(checked, successful, skipped)` counts.
+fn lychee_stats(
+    root_url: &str,
+    not_found_errors: &[NotFoundError],
+    (checked, successful, skipped): (usize, usize, usize),
+    crawl_started: Instant,
+) -> LycheeStats {
+    LycheeStats {
+        total: checked + skipped,
+        successful,
+        excludes: skipped,
+        duration_secs: crawl_started.elapsed().as_secs(),
+        ..LycheeStats::from_broken_links(root_url, not_found_errors)
+    }
 }
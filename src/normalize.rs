@@ -0,0 +1,139 @@
+use url::Url;
+
+/// Index files collapsed into their directory by `--collapse-index` unless configured otherwise.
+pub const DEFAULT_INDEX_FILES: &[&str] = &["index.html", "index.htm"];
+
+/// Decides when two URLs name the same page, so it is only crawled once.
+///
+/// By default URLs only need to match once their fragment, credentials and empty query (`?`)
+/// are left out, none of which the server ever sees as a different page; the host is already
+/// lowercase once parsed. Every other rule is opt-in, since some servers do tell the variants
+/// apart.
+#[derive(Debug, Clone, Default)]
+pub struct UrlNormalizer {
+    index_files: Vec<String>,
+    collapse_slashes: bool,
+    strip_query: bool,
+    ignored_query_params: Vec<String>,
+    ignore_trailing_slash: bool,
+    sort_query: bool,
+}
+
+impl UrlNormalizer {
+    /// Treat `/dir/<index file>` and `/dir/` as the same page, for each of `index_files`.
+    ///
+    /// Only the exact file names given are collapsed, and only into the directory URL with its
+    /// trailing slash: `/dir` (no slash) stays distinct, as does any change to the query.
+    pub fn collapse_index(mut self, index_files: Vec<String>) -> Self {
+        self.index_files = index_files;
+        self
+    }
+
+    /// Rewrite runs of slashes in the path (`/foo//bar`, `https://site.com//page`) to a single
+    /// one before fetching. The `//` after the scheme is never touched, and neither is the query.
+    pub fn collapse_slashes(mut self, collapse_slashes: bool) -> Self {
+        self.collapse_slashes = collapse_slashes;
+        self
+    }
+
+    /// Treat URLs that differ only in their query string (`/item?id=1`, `/item?id=2`, `/item`)
+    /// as the same page, so only the first one found is fetched.
+    ///
+    /// This is the only rule that ignores the query: servers often return a 404 for some
+    /// query values and a 200 for others, and collapsing them would hide those.
+    pub fn strip_query(mut self, strip_query: bool) -> Self {
+        self.strip_query = strip_query;
+        self
+    }
+
+    /// Leave the query parameters named `names` (like `utm_source`) out of the comparison, so
+    /// `/item?id=5&utm_source=feed` and `/item?id=5` are the same page, while `/item?id=6`
+    /// isn't. Names are matched exactly, case included; the other parameters keep their order
+    /// and encoding.
+    pub fn ignore_query_params(mut self, names: Vec<String>) -> Self {
+        self.ignored_query_params = names;
+        self
+    }
+
+    /// Treat `/page` and `/page/` as the same page. The root path is always `/`.
+    pub fn ignore_trailing_slash(mut self, ignore_trailing_slash: bool) -> Self {
+        self.ignore_trailing_slash = ignore_trailing_slash;
+        self
+    }
+
+    /// Treat queries with the same parameters in a different order (`?a=1&b=2`, `?b=2&a=1`) as
+    /// the same page. Repeated parameters keep their values, in sorted order too.
+    pub fn sort_query(mut self, sort_query: bool) -> Self {
+        self.sort_query = sort_query;
+        self
+    }
+
+    /// Applies the rewriting rules to a link before it is queued; `None` if nothing changed.
+    pub fn rewrite(&self, url: &Url) -> Option<Url> {
+        if !self.collapse_slashes || !url.path().contains("//") {
+            return None;
+        }
+        let mut path = String::with_capacity(url.path().len());
+        for c in url.path().chars() {
+            if !(c == '/' && path.ends_with('/')) {
+                path.push(c);
+            }
+        }
+        let mut rewritten = url.clone();
+        rewritten.set_path(&path);
+        Some(rewritten)
+    }
+
+    /// The key `url` is deduplicated under: URLs with the same key are only fetched once.
+    /// Unparseable URLs are their own key.
+    pub fn dedup_key(&self, url: &str) -> String {
+        match Url::parse(url) {
+            Ok(parsed) => self.normalize_url(&parsed).into(),
+            Err(_) => url.to_string(),
+        }
+    }
+
+    /// `url` in the form every URL naming the same page shares, for comparing rather than
+    /// fetching: links are still fetched and reported as written.
+    pub fn normalize_url(&self, url: &Url) -> Url {
+        let mut normalized = url.clone();
+        normalized.set_fragment(None);
+        // Only fails for URLs that can't have credentials in the first place
+        let _ = normalized.set_username("");
+        let _ = normalized.set_password(None);
+        let path = normalized.path();
+        let (dir, file) = path.rsplit_once('/').unwrap_or(("", path));
+        if self.index_files.iter().any(|index| index == file) {
+            let dir = format!("{}/", dir);
+            normalized.set_path(&dir);
+        }
+        if self.ignore_trailing_slash && normalized.path().len() > 1 {
+            let path = normalized.path().trim_end_matches('/').to_string();
+            normalized.set_path(&path);
+        }
+        if let (false, Some(query)) = (self.ignored_query_params.is_empty(), normalized.query()) {
+            let kept: Vec<&str> = query
+                .split('&')
+                .filter(|pair| !self.is_ignored_param(pair))
+                .collect();
+            let kept = kept.join("&");
+            normalized.set_query(Some(&kept));
+        }
+        if self.strip_query || normalized.query() == Some("") {
+            normalized.set_query(None);
+        } else if self.sort_query && normalized.query().is_some() {
+            let mut pairs: Vec<(String, String)> = normalized.query_pairs().into_owned().collect();
+            pairs.sort();
+            normalized.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+        normalized
+    }
+
+    /// Whether `pair`, one `name=value` of a query string, is a parameter to leave out
+    fn is_ignored_param(&self, pair: &str) -> bool {
+        let name = pair.split('=').next().unwrap_or_default();
+        url::form_urlencoded::parse(name.as_bytes())
+            .next()
+            .is_some_and(|(name, _)| self.ignored_query_params.iter().any(|n| *n == name))
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Starts a crawl gently: the gap between request starts begins at `initial_delay` and shrinks
+/// linearly to the normal pace over the first `warmup_requests` requests.
+///
+/// With a 2s initial delay over 10 requests, the second request starts 2s after the first, the
+/// third 1.8s after the second, and so on until request 11, from which on requests go out at the
+/// normal pace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlowStart {
+    pub initial_delay: Duration,
+    pub warmup_requests: u32,
+}
+
+impl SlowStart {
+    /// Minimum gap between the start of request number `request - 1` and `request` (counting
+    /// from 0), given the gap the crawl keeps after warming up.
+    pub fn delay_before(&self, request: u64, target: Duration) -> Duration {
+        if request == 0 || self.initial_delay <= target {
+            return target;
+        }
+        let step = request - 1;
+        if step >= u64::from(self.warmup_requests) {
+            return target;
+        }
+        let remaining = 1.0 - step as f64 / f64::from(self.warmup_requests);
+        target + (self.initial_delay - target).mul_f64(remaining)
+    }
+}
+
+/// Keeps a minimum gap between the starts of requests to the same host, for `--delay-ms`.
+/// Each host has its own schedule, so a crawl spanning several hosts isn't held to the pace of
+/// one of them.
+///
+/// Every host gets the default gap unless [`HostPacer::set_delay`] gives it another one, which
+/// is where a `Crawl-delay` from a host's robots.txt goes.
+#[derive(Debug, Default)]
+pub struct HostPacer {
+    default_delay: Duration,
+    hosts: Mutex<HashMap<String, HostPace>>,
+}
+
+#[derive(Debug, Default)]
+struct HostPace {
+    /// Replaces the default gap for this host
+    delay: Option<Duration>,
+    /// When the last request reserved for this host starts
+    last_start: Option<Instant>,
+}
+
+impl HostPacer {
+    pub fn new(default_delay: Duration) -> Self {
+        HostPacer {
+            default_delay,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the gap between requests to `host`, in place of the default.
+    pub fn set_delay(&self, host: &str, delay: Duration) {
+        let mut hosts = self.hosts.lock().unwrap();
+        hosts.entry(host.to_ascii_lowercase()).or_default().delay = Some(delay);
+    }
+
+    /// The gap between requests to `host`.
+    pub fn delay_for(&self, host: &str) -> Duration {
+        let hosts = self.hosts.lock().unwrap();
+        hosts
+            .get(&host.to_ascii_lowercase())
+            .and_then(|pace| pace.delay)
+            .unwrap_or(self.default_delay)
+    }
+
+    /// Books the next request to `host` and returns when it may start: now, or one gap after
+    /// the request booked before it. Requests booked together go out one gap apart, in the
+    /// order they were booked.
+    pub fn reserve(&self, host: &str, now: Instant) -> Instant {
+        let mut hosts = self.hosts.lock().unwrap();
+        let pace = hosts.entry(host.to_ascii_lowercase()).or_default();
+        let delay = pace.delay.unwrap_or(self.default_delay);
+        let start = match pace.last_start {
+            Some(last_start) => (last_start + delay).max(now),
+            None => now,
+        };
+        pace.last_start = Some(start);
+        start
+    }
+
+    /// Waits until a request to `host` may start, booking it.
+    pub async fn wait(&self, host: &str) {
+        let start = self.reserve(host, Instant::now());
+        if start > Instant::now() {
+            tokio::time::sleep_until(start.into()).await;
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::links::{ExtractedLinks, LinkSources};
+use crate::robots::RobotsDirectives;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Version of what's cached about a page. Bumped whenever [`CachedPage`] or the links in it
+/// change shape, so a cache from an older version is dropped rather than misread.
+pub const PAGE_CACHE_VERSION: u32 = 2;
+
+/// What a crawl learned about a page, to skip downloading and parsing it again while it's
+/// unchanged: the validators to ask the server with, and the response and links they stand for.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CachedPage {
+    /// `ETag` of the response, sent back as `If-None-Match`
+    pub etag: Option<String>,
+    /// `Last-Modified` of the response, sent back as `If-Modified-Since`
+    pub last_modified: Option<String>,
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub robots: RobotsDirectives,
+    /// `rel="next"`/`rel="prev"` targets from the `Link` header
+    pub link_header: Vec<String>,
+    pub body_len: usize,
+    pub redirected_to: Option<String>,
+    /// What was extracted from the page, and with which sources: the links only stand in for
+    /// a crawl extracting the same ones
+    pub sources: LinkSources,
+    pub links: ExtractedLinks,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile<Pages> {
+    version: u32,
+    /// Dedup key of the page's URL -> what's cached about it
+    pages: Pages,
+}
+
+/// An on-disk cache of the pages a crawl loaded, for `--cache-dir`, so the next crawl of the
+/// same site can ask for each page only if it changed (`If-None-Match`, `If-Modified-Since`) and
+/// reuse its links on a `304 Not Modified`.
+///
+/// Pages are keyed by the crawl's dedup key of their URL (see
+/// [`crate::normalize::UrlNormalizer::dedup_key`]), so a page reached through another form of
+/// the same URL, like `/page?` for `/page`, is still revalidated. Only pages seen by the current
+/// crawl are saved, so pages gone from the site drop out of the cache.
+pub struct PageCache {
+    path: PathBuf,
+    previous: HashMap<String, CachedPage>,
+    current: Mutex<HashMap<String, CachedPage>>,
+}
+
+impl PageCache {
+    /// Reads the cache saved at `path`. A missing file is an empty cache, and so is one from
+    /// another [`PAGE_CACHE_VERSION`] or one that can't be read, which is logged.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let previous = match fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str::<CacheFile<HashMap<_, _>>>(&json) {
+                Ok(file) if file.version == PAGE_CACHE_VERSION => file.pages,
+                Ok(file) => {
+                    log::info!(
+                        "Not using the page cache in {}, it's from version {} (now {})",
+                        path.display(),
+                        file.version,
+                        PAGE_CACHE_VERSION
+                    );
+                    HashMap::new()
+                }
+                Err(e) => {
+                    log::warn!("Not using the page cache in {}: {}", path.display(), e);
+                    HashMap::new()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                log::warn!("Not using the page cache in {}: {}", path.display(), e);
+                HashMap::new()
+            }
+        };
+        log::info!(
+            "{} pages in the page cache {}",
+            previous.len(),
+            path.display()
+        );
+        PageCache {
+            path,
+            previous,
+            current: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// What the last crawl saved about the page with dedup key `key`.
+    pub fn get(&self, key: &str) -> Option<CachedPage> {
+        self.previous.get(key).cloned()
+    }
+
+    /// Records `page` under the dedup key `key`, to be saved.
+    pub fn insert(&self, key: String, page: CachedPage) {
+        self.current.lock().unwrap().insert(key, page);
+    }
+
+    /// Pages recorded by this crawl.
+    pub fn len(&self) -> usize {
+        self.current.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the pages recorded by this crawl to the cache file, replacing it.
+    pub fn save(&self) -> io::Result<()> {
+        let current = self.current.lock().unwrap();
+        let file = CacheFile {
+            version: PAGE_CACHE_VERSION,
+            pages: &*current,
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_vec(&file)?)
+    }
+}
@@ -0,0 +1,32 @@
+use lopdf::{Document, Object};
+
+/// Extracts the target URIs of the hyperlinks embedded in a PDF.
+///
+/// Only link annotations with a URI action (`/Subtype /Link`, `/A << /S /URI /URI (...) >>`) are
+/// returned; internal "go to page" links have no URL to check.
+pub fn find_pdf_links(pdf: &[u8]) -> Result<Vec<String>, lopdf::Error> {
+    let document = Document::load_mem(pdf)?;
+    let mut links = Vec::new();
+
+    for page_id in document.get_pages().into_values() {
+        for annotation in document.get_page_annotations(page_id)? {
+            let is_link = annotation
+                .get(b"Subtype")
+                .and_then(Object::as_name)
+                .is_ok_and(|subtype| subtype == b"Link");
+            if !is_link {
+                continue;
+            }
+            let Ok(action) = document.get_dict_in_dict(annotation, b"A") else {
+                continue;
+            };
+            if let Ok(uri) = action.get(b"URI").and_then(Object::as_str) {
+                let uri = String::from_utf8_lossy(uri).into_owned();
+                log::debug!("Adding PDF link: {}", uri);
+                links.push(uri);
+            }
+        }
+    }
+
+    Ok(links)
+}
@@ -0,0 +1,236 @@
+use crate::debug_channel::ChannelStats;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Counters the crawler updates as it goes, readable from other tasks while it runs.
+#[derive(Debug, Default)]
+pub struct CrawlProgress {
+    pages_crawled: AtomicU64,
+    queued: AtomicUsize,
+    links_found: AtomicU64,
+    broken: AtomicU64,
+    broken_by_status: Mutex<BTreeMap<u16, u64>>,
+    stopped_at_max_errors: AtomicBool,
+}
+
+impl CrawlProgress {
+    /// URLs fetched so far, broken or not
+    pub fn pages_crawled(&self) -> u64 {
+        self.pages_crawled.load(Ordering::Relaxed)
+    }
+
+    /// URLs waiting to be fetched (may include duplicates that will be skipped)
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Distinct links found on pages and queued to be checked so far
+    pub fn links_found(&self) -> u64 {
+        self.links_found.load(Ordering::Relaxed)
+    }
+
+    /// Broken links reported so far
+    pub fn broken(&self) -> u64 {
+        self.broken.load(Ordering::Relaxed)
+    }
+
+    /// Broken links reported so far by the status they were reported with, 0 for no response
+    pub fn broken_by_status(&self) -> BTreeMap<u16, u64> {
+        self.broken_by_status.lock().unwrap().clone()
+    }
+
+    /// Whether the crawl stopped at `max_errors` with links left to check, so there may be more
+    /// broken links than were reported
+    pub fn stopped_at_max_errors(&self) -> bool {
+        self.stopped_at_max_errors.load(Ordering::Relaxed)
+    }
+
+    /// The counters as they are now, for the end of a crawl that took `elapsed`. `channel` is
+    /// [`DebugChannel::stats`] of the channel the findings went through.
+    ///
+    /// [`DebugChannel::stats`]: crate::debug_channel::DebugChannel::stats
+    pub fn summary(&self, elapsed: Duration, channel: ChannelStats) -> CrawlSummary {
+        CrawlSummary {
+            pages_crawled: self.pages_crawled(),
+            links_found: self.links_found(),
+            broken: self.broken(),
+            broken_by_status: self.broken_by_status(),
+            elapsed_ms: elapsed.as_millis() as u64,
+            max_channel_buffer: channel.max_usage,
+            findings_sent: channel.total_sent,
+        }
+    }
+
+    pub(crate) fn record_page(&self) {
+        self.pages_crawled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_queued(&self, queued: usize) {
+        self.queued.store(queued, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_stopped_at_max_errors(&self) {
+        self.stopped_at_max_errors.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_links(&self, links: u64) {
+        self.links_found.fetch_add(links, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_broken(&self, status: u16) {
+        self.broken.fetch_add(1, Ordering::Relaxed);
+        *self
+            .broken_by_status
+            .lock()
+            .unwrap()
+            .entry(status)
+            .or_default() += 1;
+    }
+}
+
+/// What a finished crawl did, logged at the end and written by `--summary-json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlSummary {
+    /// URLs fetched, broken or not
+    pub pages_crawled: u64,
+    /// Distinct links found on pages and queued to be checked
+    pub links_found: u64,
+    pub broken: u64,
+    /// Status -> broken links reported with it, 0 for no response
+    pub broken_by_status: BTreeMap<u16, u64>,
+    pub elapsed_ms: u64,
+    /// Most findings waiting in the channel at once
+    pub max_channel_buffer: usize,
+    /// Findings sent through the channel, including the ones not written to the report, like
+    /// `AlsoLinkedFrom` and the completion signal
+    pub findings_sent: u64,
+}
+
+/// One line: `pages=532 links=1210 broken=7 (404: 5, 0: 2) elapsed=120.5s max_channel_buffer=12
+/// findings_sent=1304`.
+impl fmt::Display for CrawlSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pages={} links={} broken={}",
+            self.pages_crawled, self.links_found, self.broken
+        )?;
+        if !self.broken_by_status.is_empty() {
+            let statuses: Vec<_> = self
+                .broken_by_status
+                .iter()
+                .map(|(status, count)| format!("{}: {}", status, count))
+                .collect();
+            write!(f, " ({})", statuses.join(", "))?;
+        }
+        write!(
+            f,
+            " elapsed={:.1}s max_channel_buffer={} findings_sent={}",
+            self.elapsed_ms as f64 / 1000.0,
+            self.max_channel_buffer,
+            self.findings_sent
+        )
+    }
+}
+
+/// Schedule for work repeated during a crawl, such as saving a snapshot of the results: due
+/// every `interval`, or every `every_pages` pages crawled, whichever comes first.
+#[derive(Debug)]
+pub struct Periodic {
+    interval: Option<Duration>,
+    every_pages: Option<u64>,
+    last_at: Instant,
+    last_pages: u64,
+}
+
+impl Periodic {
+    pub fn new(interval: Option<Duration>, every_pages: Option<u64>) -> Self {
+        Periodic {
+            interval,
+            every_pages,
+            last_at: Instant::now(),
+            last_pages: 0,
+        }
+    }
+
+    /// Whether either limit was reached since the last [`Periodic::done`].
+    pub fn due(&self, pages_crawled: u64) -> bool {
+        self.interval
+            .is_some_and(|interval| self.last_at.elapsed() >= interval)
+            || self
+                .every_pages
+                .is_some_and(|every| pages_crawled >= self.last_pages + every.max(1))
+    }
+
+    /// Starts the next period.
+    pub fn done(&mut self, pages_crawled: u64) {
+        self.last_at = Instant::now();
+        self.last_pages = pages_crawled;
+    }
+}
+
+/// Appends one line of [`CrawlProgress`] per call, for tailing unattended runs:
+///
+/// ```text
+/// 2026-10-14T09:30:00Z elapsed=120s pages=532 queued=1210 broken=7 rate=4.4/s
+/// ```
+///
+/// `rate` is pages per second since the previous line.
+pub struct ProgressLog<W: Write> {
+    out: W,
+    progress: Arc<CrawlProgress>,
+    started: Instant,
+    last_line_at: Instant,
+    last_line_pages: u64,
+}
+
+impl<W: Write> ProgressLog<W> {
+    pub fn new(out: W, progress: Arc<CrawlProgress>) -> Self {
+        let now = Instant::now();
+        ProgressLog {
+            out,
+            progress,
+            started: now,
+            last_line_at: now,
+            last_line_pages: 0,
+        }
+    }
+
+    /// Whether `interval` has passed or `every_pages` pages were crawled since the last line.
+    pub fn due(&self, interval: Duration, every_pages: Option<u64>) -> bool {
+        self.last_line_at.elapsed() >= interval
+            || every_pages.is_some_and(|every| {
+                self.progress.pages_crawled() >= self.last_line_pages + every.max(1)
+            })
+    }
+
+    pub fn write_line(&mut self) -> io::Result<()> {
+        let now = Instant::now();
+        let pages = self.progress.pages_crawled();
+        let since_last = now.duration_since(self.last_line_at).as_secs_f64();
+        let rate = if since_last > 0.0 {
+            (pages - self.last_line_pages) as f64 / since_last
+        } else {
+            0.0
+        };
+        writeln!(
+            self.out,
+            "{} elapsed={}s pages={} queued={} broken={} rate={:.1}/s",
+            humantime::format_rfc3339_seconds(SystemTime::now()),
+            now.duration_since(self.started).as_secs(),
+            pages,
+            self.progress.queued(),
+            self.progress.broken(),
+            rate
+        )?;
+        self.out.flush()?;
+        self.last_line_at = now;
+        self.last_line_pages = pages;
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use crate::crawler::Crawler;
+use crate::report::{NotFoundError, ReferrerReport};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use url::Url;
+
+/// Which previously broken links `--recheck-status` selects: one status, or a class like `4xx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFilter {
+    Status(u16),
+    /// The first digit of the statuses in the class, e.g. 4 for `4xx`
+    Class(u16),
+}
+
+impl StatusFilter {
+    pub fn matches(&self, status: u16) -> bool {
+        match self {
+            StatusFilter::Status(wanted) => status == *wanted,
+            StatusFilter::Class(class) => status / 100 == *class,
+        }
+    }
+}
+
+impl FromStr for StatusFilter {
+    type Err = String;
+
+    /// Parses a status like `404` or a class like `4xx`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "expected a status like 404 or a class like 4xx, got '{}'",
+                s
+            )
+        };
+        match s.to_ascii_lowercase().strip_suffix("xx") {
+            Some(digit) if digit.len() == 1 => match digit.parse::<u16>() {
+                Ok(class @ 1..=5) => Ok(StatusFilter::Class(class)),
+                _ => Err(invalid()),
+            },
+            Some(_) => Err(invalid()),
+            None => match s.parse::<u16>() {
+                Ok(status @ 100..=599) => Ok(StatusFilter::Status(status)),
+                _ => Err(invalid()),
+            },
+        }
+    }
+}
+
+/// Reads the broken links of a results file, in either `--group-by` shape, as a
+/// [`crate::report::Report`] or a bare array (`--legacy-output`), or as JSON Lines.
+///
+/// Lychee reports (`--format lychee`) can't be read back: they leave out what's needed to
+/// check a link again the same way, like its kind.
+pub fn load_report(json: &str) -> Result<Vec<NotFoundError>, serde_json::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Entries {
+        Url(Vec<NotFoundError>),
+        Referrer(Vec<ReferrerReport>),
+    }
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Report {
+        // The rest of the header isn't needed to check the links again
+        Versioned { broken_links: Entries },
+        Legacy(Entries),
+    }
+
+    let entries = match serde_json::from_str(json) {
+        Ok(Report::Versioned { broken_links }) | Ok(Report::Legacy(broken_links)) => broken_links,
+        // `--format jsonl` writes an object per line instead
+        Err(_) if json.trim_start().starts_with('{') => return load_lines(json),
+        Err(e) => return Err(e),
+    };
+    Ok(match entries {
+        Entries::Url(errors) => errors,
+        Entries::Referrer(pages) => pages
+            .into_iter()
+            .flat_map(|page| {
+                let referrer = page.referrer;
+                page.broken_links
+                    .into_iter()
+                    .map(move |link| NotFoundError {
+                        url: link.url,
+                        title: link.title,
+                        source_url: referrer.clone(),
+                        suggestion: link.suggestion,
+                        kind: link.kind,
+                        error: link.error,
+                        class: link.class,
+                        status: link.status,
+                        redirected_to: link.redirected_to,
+                        also_linked_from: Vec::new(),
+                        soft_404: link.soft_404,
+                    })
+            })
+            .collect(),
+    })
+}
+
+/// Reads a `--format jsonl` report, merging the lines of a URL found on several pages.
+fn load_lines(jsonl: &str) -> Result<Vec<NotFoundError>, serde_json::Error> {
+    let mut errors: Vec<NotFoundError> = Vec::new();
+    for line in jsonl.lines().filter(|line| !line.trim().is_empty()) {
+        let error: NotFoundError = serde_json::from_str(line)?;
+        match (
+            errors.iter_mut().find(|e| e.url == error.url),
+            error.source_url,
+        ) {
+            (Some(first), Some(page)) => first.add_source(page),
+            (Some(_), None) => {}
+            (None, source_url) => errors.push(NotFoundError {
+                source_url,
+                ..error
+            }),
+        }
+    }
+    Ok(errors)
+}
+
+/// A previously broken link, checked again.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecheckedLink {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    pub previous_status: u16,
+    /// `None` if no response was received
+    pub status: Option<u16>,
+    /// The error in the response body, with `body_error_paths`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RecheckedLink {
+    /// A link is fixed once it answers with a 2xx (after redirects) and no error in its body.
+    pub fn is_fixed(&self) -> bool {
+        self.status
+            .is_some_and(|status| (200..300).contains(&status))
+            && self.error.is_none()
+    }
+}
+
+/// What changed since the previous report, for `--recheck-from`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecheckReport {
+    pub fixed: Vec<RecheckedLink>,
+    pub still_broken: Vec<RecheckedLink>,
+}
+
+impl RecheckReport {
+    pub fn new(links: Vec<RecheckedLink>) -> Self {
+        let (fixed, still_broken) = links.into_iter().partition(RecheckedLink::is_fixed);
+        RecheckReport {
+            fixed,
+            still_broken,
+        }
+    }
+}
+
+/// Where the recheck report for `previous` goes: `results/example.com.json` is rechecked into
+/// `results/example.com-recheck.json`.
+pub fn report_path(previous: &Path) -> PathBuf {
+    let stem = previous.file_stem().unwrap_or_default().to_string_lossy();
+    previous.with_file_name(format!("{}-recheck.json", stem))
+}
+
+/// Checks each of `errors` again with `crawler`, `concurrency` URLs at a time, without
+/// following any links. A URL listed on several pages is requested once.
+pub async fn recheck(
+    crawler: Arc<Crawler>,
+    errors: &[NotFoundError],
+    concurrency: usize,
+) -> Vec<RecheckedLink> {
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut requests = JoinSet::new();
+    let mut results = HashMap::new();
+    for error in errors {
+        if results.insert(error.url.clone(), None).is_some() {
+            continue;
+        }
+        let (crawler, permits, url) = (crawler.clone(), permits.clone(), error.url.clone());
+        requests.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let result = match Url::parse(&url) {
+                Ok(parsed) => match crawler.recheck(&parsed).await {
+                    Ok((checked, error)) => (checked.status, error),
+                    Err(e) => {
+                        log::info!("{}: {}", url, e);
+                        (None, None)
+                    }
+                },
+                Err(e) => {
+                    log::warn!("Not rechecking {}: {}", url, e);
+                    (None, None)
+                }
+            };
+            (url, result)
+        });
+    }
+    while let Some(joined) = requests.join_next().await {
+        if let Ok((url, result)) = joined {
+            results.insert(url, Some(result));
+        }
+    }
+
+    errors
+        .iter()
+        .map(|error| {
+            let (status, body_error) = results
+                .get(&error.url)
+                .cloned()
+                .flatten()
+                .unwrap_or_default();
+            RecheckedLink {
+                url: error.url.clone(),
+                source_url: error.source_url.clone(),
+                previous_status: error.response_status(),
+                status,
+                error: body_error,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,594 @@
+use crate::crawler::{CheckedUrl, Finding};
+use crate::links::{LinkClass, LinkKind};
+use crate::progress::CrawlSummary;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NotFoundError {
+    pub url: String,
+    pub title: Option<String>, // <title> of the error page; not every error page is HTML or has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>, // Page (or PDF) that linked to the broken URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>, // Likely fix, only filled in with --suggest-fixes
+    #[serde(default, skip_serializing_if = "LinkKind::is_link")]
+    pub kind: LinkKind, // Only written for links that aren't plain links, e.g. HTMX endpoints
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // Error in a 2xx response body, or why another site didn't answer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<LinkClass>, // Where the link points and whether it's an asset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>, // HTTP status of the response, 0 for none; missing in older reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>, // Where the link redirected to, if it did
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also_linked_from: Vec<String>, // Other pages with the same broken link, besides source_url
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub soft_404: bool, // Loaded with a 2xx, but an error page; only written when true
+}
+
+impl NotFoundError {
+    /// Every page the broken link is on, `source_url` first; empty for the root URL.
+    pub fn source_urls(&self) -> impl Iterator<Item = &str> {
+        self.source_url
+            .iter()
+            .chain(&self.also_linked_from)
+            .map(String::as_str)
+    }
+
+    /// Records another page with this broken link, unless it's already listed.
+    pub fn add_source(&mut self, page: String) {
+        if !self.source_urls().any(|source| source == page) {
+            self.also_linked_from.push(page);
+        }
+    }
+
+    /// The report entry for a [`Finding::NotFound`]; `None` for any other finding.
+    pub fn from_finding(finding: Finding) -> Option<Self> {
+        match finding {
+            Finding::NotFound {
+                url,
+                title,
+                referrer,
+                suggestion,
+                kind,
+                error,
+                class,
+                status,
+                redirected_to,
+                soft_404,
+            } => Some(NotFoundError {
+                url,
+                title,
+                source_url: referrer,
+                suggestion,
+                kind,
+                error,
+                class,
+                status,
+                redirected_to,
+                also_linked_from: Vec::new(),
+                soft_404,
+            }),
+            _ => None,
+        }
+    }
+
+    /// HTTP status the link was broken with. Reports from before statuses were recorded only
+    /// hold 404s, and 200s with an error in the body.
+    pub fn response_status(&self) -> u16 {
+        self.status
+            .unwrap_or(if self.error.is_some() { 200 } else { 404 })
+    }
+}
+
+/// Adds what `finding` says about broken links to `errors`: a new entry for a
+/// [`Finding::NotFound`], another page on the entry for its URL for a
+/// [`Finding::AlsoLinkedFrom`]. Other findings are ignored.
+pub fn add_finding(errors: &mut Vec<NotFoundError>, finding: Finding) {
+    match finding {
+        Finding::AlsoLinkedFrom { url, referrer } => {
+            if let Some(error) = errors.iter_mut().rev().find(|e| e.url == url) {
+                error.add_source(referrer);
+            }
+        }
+        finding => errors.extend(NotFoundError::from_finding(finding)),
+    }
+}
+
+/// The broken-links report grouped by URL, as saved: sorted by URL so two runs finding the same
+/// broken links write the same file, whatever order they were found in.
+///
+/// With `dedupe`, entries for the same URL, which a report merged from several crawls can hold,
+/// become one, listing every page of each; their other pages are sorted too. Without it, a URL
+/// gets an entry per page it's on, each with just that `source_url`, for counting links rather
+/// than URLs.
+pub fn url_report(errors: &[NotFoundError], dedupe: bool) -> Vec<NotFoundError> {
+    let mut report: Vec<NotFoundError> = Vec::with_capacity(errors.len());
+    if dedupe {
+        let mut by_url: BTreeMap<&str, NotFoundError> = BTreeMap::new();
+        for error in errors {
+            match by_url.get_mut(error.url.as_str()) {
+                Some(merged) => {
+                    for source in error.source_urls() {
+                        merged.add_source(source.to_string());
+                    }
+                }
+                None => {
+                    by_url.insert(&error.url, error.clone());
+                }
+            }
+        }
+        report.extend(by_url.into_values().map(|mut error| {
+            error.also_linked_from.sort();
+            error
+        }));
+    } else {
+        for error in errors {
+            let entry = |source_url| NotFoundError {
+                source_url,
+                also_linked_from: Vec::new(),
+                ..error.clone()
+            };
+            if error.source_url.is_none() {
+                report.push(entry(None));
+            }
+            report.extend(
+                error
+                    .source_urls()
+                    .map(|source| entry(Some(source.to_string()))),
+            );
+        }
+        report.sort_by(|a, b| (&a.url, &a.source_url).cmp(&(&b.url, &b.source_url)));
+    }
+    report
+}
+
+/// Shape of the broken-links report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// One entry per broken URL
+    #[default]
+    Url,
+    /// One entry per page, listing the broken links on it: a to-do list for whoever edits it
+    #[value(alias = "source")]
+    Referrer,
+}
+
+/// The broken links found on one page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReferrerReport {
+    /// `None` for the root URL, which no page links to
+    pub referrer: Option<String>,
+    pub broken_links: Vec<BrokenLink>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    #[serde(default, skip_serializing_if = "LinkKind::is_link")]
+    pub kind: LinkKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<LinkClass>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub soft_404: bool,
+}
+
+/// Regroups broken links by the page they were found on, pages sorted by URL. A link on several
+/// pages is listed under each.
+pub fn group_by_referrer(errors: &[NotFoundError]) -> Vec<ReferrerReport> {
+    let mut by_referrer: BTreeMap<Option<&str>, Vec<BrokenLink>> = BTreeMap::new();
+    for error in errors {
+        let link = BrokenLink {
+            url: error.url.clone(),
+            title: error.title.clone(),
+            suggestion: error.suggestion.clone(),
+            kind: error.kind,
+            error: error.error.clone(),
+            class: error.class,
+            status: error.status,
+            redirected_to: error.redirected_to.clone(),
+            soft_404: error.soft_404,
+        };
+        if error.source_url.is_none() {
+            by_referrer.entry(None).or_default().push(link.clone());
+        }
+        for source in error.source_urls() {
+            by_referrer
+                .entry(Some(source))
+                .or_default()
+                .push(link.clone());
+        }
+    }
+    by_referrer
+        .into_iter()
+        .map(|(referrer, broken_links)| ReferrerReport {
+            referrer: referrer.map(str::to_string),
+            broken_links,
+        })
+        .collect()
+}
+
+/// Version of the [`Report`] shape, bumped whenever a field is renamed, removed or changes
+/// meaning. New optional fields don't bump it.
+pub const REPORT_VERSION: u32 = 1;
+
+/// What a [`Report`] says about the crawl that found its broken links.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReportHeader {
+    /// [`REPORT_VERSION`] of the file
+    pub version: u32,
+    /// The root URL
+    pub crawled_url: String,
+    /// When the crawl started, RFC 3339 in UTC
+    pub timestamp: String,
+    /// The crawl so far, for a report saved while it runs
+    pub summary: CrawlSummary,
+}
+
+impl ReportHeader {
+    pub fn new(crawled_url: &str, started_at: SystemTime, summary: CrawlSummary) -> Self {
+        ReportHeader {
+            version: REPORT_VERSION,
+            crawled_url: crawled_url.to_string(),
+            timestamp: humantime::format_rfc3339_seconds(started_at).to_string(),
+            summary,
+        }
+    }
+}
+
+/// The broken-links report as saved, self-describing: `broken_links` holds the entries of
+/// [`url_report`] or [`group_by_referrer`], next to the fields of the [`ReportHeader`]. With
+/// `--legacy-output`, `broken_links` is saved on its own, as a bare array.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Report<T> {
+    #[serde(flatten)]
+    pub header: ReportHeader,
+    pub broken_links: Vec<T>,
+}
+
+/// Format of the broken-links report file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// This tool's own JSON, shaped by `--group-by`
+    #[default]
+    Native,
+    /// The JSON schema of `lychee --format json` (lychee 0.15), for existing dashboards
+    Lychee,
+    /// Sorted `STATUS URL (from REFERRER)` lines, for committing and diffing
+    Text,
+    /// One JSON object per line, each written as soon as the broken link is found
+    Jsonl,
+    /// `url,status,title,source_url` rows, for spreadsheets
+    Csv,
+}
+
+/// Crawl summary in the JSON schema of lychee 0.15 (`lychee --format json`), so dashboards and
+/// scripts built on lychee's output can read our results unchanged.
+///
+/// Maps are keyed by the page a link was found on (lychee's "input"); a broken root URL is
+/// listed under itself. Only the counters and maps this crawler has data for are filled in:
+/// `total`, `successful`, `excludes`, `errors`, `fail_map` and `duration_secs`. Everything else
+/// is zero or empty, but present, since consumers expect every field.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct LycheeStats {
+    pub total: usize,
+    pub successful: usize,
+    pub unknown: usize,
+    pub unsupported: usize,
+    pub timeouts: usize,
+    pub redirects: usize,
+    pub excludes: usize,
+    pub errors: usize,
+    pub cached: usize,
+    pub success_map: BTreeMap<String, Vec<LycheeResponse>>,
+    pub fail_map: BTreeMap<String, Vec<LycheeResponse>>,
+    pub suggestion_map: BTreeMap<String, Vec<LycheeSuggestion>>,
+    pub excluded_map: BTreeMap<String, Vec<LycheeResponse>>,
+    pub duration_secs: u64,
+    pub detailed_stats: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LycheeResponse {
+    pub url: String,
+    pub status: LycheeStatus,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LycheeStatus {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<u16>,
+}
+
+/// A replacement URL, in lychee's `suggestion_map`. Our suggestions are free-form text rather
+/// than URLs, so this map is always empty.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LycheeSuggestion {
+    pub original: String,
+    pub suggestion: String,
+}
+
+impl LycheeStats {
+    /// Stats with `fail_map` and `errors` filled in from the broken links; the other counters
+    /// are left for the caller to set.
+    pub fn from_broken_links(root_url: &str, errors: &[NotFoundError]) -> Self {
+        let mut fail_map: BTreeMap<String, Vec<LycheeResponse>> = BTreeMap::new();
+        let mut failed = 0;
+        for error in errors {
+            let status = match &error.error {
+                // The response was a success, just not a useful one
+                Some(message) => LycheeStatus {
+                    text: message.clone(),
+                    code: None,
+                },
+                None => {
+                    let code = error.response_status();
+                    let text = match reqwest::StatusCode::from_u16(code) {
+                        Ok(status) => status.to_string(),
+                        Err(_) => code.to_string(),
+                    };
+                    LycheeStatus {
+                        text,
+                        code: Some(code),
+                    }
+                }
+            };
+            // A broken root URL is listed under itself
+            let root = error.source_url.is_none().then_some(root_url);
+            for input in root.into_iter().chain(error.source_urls()) {
+                fail_map
+                    .entry(input.to_string())
+                    .or_default()
+                    .push(LycheeResponse {
+                        url: error.url.clone(),
+                        status: status.clone(),
+                    });
+                failed += 1;
+            }
+        }
+        LycheeStats {
+            errors: failed,
+            fail_map,
+            ..LycheeStats::default()
+        }
+    }
+}
+
+/// The report for `--format text`: one `STATUS URL (from REFERRER)` line per broken link, or
+/// `STATUS URL` for the root URL, sorted and without duplicates.
+///
+/// Nothing in it depends on the order links were found in or on when the crawl ran, so two
+/// reports of the same broken links are identical and a diff between runs shows only real changes.
+pub fn text_report(errors: &[NotFoundError]) -> String {
+    let mut lines = Vec::new();
+    for error in errors {
+        let status = error.response_status();
+        if error.source_url.is_none() {
+            lines.push(text_line(status, &error.url, None));
+        }
+        for referrer in error.source_urls() {
+            lines.push(text_line(status, &error.url, Some(referrer)));
+        }
+    }
+    lines.sort();
+    lines.dedup();
+    lines.into_iter().map(|line| line + "\n").collect()
+}
+
+/// A line of the text report, without the line break: `STATUS URL (from REFERRER)`, or
+/// `STATUS URL` for the root URL.
+pub fn text_line(status: u16, url: &str, referrer: Option<&str>) -> String {
+    match referrer {
+        Some(referrer) => format!("{} {} (from {})", status, url, referrer),
+        None => format!("{} {}", status, url),
+    }
+}
+
+/// The report for `--format csv`: a header, then a `url,status,title,source_url` row per broken
+/// link and page it's on, in the order found. `source_url` is empty for the root URL, `title`
+/// when the error page had none.
+pub fn csv_report(errors: &[NotFoundError]) -> String {
+    let mut csv = csv::Writer::from_writer(Vec::new());
+    let mut row = |fields: [&str; 4]| {
+        csv.write_record(fields)
+            .expect("writing CSV to memory can't fail")
+    };
+    row(["url", "status", "title", "source_url"]);
+    for error in errors {
+        let status = error.response_status().to_string();
+        let title = error.title.as_deref().unwrap_or_default();
+        if error.source_url.is_none() {
+            row([&error.url, &status, title, ""]);
+        }
+        for source_url in error.source_urls() {
+            row([&error.url, &status, title, source_url]);
+        }
+    }
+    let csv = csv.into_inner().expect("flushing to memory can't fail");
+    String::from_utf8(csv).expect("every field is a string")
+}
+
+/// Where a run's files go: `<dir>/<hostname>.json`, `<dir>/<hostname>-all.csv` and so on.
+///
+/// A timestamped run puts the Unix time it started after the hostname, as in
+/// `results/example.com-1760443200.json`, so it doesn't overwrite earlier reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultsDir {
+    dir: PathBuf,
+    hostname: String,
+    timestamp: Option<u64>,
+}
+
+impl ResultsDir {
+    pub fn new(dir: impl Into<PathBuf>, hostname: &str, timestamp: Option<SystemTime>) -> Self {
+        ResultsDir {
+            dir: dir.into(),
+            hostname: hostname.to_string(),
+            timestamp: timestamp.map(|time| {
+                time.duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            }),
+        }
+    }
+
+    /// The file for this run with `suffix` after the hostname (and timestamp), like `-all`, or
+    /// `""` for the report itself.
+    pub fn file(&self, suffix: &str, extension: &str) -> PathBuf {
+        let stem = match self.timestamp {
+            Some(timestamp) => format!("{}-{}", self.hostname, timestamp),
+            None => self.hostname.clone(),
+        };
+        self.dir.join(format!("{}{}.{}", stem, suffix, extension))
+    }
+
+    /// `<hostname>-latest.<extension>`, the copy of a timestamped run's report that tools can
+    /// read without knowing when the last run was. `None` when the run isn't timestamped.
+    pub fn latest(&self, extension: &str) -> Option<PathBuf> {
+        self.timestamp?;
+        Some(
+            self.dir
+                .join(format!("{}-latest.{}", self.hostname, extension)),
+        )
+    }
+}
+
+/// Copies the report at `report` to `latest` the same way as [`save_json`]. A copy rather than
+/// a symlink, which not every system or CI artifact store keeps.
+pub fn copy_report(report: &Path, latest: &Path) -> io::Result<()> {
+    let mut source = File::open(report)?;
+    replace_file(latest, |file| io::copy(&mut source, file).map(|_| ()))
+}
+
+/// Writes `value` as pretty JSON to `file_path`, creating its directory if needed.
+///
+/// The JSON goes to a temporary file next to it first, which is then renamed over `file_path`,
+/// so a crash or kill mid-write leaves the previous version rather than a truncated file.
+pub fn save_json(value: &impl Serialize, file_path: &Path) -> io::Result<()> {
+    replace_file(file_path, |file| {
+        Ok(serde_json::to_writer_pretty(file, value)?)
+    })
+}
+
+/// Writes `text` to `file_path` the same way as [`save_json`].
+pub fn save_text(text: &str, file_path: &Path) -> io::Result<()> {
+    replace_file(file_path, |file| file.write_all(text.as_bytes()))
+}
+
+fn replace_file(
+    file_path: &Path,
+    write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> io::Result<()> {
+    let dir = file_path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+
+    let mut temp_name = file_path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = dir.join(temp_name);
+    let mut file = File::create(&temp_path)?;
+    write(&mut file)?;
+    file.sync_all()?;
+    fs::rename(&temp_path, file_path)
+}
+
+/// Streams broken links to a JSON Lines file as the crawl goes, for `--format jsonl`, so none
+/// are held in memory and a crawl that's killed keeps every line written before it.
+///
+/// Each line is a [`NotFoundError`]. Lines can't be amended once written, so a URL found on
+/// another page gets a line of its own with just its `url` and that page as `source_url`.
+pub struct JsonlWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonlWriter<W> {
+    pub fn new(out: W) -> Self {
+        JsonlWriter { out }
+    }
+
+    /// Writes and flushes the line for a [`Finding::NotFound`] or [`Finding::AlsoLinkedFrom`];
+    /// other findings are ignored.
+    pub fn record(&mut self, finding: Finding) -> io::Result<()> {
+        let error = match finding {
+            Finding::AlsoLinkedFrom { url, referrer } => NotFoundError {
+                url,
+                title: None,
+                source_url: Some(referrer),
+                suggestion: None,
+                kind: LinkKind::default(),
+                error: None,
+                class: None,
+                status: None,
+                redirected_to: None,
+                also_linked_from: Vec::new(),
+                soft_404: false,
+            },
+            finding => match NotFoundError::from_finding(finding) {
+                Some(error) => error,
+                None => return Ok(()),
+            },
+        };
+        serde_json::to_writer(&mut self.out, &error)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Streams every checked URL to a CSV file as the crawl goes, for `--report-all`.
+///
+/// Columns: `url,status,elapsed_ms,content_type,referrer,checked_at`. `status` is empty when no
+/// response was received, `referrer` for the root URL; `checked_at` is RFC 3339 in UTC.
+pub struct CheckedCsvWriter<W: Write> {
+    out: csv::Writer<W>,
+}
+
+impl<W: Write> CheckedCsvWriter<W> {
+    pub fn new(out: W) -> io::Result<Self> {
+        let mut out = csv::Writer::from_writer(out);
+        out.write_record([
+            "url",
+            "status",
+            "elapsed_ms",
+            "content_type",
+            "referrer",
+            "checked_at",
+        ])?;
+        Ok(CheckedCsvWriter { out })
+    }
+
+    pub fn record(&mut self, checked: &CheckedUrl) -> io::Result<()> {
+        self.out.write_record([
+            checked.url.as_str(),
+            &checked.status.map(|s| s.to_string()).unwrap_or_default(),
+            &checked.elapsed.as_millis().to_string(),
+            checked.content_type.as_deref().unwrap_or_default(),
+            checked.referrer.as_deref().unwrap_or_default(),
+            &humantime::format_rfc3339_millis(checked.checked_at).to_string(),
+        ])?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -0,0 +1,106 @@
+use rand::Rng;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How many times to retry a request that failed with a given HTTP status, or got no response.
+///
+/// Statuses not in the policy (404 included) are never retried: they are a definitive answer.
+/// The exception is `max_retries`, which covers every 5xx the policy doesn't list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    per_status: HashMap<u16, u32>,
+    max_retries: u32,
+    base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that retries nothing.
+    fn empty() -> Self {
+        RetryPolicy {
+            per_status: HashMap::new(),
+            max_retries: 0,
+            base_delay: Duration::from_millis(500),
+        }
+    }
+
+    /// Retries for requests that got no response (refused or dropped connections, timeouts) and
+    /// for 5xx statuses not in the policy, which keep their own count. 0 by default.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Delay before the first retry, doubled for each one after it. 500ms by default.
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn retries_for(&self, status: u16) -> u32 {
+        match self.per_status.get(&status) {
+            Some(retries) => *retries,
+            None if (500..600).contains(&status) => self.max_retries,
+            None => 0,
+        }
+    }
+
+    /// Retries for a request that got no response at all.
+    pub fn retries_without_response(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Delay before retry number `attempt` (starting at 0): 500ms, 1s, 2s, ... with the default
+    /// base delay, plus up to half as much again at random so that requests failing together
+    /// don't all retry at once.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        let jitter = rand::thread_rng().gen_range(0.0..=0.5);
+        delay.saturating_add(delay.mul_f64(jitter))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Back off hard on rate limiting, give overloaded servers a few chances, and retry a plain
+    /// 500 once in case it was a blip.
+    fn default() -> Self {
+        RetryPolicy {
+            per_status: HashMap::from([(429, 5), (502, 2), (503, 3), (504, 2), (500, 1)]),
+            ..RetryPolicy::empty()
+        }
+    }
+}
+
+impl FromStr for RetryPolicy {
+    type Err = String;
+
+    /// Parses `STATUS:RETRIES` pairs separated by commas, e.g. `429:5,503:3,500:1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut per_status = HashMap::new();
+        for entry in s
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (status, retries) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("expected STATUS:RETRIES, got '{}'", entry))?;
+            let status: u16 = status
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid status code '{}'", status))?;
+            if !(100..=599).contains(&status) {
+                return Err(format!("invalid status code '{}'", status));
+            }
+            let retries: u32 = retries
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid retry count '{}'", retries))?;
+            per_status.insert(status, retries);
+        }
+        Ok(RetryPolicy {
+            per_status,
+            ..RetryPolicy::empty()
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+/// Crawl directives that apply to a single page.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RobotsDirectives {
+    /// Don't follow any link on the page
+    pub nofollow: bool,
+    /// The page asks not to be indexed (reported, doesn't change what is crawled)
+    pub noindex: bool,
+}
+
+impl RobotsDirectives {
+    /// Parses the values of one or more `X-Robots-Tag` headers, e.g. `noindex, nofollow`.
+    ///
+    /// Directives scoped to a specific crawler (`googlebot: nofollow`) are ignored since they
+    /// aren't addressed to us. `none` means both `noindex` and `nofollow`.
+    pub fn from_x_robots_tag<'a>(values: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut directives = RobotsDirectives::default();
+        for value in values {
+            // A "<bot>:" prefix scopes the rest of the header value to that bot
+            if let Some((scope, _)) = value.split_once(':') {
+                if !scope.contains(',') && !is_known_directive(scope) {
+                    continue;
+                }
+            }
+            directives.apply(value);
+        }
+        directives
+    }
+
+    /// Parses the `content` of a page's `<meta name="robots">` tags, which take the same
+    /// directives as the header, without bot prefixes.
+    pub fn from_meta_robots<'a>(contents: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut directives = RobotsDirectives::default();
+        for content in contents {
+            directives.apply(content);
+        }
+        directives
+    }
+
+    /// Applies a comma-separated directive list like `noindex, nofollow`.
+    pub(crate) fn apply(&mut self, list: &str) {
+        for directive in list.split(',') {
+            match directive.trim().to_ascii_lowercase().as_str() {
+                "nofollow" => self.nofollow = true,
+                "noindex" => self.noindex = true,
+                "none" => {
+                    self.nofollow = true;
+                    self.noindex = true;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_known_directive(name: &str) -> bool {
+    matches!(
+        name.trim().to_ascii_lowercase().as_str(),
+        "all"
+            | "none"
+            | "noindex"
+            | "nofollow"
+            | "noarchive"
+            | "nosnippet"
+            | "notranslate"
+            | "noimageindex"
+            | "unavailable_after"
+            | "max-snippet"
+            | "max-image-preview"
+            | "max-video-preview"
+    )
+}
@@ -0,0 +1,73 @@
+use url::Url;
+
+/// Patterns for links that look like they trigger an action rather than show a page.
+pub const DEFAULT_UNSAFE_PATTERNS: &[&str] = &[
+    "*delete*",
+    "*remove*",
+    "*logout*",
+    "*log-out*",
+    "*signout*",
+    "*sign-out*",
+    "*unsubscribe*",
+];
+
+/// Decides which URLs are too risky to request in `--safe-only` mode.
+///
+/// Some sites expose state-changing actions as plain links (`/delete?id=5`, `/logout`), which a
+/// crawler would happily follow. Patterns are matched case-insensitively against the path and
+/// query of the URL; `*` matches any run of characters and everything else matches literally.
+#[derive(Debug, Clone)]
+pub struct UnsafeUrlFilter {
+    patterns: Vec<String>,
+}
+
+impl UnsafeUrlFilter {
+    pub fn new(patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        UnsafeUrlFilter {
+            patterns: patterns
+                .into_iter()
+                .map(|pattern| pattern.into().to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// The first pattern that `url` matches, if any.
+    pub fn matching_pattern(&self, url: &Url) -> Option<&str> {
+        let mut target = url.path().to_lowercase();
+        if let Some(query) = url.query() {
+            target.push('?');
+            target.push_str(&query.to_lowercase());
+        }
+        self.patterns
+            .iter()
+            .find(|pattern| glob_matches(pattern, &target))
+            .map(String::as_str)
+    }
+}
+
+impl Default for UnsafeUrlFilter {
+    fn default() -> Self {
+        UnsafeUrlFilter::new(DEFAULT_UNSAFE_PATTERNS.iter().copied())
+    }
+}
+
+/// Whether `text` matches `pattern` as a whole, where `*` matches any run of characters.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*` at all: the whole text has to match
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
@@ -0,0 +1,108 @@
+use std::str::FromStr;
+use url::Url;
+
+/// One `--scope` entry: a host, optionally pinned to a scheme and a port.
+///
+/// Entries replace the domain comparison when any are given, so a crawl can cover exactly the
+/// app on `:8443` and the API on `:3000` without following links to every other port or
+/// subdomain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeEntry {
+    scheme: Option<String>,
+    /// Lowercase, with IPv6 addresses in brackets like [`Url::host_str`] returns them
+    host: String,
+    port: Option<u16>,
+}
+
+impl ScopeEntry {
+    /// Whether `url` is on this entry's host and, if given, scheme and port. Ports are compared
+    /// after filling in the scheme's default, so `example.com:443` matches `https://example.com/`.
+    pub fn matches(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        host.eq_ignore_ascii_case(&self.host)
+            && self
+                .scheme
+                .as_ref()
+                .is_none_or(|scheme| scheme == url.scheme())
+            && self
+                .port
+                .is_none_or(|port| Some(port) == url.port_or_known_default())
+    }
+}
+
+/// Whether `domain` is `parent` or one of its subdomains, comparing whole labels: `blog.example.com`
+/// and `a.b.example.com` are under `example.com`, but `notexample.com` and
+/// `example.com.evil.com` aren't. Case and a trailing dot don't matter.
+pub fn is_subdomain_of(domain: &str, parent: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    let parent = parent.trim_end_matches('.').to_ascii_lowercase();
+    !parent.is_empty()
+        && domain
+            .strip_suffix(&parent)
+            .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+}
+
+/// Whether `domain` matches the fuzzy match string `fuzzy` on label boundaries, not as a
+/// substring: a single label like `shop` matches a domain with that label anywhere
+/// (`shop.example.com`, `www.shop.io`), and a dotted one like `example.com` matches that domain
+/// or its subdomains, as [`is_subdomain_of`]. So `example.com` doesn't match
+/// `example.com.attacker.net`, and `shop` doesn't match `myshop.com`.
+pub fn fuzzy_matches(domain: &str, fuzzy: &str) -> bool {
+    let fuzzy = fuzzy.trim_matches('.');
+    if fuzzy.contains('.') {
+        return is_subdomain_of(domain, fuzzy);
+    }
+    !fuzzy.is_empty()
+        && domain
+            .trim_end_matches('.')
+            .split('.')
+            .any(|label| label.eq_ignore_ascii_case(fuzzy))
+}
+
+impl FromStr for ScopeEntry {
+    type Err = String;
+
+    /// Parses `[scheme://]host[:port]`, e.g. `example.com`, `api.example.com:3000` or
+    /// `https://[::1]:8443`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, rest) = match s.split_once("://") {
+            Some((scheme, rest)) => (Some(scheme.to_ascii_lowercase()), rest),
+            None => (None, s),
+        };
+        let (host, port) = match rest.strip_prefix('[') {
+            // An IPv6 address, whose colons aren't a port separator
+            Some(address) => {
+                let (address, after) = address
+                    .split_once(']')
+                    .ok_or_else(|| format!("unclosed '[' in scope '{}'", s))?;
+                let port = match after {
+                    "" => None,
+                    _ => Some(after.strip_prefix(':').ok_or_else(|| {
+                        format!("expected ':port' after the address in scope '{}'", s)
+                    })?),
+                };
+                (format!("[{}]", address), port)
+            }
+            None => match rest.rsplit_once(':') {
+                Some((host, port)) => (host.to_string(), Some(port)),
+                None => (rest.to_string(), None),
+            },
+        };
+        if host.is_empty() || host.contains(['/', '?', '#', '@']) {
+            return Err(format!("scope '{}' should be [scheme://]host[:port]", s));
+        }
+        let port = port
+            .map(|port| {
+                port.parse::<u16>()
+                    .map_err(|_| format!("invalid port '{}' in scope '{}'", port, s))
+            })
+            .transpose()?;
+        Ok(ScopeEntry {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            port,
+        })
+    }
+}
@@ -0,0 +1,62 @@
+use flate2::read::GzDecoder;
+use regex::Regex;
+use std::io::Read;
+use std::sync::OnceLock;
+
+/// The `<loc>` entries of a sitemap, for `--use-sitemap`: page URLs from a `<urlset>`, or the
+/// URLs of more sitemaps from a `<sitemapindex>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sitemap {
+    Urls(Vec<String>),
+    Index(Vec<String>),
+}
+
+impl Sitemap {
+    /// Parses a sitemap, gunzipping it first if it's gzipped (`sitemap.xml.gz`), which is told
+    /// by its magic bytes rather than the URL or content type, since servers label those any
+    /// which way.
+    ///
+    /// Only the `<loc>` elements are read, with the XML entities and any CDATA section taken
+    /// out; the rest of the format (`<lastmod>`, `<priority>`, ...) doesn't say where a link goes.
+    pub fn parse(body: &[u8]) -> Result<Self, String> {
+        let xml = if body.starts_with(&[0x1f, 0x8b]) {
+            let mut xml = String::new();
+            GzDecoder::new(body)
+                .read_to_string(&mut xml)
+                .map_err(|e| format!("can't gunzip sitemap: {}", e))?;
+            xml
+        } else {
+            String::from_utf8_lossy(body).into_owned()
+        };
+        if !xml.contains("<urlset") && !xml.contains("<sitemapindex") {
+            return Err("not a sitemap: no <urlset> or <sitemapindex>".to_string());
+        }
+        static LOC: OnceLock<Regex> = OnceLock::new();
+        let loc = LOC.get_or_init(|| Regex::new(r"(?s)<loc>(.*?)</loc>").unwrap());
+        let locs = loc
+            .captures_iter(&xml)
+            .map(|captures| unescape(captures[1].trim()))
+            .filter(|loc| !loc.is_empty())
+            .collect();
+        if xml.contains("<sitemapindex") {
+            Ok(Sitemap::Index(locs))
+        } else {
+            Ok(Sitemap::Urls(locs))
+        }
+    }
+}
+
+fn unescape(text: &str) -> String {
+    if let Some(cdata) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|text| text.strip_suffix("]]>"))
+    {
+        return cdata.trim().to_string();
+    }
+    // `&amp;` last, so `&amp;lt;` comes out as `&lt;`
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,61 @@
+use crate::links::LinkClass;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Why a link found on a page was deliberately not checked.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkipReason {
+    /// `mailto:`, `ftp:`, `tel:`, `data:` or `blob:`: not something an HTTP client can check
+    DeniedProtocol,
+    /// Placeholder hrefs like `#`, and `javascript:` ones like `javascript:void(0)`
+    DeniedLink,
+    /// The link points to another domain (and doesn't match the fuzzy match string)
+    OutOfDomain,
+    /// The link resolved to a URL without a domain name, e.g. an IP address or `data:` URL
+    NoDomain,
+    /// The link couldn't be parsed as a URL, like `http://exa mple.com/`
+    InvalidUrl,
+    /// The page is marked `nofollow` by `X-Robots-Tag` (with `--respect-robots-header`), or the
+    /// link is `<a rel="nofollow">` (with `--respect-nofollow`)
+    Nofollow,
+    /// The link matches an unsafe URL pattern (with `--safe-only`)
+    Unsafe,
+    /// The link matches an `--exclude` pattern, or none of the `--include` patterns
+    Excluded,
+    /// The link is on another site, and `--max-distinct-hosts` other sites were already
+    /// contacted (with `--check-external`)
+    TooManyHosts,
+    /// An HTMX `hx-post` endpoint, which could trigger an action (with `--htmx-links` and
+    /// `--safe-only`)
+    HtmxPost,
+    /// The URL with a different query string, when another query string on it was a duplicate
+    /// of the URL without one, by its `<link rel="canonical">` (with `--follow-canonical`)
+    CanonicalAlias,
+    /// The link is on the site, but its path is outside `--path-prefix` (without
+    /// `--check-out-of-scope`)
+    OutsidePathPrefix,
+}
+
+/// The kebab-case name, as in `--skipped-out`.
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(name)) => f.write_str(&name),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SkippedLink {
+    /// The absolute URL when the link could be resolved for the scope check, the href as
+    /// written otherwise
+    pub url: String,
+    /// Page (or PDF) the link was found on
+    pub referrer: String,
+    pub reason: SkipReason,
+    /// `None` if the link couldn't be resolved to a URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<LinkClass>,
+}
@@ -0,0 +1,134 @@
+use regex::{Regex, RegexBuilder};
+use select::document::Document;
+use select::node::Node;
+use select::predicate::Name;
+
+/// What `--soft-404` looks for when no patterns are given.
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    "not found",
+    r"\b404\b",
+    r"page (doesn't|doesn’t|does not) exist",
+];
+
+/// Spots "soft 404s": missing pages a CMS answers with a 200 and an error page, which a status
+/// check can't tell from real ones.
+///
+/// A page is a soft 404 when its `<title>` or visible text matches one of the patterns,
+/// case-insensitively. With [`Soft404Detector::probe`], the crawl first requests a URL that
+/// can't exist, to see what the site's error page looks like, and only pages that look like it
+/// count; see [`PageText::looks_like`].
+#[derive(Debug, Clone)]
+pub struct Soft404Detector {
+    patterns: Vec<Regex>,
+    probe: bool,
+}
+
+impl Soft404Detector {
+    /// A detector for `patterns`, regexes matched case-insensitively anywhere in the title or
+    /// text.
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<Self, regex::Error> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern.as_ref())
+                    .case_insensitive(true)
+                    .build()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Soft404Detector {
+            patterns,
+            probe: false,
+        })
+    }
+
+    /// Compare pages with the answer to a URL that can't exist before calling them soft 404s.
+    /// If that answer is an error status, the site reports missing pages properly and no page
+    /// is checked.
+    pub fn probe(mut self, probe: bool) -> Self {
+        self.probe = probe;
+        self
+    }
+
+    pub fn probes(&self) -> bool {
+        self.probe
+    }
+
+    /// The first pattern the page's title or text matches.
+    pub fn matching_pattern(&self, page: &PageText) -> Option<&str> {
+        self.patterns
+            .iter()
+            .find(|pattern| {
+                page.title
+                    .as_deref()
+                    .is_some_and(|title| pattern.is_match(title))
+                    || pattern.is_match(&page.text)
+            })
+            .map(Regex::as_str)
+    }
+}
+
+impl Default for Soft404Detector {
+    fn default() -> Self {
+        Soft404Detector::new(DEFAULT_PATTERNS).expect("the default patterns are valid")
+    }
+}
+
+/// The `<title>` and the visible text of an HTML page, whitespace collapsed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageText {
+    pub title: Option<String>,
+    /// Text in the `<body>`, leaving out scripts, styles and the like
+    pub text: String,
+}
+
+impl PageText {
+    pub fn from_html(html: &str) -> Self {
+        let document = Document::from(html);
+        let title = document
+            .find(Name("title"))
+            .next()
+            .map(|title| collapse_whitespace(&title.text()))
+            .filter(|title| !title.is_empty());
+        let mut text = String::new();
+        for body in document.find(Name("body")) {
+            visible_text(&body, &mut text);
+        }
+        PageText {
+            title,
+            text: collapse_whitespace(&text),
+        }
+    }
+
+    /// Whether this page, fetched from `path`, is the same error page as `error_page` fetched
+    /// from `error_path`: the same title and text, once the paths, which error pages like to
+    /// repeat back, are taken out.
+    pub fn looks_like(&self, path: &str, error_page: &PageText, error_path: &str) -> bool {
+        let without = |text: &str, path: &str| collapse_whitespace(&text.replace(path, " "));
+        let title = |page: &PageText, path: &str| page.title.as_deref().map(|t| without(t, path));
+        title(self, path) == title(error_page, error_path)
+            && without(&self.text, path) == without(&error_page.text, error_path)
+    }
+}
+
+fn visible_text(body: &Node, text: &mut String) {
+    // A stack rather than recursion, so a deeply nested page can't overflow ours
+    let mut nodes = vec![*body];
+    while let Some(node) = nodes.pop() {
+        if matches!(
+            node.name(),
+            Some("script" | "style" | "noscript" | "template")
+        ) {
+            continue;
+        }
+        if let Some(node_text) = node.as_text() {
+            text.push_str(node_text);
+            text.push(' ');
+        }
+        let children: Vec<_> = node.children().collect();
+        nodes.extend(children.into_iter().rev());
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
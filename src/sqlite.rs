@@ -0,0 +1,61 @@
+use crate::crawler::CheckedUrl;
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// Version of the table layout below, stored in `PRAGMA user_version`. Bump it whenever the
+/// schema changes so queries built on top of it can detect the difference.
+pub const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS checked_urls (
+        id           INTEGER PRIMARY KEY,
+        url          TEXT    NOT NULL,
+        status       INTEGER,          -- NULL when no response was received
+        elapsed_ms   INTEGER NOT NULL,
+        content_type TEXT,
+        referrer     TEXT,             -- NULL for the root URL
+        checked_at   TEXT    NOT NULL  -- RFC 3339, UTC
+    );
+    CREATE INDEX IF NOT EXISTS checked_urls_url ON checked_urls (url);
+    CREATE INDEX IF NOT EXISTS checked_urls_status ON checked_urls (status);
+    CREATE INDEX IF NOT EXISTS checked_urls_elapsed_ms ON checked_urls (elapsed_ms);
+";
+
+/// Writes every checked URL into a SQLite database for ad-hoc querying, e.g.
+/// `SELECT url, elapsed_ms FROM checked_urls ORDER BY elapsed_ms DESC`.
+///
+/// Rows from repeated runs accumulate in the same database; use `checked_at` to tell them apart.
+/// Everything is written in one transaction that is committed by [`SqliteWriter::finish`].
+pub struct SqliteWriter {
+    connection: Connection,
+}
+
+impl SqliteWriter {
+    pub fn create(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        connection.execute_batch("BEGIN")?;
+        Ok(SqliteWriter { connection })
+    }
+
+    pub fn record(&self, checked: &CheckedUrl) -> rusqlite::Result<()> {
+        let mut statement = self.connection.prepare_cached(
+            "INSERT INTO checked_urls (url, status, elapsed_ms, content_type, referrer, checked_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        statement.execute(params![
+            checked.url,
+            checked.status,
+            checked.elapsed.as_millis() as i64,
+            checked.content_type,
+            checked.referrer,
+            humantime::format_rfc3339_millis(checked.checked_at).to_string(),
+        ])?;
+        Ok(())
+    }
+
+    pub fn finish(self) -> rusqlite::Result<()> {
+        self.connection.execute_batch("COMMIT")
+    }
+}
@@ -0,0 +1,56 @@
+use find_broken_links::warnings::LinkWarning;
+use std::str::FromStr;
+use syslog::{Facility, Formatter3164, Logger, LoggerBackend};
+
+/// Mirrors broken links, warnings and the final summary to the local syslog daemon.
+///
+/// This is in addition to the results files, never instead of them. Write failures are logged
+/// and otherwise ignored so a flaky syslog socket can't take down a crawl.
+pub struct SyslogSink {
+    logger: Logger<LoggerBackend, Formatter3164>,
+}
+
+impl SyslogSink {
+    /// Connects to the syslog unix socket. Fails on platforms without one (e.g. Windows).
+    pub fn connect(facility: &str, tag: &str) -> Result<Self, String> {
+        let facility = Facility::from_str(facility)
+            .map_err(|_| format!("unknown syslog facility '{}'", facility))?;
+        let formatter = Formatter3164 {
+            facility,
+            hostname: None,
+            process: tag.to_string(),
+            pid: std::process::id(),
+        };
+        let logger = syslog::unix(formatter).map_err(|e| e.to_string())?;
+        Ok(SyslogSink { logger })
+    }
+
+    pub fn broken_link(&mut self, url: &str, suggestion: Option<&str>) {
+        let message = match suggestion {
+            Some(suggestion) => format!("broken link: {} ({})", url, suggestion),
+            None => format!("broken link: {}", url),
+        };
+        if let Err(e) = self.logger.err(message) {
+            log::warn!("Failed to write to syslog: {}", e);
+        }
+    }
+
+    pub fn warning(&mut self, warning: &LinkWarning) {
+        if let Err(e) = self
+            .logger
+            .warning(format!("warning: {}: {}", warning.url, warning.message))
+        {
+            log::warn!("Failed to write to syslog: {}", e);
+        }
+    }
+
+    pub fn summary(&mut self, root_url: &str, broken: usize, warnings: usize) {
+        let message = format!(
+            "crawl of {} finished: {} broken links, {} warnings",
+            root_url, broken, warnings
+        );
+        if let Err(e) = self.logger.notice(message) {
+            log::warn!("Failed to write to syslog: {}", e);
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use regex::Regex;
+
+/// `--include` and `--exclude` patterns, for keeping a crawl out of parts of a site that
+/// generate endless URLs, like search results or a calendar.
+///
+/// Patterns are regexes matched against the whole absolute URL, query string included, and
+/// match anywhere in it unless anchored with `^` or `$`.
+#[derive(Debug, Clone, Default)]
+pub struct UrlPatternFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl UrlPatternFilter {
+    /// With no `include` patterns every URL is included.
+    pub fn new(include: Vec<Regex>, exclude: Vec<Regex>) -> Self {
+        UrlPatternFilter { include, exclude }
+    }
+
+    /// Whether `url` is checked: it matches no exclude pattern, and an include pattern if there
+    /// are any. Exclude patterns win when both match.
+    pub fn allows(&self, url: &str) -> bool {
+        if self.exclude.iter().any(|pattern| pattern.is_match(url)) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|pattern| pattern.is_match(url))
+    }
+}
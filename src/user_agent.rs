@@ -0,0 +1,68 @@
+use rand::Rng;
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use url::Url;
+
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/58.0.3029.110 Safari/537.3";
+
+/// Chooses the `User-Agent` header for each request.
+///
+/// With zero or one configured agent there is nothing to rotate. With several, a random one is
+/// picked for every request, unless `per_host` is set, in which case the first pick for a host is
+/// remembered and reused for every later request to that host (closer to a real browser session).
+pub struct UserAgentPicker {
+    agents: Vec<String>,
+    per_host: Option<Mutex<HashMap<String, usize>>>, // host -> index into `agents`
+}
+
+impl UserAgentPicker {
+    pub fn new(agents: Vec<String>, per_host: bool) -> Self {
+        UserAgentPicker {
+            agents,
+            per_host: per_host.then(|| Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn pick(&self, url: &Url) -> &str {
+        match self.agents.len() {
+            0 => DEFAULT_USER_AGENT,
+            1 => &self.agents[0],
+            len => {
+                let index = match (&self.per_host, url.host_str()) {
+                    (Some(per_host), Some(host)) => *per_host
+                        .lock()
+                        .unwrap()
+                        .entry(host.to_string())
+                        .or_insert_with(|| rand::thread_rng().gen_range(0..len)),
+                    _ => rand::thread_rng().gen_range(0..len),
+                };
+                &self.agents[index]
+            }
+        }
+    }
+}
+
+/// Headers a current desktop browser sends on a top-level navigation.
+///
+/// Web application firewalls often reject requests that only look like a browser by their
+/// `User-Agent`; retrying with this fuller set tells such blocks apart from real 403s.
+pub fn browser_headers() -> HeaderMap {
+    let headers = [
+        (header::USER_AGENT, DEFAULT_USER_AGENT),
+        (
+            header::ACCEPT,
+            "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8",
+        ),
+        (header::ACCEPT_LANGUAGE, "en-US,en;q=0.9"),
+        (header::UPGRADE_INSECURE_REQUESTS, "1"),
+        (HeaderName::from_static("sec-fetch-dest"), "document"),
+        (HeaderName::from_static("sec-fetch-mode"), "navigate"),
+        (HeaderName::from_static("sec-fetch-site"), "none"),
+        (HeaderName::from_static("sec-fetch-user"), "?1"),
+    ];
+    headers
+        .into_iter()
+        .map(|(name, value)| (name, HeaderValue::from_static(value)))
+        .collect()
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use find_broken_links::accept::{accept_for_url, AcceptHeader, HTML_ACCEPT};
+use find_broken_links::crawler::Crawler;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn picks_accept_from_the_file_extension() {
+    let cases = [
+        ("https://example.com/", HTML_ACCEPT),
+        ("https://example.com/about", HTML_ACCEPT),
+        ("https://example.com/page.html", HTML_ACCEPT),
+        ("https://example.com/img/Logo.PNG", "image/*,*/*;q=0.8"),
+        (
+            "https://example.com/data.json?v=2",
+            "application/json,*/*;q=0.1",
+        ),
+        ("https://example.com/v1.2/users", HTML_ACCEPT),
+        (
+            "https://example.com/feed.rss",
+            "application/xml,text/xml,*/*;q=0.1",
+        ),
+    ];
+    for (url, expected) in cases {
+        assert_eq!(
+            accept_for_url(&Url::parse(url).unwrap()),
+            expected,
+            "{}",
+            url
+        );
+    }
+}
+
+#[test]
+fn parses_fixed_values_and_auto() {
+    assert_eq!("auto".parse::<AcceptHeader>(), Ok(AcceptHeader::Auto));
+    assert_eq!(
+        "application/json".parse::<AcceptHeader>(),
+        Ok(AcceptHeader::Fixed("application/json".parse().unwrap()))
+    );
+    assert!("text/html\n".parse::<AcceptHeader>().is_err());
+}
+
+#[tokio::test]
+async fn sends_the_accept_header() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/logo.png\">logo</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/logo.png"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .accept(Some(AcceptHeader::Auto))
+        .build();
+    common::crawl(crawler).await;
+
+    let sent: Vec<(String, String)> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            let accept = request.headers.get("accept").unwrap().to_str().unwrap();
+            (request.url.path().to_string(), accept.to_string())
+        })
+        .collect();
+    assert_eq!(
+        sent,
+        [
+            ("/".to_string(), HTML_ACCEPT.to_string()),
+            ("/logo.png".to_string(), "image/*,*/*;q=0.8".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use find_broken_links::access_log::AccessLogParser;
+use find_broken_links::crawler::Crawler;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const LOG: &str = r#"203.0.113.7 - - [14/Oct/2026:09:30:00 +0000] "GET /docs/intro?lang=en HTTP/1.1" 200 5120 "-" "Mozilla/5.0"
+203.0.113.7 - - [14/Oct/2026:09:30:01 +0000] "GET /pricing HTTP/2.0" 200 812
+198.51.100.2 - - [14/Oct/2026:09:30:02 +0000] "POST /api/login HTTP/1.1" 302 0 "-" "curl/8.0"
+198.51.100.2 - - [14/Oct/2026:09:30:03 +0000] "HEAD /docs/intro?lang=en HTTP/1.1" 200 0
+198.51.100.3 - - [14/Oct/2026:09:30:04 +0000] "GET https://other.example/page HTTP/1.1" 200 10
+not a log line
+198.51.100.3 - - [14/Oct/2026:09:30:05 +0000] "GET https://example.com/old-post#comments HTTP/1.1" 404 0
+"#;
+
+#[test]
+fn extracts_distinct_same_host_paths() {
+    let root = Url::parse("https://example.com/").unwrap();
+    let urls = AccessLogParser::default()
+        .seed_urls(LOG.as_bytes(), &root)
+        .unwrap();
+    assert_eq!(
+        urls.iter().map(Url::as_str).collect::<Vec<_>>(),
+        [
+            "https://example.com/docs/intro?lang=en",
+            "https://example.com/pricing",
+            "https://example.com/old-post",
+        ]
+    );
+}
+
+#[test]
+fn custom_patterns_need_a_path_group() {
+    let root = Url::parse("https://example.com/").unwrap();
+    let parser = AccessLogParser::new(r"^\S+ (?P<path>/\S*)").unwrap();
+    let urls = parser
+        .seed_urls("GET /a 200\nGET /b 404\n".as_bytes(), &root)
+        .unwrap();
+    assert_eq!(
+        urls.iter().map(Url::as_str).collect::<Vec<_>>(),
+        ["https://example.com/a", "https://example.com/b"]
+    );
+
+    assert!(AccessLogParser::new(r#""GET (\S+)""#).is_err());
+    assert!(AccessLogParser::new("(unclosed").is_err());
+}
+
+#[tokio::test]
+async fn crawls_seeded_paths_that_nothing_links_to() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("<p>home</p>", "text/html"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/unlinked"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/gone\">gone</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+    let log = "1.2.3.4 - - [14/Oct/2026:09:30:00 +0000] \"GET /unlinked HTTP/1.1\" 200 10\n";
+    let seeds = AccessLogParser::default()
+        .seed_urls(log.as_bytes(), &root)
+        .unwrap();
+
+    let crawler = Crawler::builder(root.clone()).seed_urls(seeds).build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/gone").unwrap().to_string()]
+    );
+}
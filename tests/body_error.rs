@@ -0,0 +1,81 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use serde_json::json;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// `/` links to JSON endpoints that all answer 200, some with an error in the body, and to an
+/// HTML page whose text looks like one.
+async fn mount_api(server: &MockServer) {
+    let page = r#"<a href="/api/missing">a</a> <a href="/api/found">b</a>
+        <a href="/api/code">c</a> <a href="/error.html">d</a>"#;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+        .mount(server)
+        .await;
+    let responses = [
+        ("/api/missing", json!({ "error": "not found" })),
+        ("/api/found", json!({ "error": null, "item": { "id": 1 } })),
+        ("/api/code", json!({ "error": { "code": 404 } })),
+    ];
+    for (route, body) in responses {
+        Mock::given(path(route))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+    Mock::given(path("/error.html"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(r#"{"error": "not found"}"#, "text/html"),
+        )
+        .mount(server)
+        .await;
+}
+
+fn errors(findings: &[Finding]) -> Vec<(String, Option<String>)> {
+    let mut errors: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, error, .. } => Some((url.clone(), error.clone())),
+            _ => None,
+        })
+        .collect();
+    errors.sort();
+    errors
+}
+
+#[tokio::test]
+async fn json_responses_with_an_error_value_are_broken() {
+    let server = MockServer::start().await;
+    mount_api(&server).await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone())
+        .body_error_paths(Some(vec!["$.error".parse().unwrap()]))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        errors(&findings),
+        [
+            (
+                root.join("/api/code").unwrap().to_string(),
+                Some(r#"{"code":404}"#.to_string())
+            ),
+            (
+                root.join("/api/missing").unwrap().to_string(),
+                Some("not found".to_string())
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn bodies_are_not_checked_by_default() {
+    let server = MockServer::start().await;
+    mount_api(&server).await;
+
+    let findings = common::crawl(Crawler::builder(common::root_url(&server)).build()).await;
+    assert!(errors(&findings).is_empty());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::warnings::{LinkWarning, WarningCategory};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn oversized_warnings(findings: &[Finding]) -> Vec<&LinkWarning> {
+    findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Warning(warning) if warning.category == WarningCategory::Oversized => {
+                Some(warning)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A root page linking to `/huge`, a page well over 1 KiB that links to `/missing`.
+async fn mount_pages(server: &MockServer) {
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/huge\">huge</a>", "text/html"),
+        )
+        .mount(server)
+        .await;
+    let huge = format!("<a href=\"/missing\">missing</a>{}", "x".repeat(4096));
+    Mock::given(path("/huge"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(huge, "text/html"))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn skips_pages_over_the_limit() {
+    let server = MockServer::start().await;
+    mount_pages(&server).await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone())
+        .max_body_bytes(Some(1024))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let warnings = oversized_warnings(&findings);
+    assert_eq!(warnings.len(), 1, "only /huge is over the limit");
+    assert_eq!(warnings[0].url, root.join("/huge").unwrap().to_string());
+    assert!(
+        warnings[0].message.contains("over the limit of 1024 bytes"),
+        "{}",
+        warnings[0].message
+    );
+    assert!(
+        common::broken_urls(&findings).is_empty(),
+        "links on an oversized page aren't followed, and it isn't broken itself"
+    );
+}
+
+#[tokio::test]
+async fn follows_links_on_pages_under_the_limit() {
+    let server = MockServer::start().await;
+    mount_pages(&server).await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone()).max_body_bytes(None).build();
+    let findings = common::crawl(crawler).await;
+
+    assert!(oversized_warnings(&findings).is_empty());
+    assert_eq!(
+        common::broken_urls(&findings),
+        vec![root.join("/missing").unwrap().to_string()]
+    );
+}
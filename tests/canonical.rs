@@ -0,0 +1,96 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::skipped::SkipReason;
+use wiremock::MockServer;
+
+/// A site whose `/post` says it's the canonical URL of every query string on it, linked from
+/// the root page only with tracking parameters.
+async fn mount_site() -> MockServer {
+    common::site(vec![
+        (
+            "/",
+            common::page(
+                r#"<a href="/post?utm_source=home">post</a>
+                   <a href="/post?utm_source=footer">post</a>"#,
+            ),
+        ),
+        (
+            "/post",
+            common::page(
+                r#"<link rel="canonical" href="/post">
+                   <a href="/missing">missing</a>"#,
+            ),
+        ),
+    ])
+    .await
+}
+
+/// Query strings of the requests made for `path`.
+async fn requests_for(server: &MockServer, path: &str) -> Vec<Option<String>> {
+    server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|request| request.url.path() == path)
+        .map(|request| request.url.query().map(str::to_string))
+        .collect()
+}
+
+#[tokio::test]
+async fn crawls_the_canonical_url_instead_of_duplicates() {
+    let server = mount_site().await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone())
+        .follow_canonical(true)
+        .report_skipped(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let post = root.join("/post").unwrap().to_string();
+    let fetched = requests_for(&server, "/post").await;
+    assert_eq!(fetched.len(), 2, "one duplicate, then /post: {:?}", fetched);
+    assert!(fetched.contains(&None));
+
+    // The broken link is only found on the canonical page
+    let broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, referrer, .. } => Some((url.clone(), referrer.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        broken,
+        [(root.join("/missing").unwrap().to_string(), Some(post))]
+    );
+
+    let aliases: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Skipped(skipped) if skipped.reason == SkipReason::CanonicalAlias => {
+                Some(skipped.url.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(aliases.len(), 1, "{:?}", aliases);
+    assert!(aliases[0].contains("/post?utm_source="), "{}", aliases[0]);
+}
+
+#[tokio::test]
+async fn crawls_every_url_by_default() {
+    let server = mount_site().await;
+    let root = common::root_url(&server);
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+
+    // Both duplicates, and /post as a `<link>` the pages load
+    assert_eq!(requests_for(&server, "/post").await.len(), 3);
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/missing").unwrap().to_string()]
+    );
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::retry::RetryPolicy;
+use std::time::Duration;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn reports_the_status_without_following_links() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/missing\">x</a>", "text/html"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/missing"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let root = common::root_url(&server);
+    let crawler = Crawler::builder(root.clone()).build();
+    let checked = crawler.check(&root).await.unwrap();
+    assert_eq!(checked.status, Some(200));
+    assert_eq!(checked.content_type.as_deref(), Some("text/html"));
+
+    let missing = root.join("/missing").unwrap();
+    assert_eq!(crawler.check(&missing).await.unwrap().status, Some(404));
+}
+
+#[tokio::test]
+async fn honors_retries_and_timeout() {
+    let server = MockServer::start().await;
+    Mock::given(path("/busy"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(path("/slow"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .mount(&server)
+        .await;
+
+    let root = common::root_url(&server);
+    let crawler = Crawler::builder(root.clone())
+        .retry_policy("503:1".parse::<RetryPolicy>().unwrap())
+        .request_timeout(Some(Duration::from_millis(200)))
+        .build();
+
+    let busy = root.join("/busy").unwrap();
+    assert_eq!(crawler.check(&busy).await.unwrap().status, Some(503));
+    let slow = root.join("/slow").unwrap();
+    assert!(crawler.check(&slow).await.unwrap_err().is_timeout());
+}
+
+#[tokio::test]
+async fn no_response_is_an_error() {
+    let url = Url::parse("http://localhost:1/").unwrap();
+    let crawler = Crawler::builder(url.clone()).build();
+    assert!(crawler.check(&url).await.is_err());
+}
+
+#[tokio::test]
+async fn retries_a_timeout_like_the_crawl_does() {
+    let server = MockServer::start().await;
+    Mock::given(path("/flaky"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/flaky"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let root = common::root_url(&server);
+    let policy = ""
+        .parse::<RetryPolicy>()
+        .unwrap()
+        .max_retries(1)
+        .base_delay(Duration::from_millis(10));
+    let crawler = Crawler::builder(root.clone())
+        .retry_policy(policy)
+        .request_timeout(Some(Duration::from_millis(200)))
+        .build();
+
+    let flaky = root.join("/flaky").unwrap();
+    assert_eq!(crawler.check(&flaky).await.unwrap().status, Some(200));
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::links::{Destination, LinkClass};
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn class_of(url: &str, root: &str) -> LinkClass {
+    LinkClass::of(&Url::parse(url).unwrap(), &Url::parse(root).unwrap())
+}
+
+#[test]
+fn classifies_by_host_relative_to_the_root() {
+    let root = "https://example.com/docs/";
+    let destination = |url| class_of(url, root).destination;
+
+    assert_eq!(
+        destination("http://example.com:8080/a"),
+        Destination::Internal
+    );
+    assert_eq!(
+        destination("https://api.example.com/"),
+        Destination::Subdomain
+    );
+    assert_eq!(
+        destination("https://notexample.com/"),
+        Destination::External
+    );
+    assert_eq!(
+        destination("https://example.com.evil/"),
+        Destination::External
+    );
+    assert_eq!(destination("mailto:me@example.com"), Destination::Contact);
+    assert_eq!(destination("tel:+15555550100"), Destination::Contact);
+}
+
+#[test]
+fn the_bare_domain_is_a_subdomain_of_a_www_root() {
+    let root = "https://www.example.com/";
+    assert_eq!(
+        class_of("https://example.com/", root).destination,
+        Destination::Subdomain
+    );
+    assert_eq!(
+        class_of("https://cdn.example.com/app.js", root).destination,
+        Destination::Subdomain
+    );
+}
+
+#[test]
+fn flags_images_stylesheets_scripts_and_fonts_as_assets() {
+    let root = "https://example.com/";
+    for asset in [
+        "https://example.com/logo.PNG?v=2",
+        "https://example.com/site.css",
+        "https://cdn.example.net/app.mjs",
+        "https://fonts.example.net/inter.woff2",
+    ] {
+        assert!(class_of(asset, root).asset, "{}", asset);
+    }
+    for page in [
+        "https://example.com/",
+        "https://example.com/about",
+        "https://example.com/report.pdf",
+    ] {
+        assert!(!class_of(page, root).asset, "{}", page);
+    }
+}
+
+#[test]
+fn only_writes_asset_when_set() {
+    let json = serde_json::to_value([
+        class_of("https://example.com/a.png", "https://example.com/"),
+        class_of("https://other.example/", "https://example.com/"),
+    ])
+    .unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([
+            {"destination": "internal", "asset": true},
+            {"destination": "external"},
+        ])
+    );
+}
+
+#[tokio::test]
+async fn broken_links_carry_their_class() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<a href=\"/missing.png\">logo</a><a href=\"/gone\">gone</a>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+
+    let findings = common::crawl(Crawler::builder(common::root_url(&server)).build()).await;
+    let mut classes: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, class, .. } => Some((url.rsplit('/').next()?, *class)),
+            _ => None,
+        })
+        .collect();
+    classes.sort_by_key(|(file, _)| *file);
+    let internal = |asset| {
+        Some(LinkClass {
+            destination: Destination::Internal,
+            asset,
+        })
+    };
+    assert_eq!(
+        classes,
+        [("gone", internal(false)), ("missing.png", internal(true))]
+    );
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use std::time::Duration;
+use tokio::process::Command;
+use wiremock::ResponseTemplate;
+
+/// Runs the binary with `args` and returns its exit code, failing if it doesn't exit.
+async fn exit_code(name: &str, args: &[&str]) -> Option<i32> {
+    let output_dir = std::env::temp_dir().join(format!("fbl-cli-{}-{}", name, std::process::id()));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_find-broken-links"))
+        .args(args)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let status = tokio::time::timeout(Duration::from_secs(30), child.wait())
+        .await
+        .expect("the binary didn't exit")
+        .unwrap();
+    status.code()
+}
+
+#[tokio::test]
+async fn exits_with_an_error_when_the_crawl_fails() {
+    let site = common::site(vec![
+        ("/", common::page(r#"<a href="/down">down</a>"#)),
+        ("/down", ResponseTemplate::new(500)),
+    ])
+    .await;
+    let root = common::root_url(&site).to_string();
+
+    // With --only-404, a 500 on the site itself ends the crawl with an error
+    assert_eq!(exit_code("only-404", &[&root, "--only-404"]).await, Some(1));
+}
+
+#[tokio::test]
+async fn exits_with_an_error_when_the_crawl_cannot_start() {
+    assert_eq!(
+        exit_code("no-domain", &["http://127.0.0.1:1/"]).await,
+        Some(1)
+    );
+}
+
+#[tokio::test]
+async fn fail_on_broken_tells_broken_links_from_a_failed_crawl() {
+    let site = common::site(vec![
+        (
+            "/",
+            common::page(r#"<a href="/gone">gone</a><a href="/down">down</a>"#),
+        ),
+        ("/down", ResponseTemplate::new(500)),
+    ])
+    .await;
+    let root = common::root_url(&site).to_string();
+
+    // `/gone` isn't served, so it's a 404; without --only-404, the 500 is broken too
+    assert_eq!(
+        exit_code("broken", &[&root, "--fail-on-broken"]).await,
+        Some(5)
+    );
+    assert_eq!(
+        exit_code("failed", &[&root, "--fail-on-broken", "--only-404"]).await,
+        Some(1)
+    );
+}
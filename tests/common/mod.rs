@@ -0,0 +1,68 @@
+#![allow(dead_code)] // Not every test binary uses every helper
+
+use find_broken_links::crawler::{CrawlMessage, Crawler, Finding};
+use find_broken_links::debug_channel::DebugChannel;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// The crawler scopes by domain name, so address the mock server as `localhost` rather than by IP.
+pub fn root_url(server: &MockServer) -> Url {
+    Url::parse(&server.uri().replace("127.0.0.1", "localhost")).unwrap()
+}
+
+/// Runs a crawl to completion and returns everything it reported.
+pub async fn crawl(crawler: Crawler) -> Vec<Finding> {
+    let mut channel = DebugChannel::<CrawlMessage>::new(5);
+    let sender = channel.sender();
+    let task = tokio::spawn(async move { crawler.run(sender).await });
+
+    let mut findings = Vec::new();
+    while let Some(CrawlMessage::Finding(finding)) = channel.recv().await {
+        findings.push(finding);
+    }
+    task.await.unwrap().unwrap();
+    findings
+}
+
+/// The broken URLs among `findings`, in the order they were reported.
+pub fn broken_urls(findings: &[Finding]) -> Vec<String> {
+    findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, .. } => Some(url.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Every request `server` received, as `METHOD /path`, sorted: requests run concurrently, so
+/// they don't arrive in a set order.
+pub async fn requested_paths(server: &MockServer) -> Vec<String> {
+    let mut paths: Vec<_> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| format!("{} {}", request.method, request.url.path()))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// An HTML page with `body` for [`site`].
+pub fn page(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.to_string(), "text/html")
+}
+
+/// A mock site serving each of `routes`, path and response. Any other path is a 404.
+pub async fn site(routes: Vec<(&str, ResponseTemplate)>) -> MockServer {
+    let server = MockServer::start().await;
+    for (route, response) in routes {
+        Mock::given(path(route))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+    }
+    server
+}
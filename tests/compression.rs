@@ -0,0 +1,86 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use std::io::Write;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const PAGE: &str = r#"<html><body><a href="/gone">gone</a></body></html>"#;
+
+/// Serves `body` as the root page with `encoding` as its `Content-Encoding`, and crawls it.
+async fn broken_links_on_encoded_page(encoding: &str, body: Vec<u8>) -> (MockServer, Vec<String>) {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-encoding", encoding)
+                .set_body_raw(body, "text/html"),
+        )
+        .mount(&server)
+        .await;
+    let crawler = Crawler::builder(common::root_url(&server)).build();
+    let findings = common::crawl(crawler).await;
+    (server, common::broken_urls(&findings))
+}
+
+#[tokio::test]
+async fn follows_links_on_gzipped_pages() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(PAGE.as_bytes()).unwrap();
+    let (server, broken) = broken_links_on_encoded_page("gzip", encoder.finish().unwrap()).await;
+    assert_eq!(broken.len(), 1);
+    assert!(broken[0].ends_with("/gone"));
+
+    let requests = server.received_requests().await.unwrap();
+    let accept_encoding = requests[0].headers.get("accept-encoding").unwrap();
+    let accept_encoding = accept_encoding.to_str().unwrap();
+    for encoding in ["gzip", "br", "deflate"] {
+        assert!(accept_encoding.contains(encoding), "{}", accept_encoding);
+    }
+}
+
+#[tokio::test]
+async fn follows_links_on_deflated_pages() {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(PAGE.as_bytes()).unwrap();
+    let (_server, broken) =
+        broken_links_on_encoded_page("deflate", encoder.finish().unwrap()).await;
+    assert_eq!(broken.len(), 1);
+    assert!(broken[0].ends_with("/gone"));
+}
+
+#[tokio::test]
+async fn reports_a_corrupt_gzipped_page_and_keeps_crawling() {
+    let server = common::site(vec![
+        (
+            "/",
+            common::page(r#"<a href="/corrupt">corrupt</a><a href="/gone">gone</a>"#),
+        ),
+        (
+            "/corrupt",
+            ResponseTemplate::new(200)
+                .insert_header("content-encoding", "gzip")
+                .set_body_raw(b"definitely not gzip".to_vec(), "text/html"),
+        ),
+    ])
+    .await;
+    let root = common::root_url(&server);
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+
+    let mut broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, error, .. } => Some((url.clone(), error.clone())),
+            _ => None,
+        })
+        .collect();
+    broken.sort();
+    assert_eq!(broken.len(), 2, "{:?}", broken);
+    assert_eq!(broken[0].0, root.join("/corrupt").unwrap().to_string());
+    let error = broken[0].1.as_deref().unwrap_or_default();
+    assert!(error.starts_with("unreadable body: "), "{}", error);
+    assert_eq!(broken[1], (root.join("/gone").unwrap().to_string(), None));
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use find_broken_links::concurrency::{AimdController, ErrorBackoff};
+use find_broken_links::crawler::Crawler;
+use std::time::Duration;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const FAST: Duration = Duration::from_millis(20);
+
+#[test]
+fn ramps_up_on_healthy_responses_up_to_max() {
+    let mut controller = AimdController::new(1, 4);
+    assert_eq!(controller.limit(), 1);
+    // +1 after `limit` healthy responses in a row: 1 -> 2 after one, 2 -> 3 after two more, ...
+    assert_eq!(controller.record(FAST, Some(200)), 2);
+    assert_eq!(controller.record(FAST, Some(200)), 2);
+    assert_eq!(controller.record(FAST, Some(404)), 3);
+    for _ in 0..20 {
+        controller.record(FAST, Some(200));
+    }
+    assert_eq!(controller.limit(), 4);
+}
+
+#[test]
+fn halves_on_rate_limiting_and_server_errors() {
+    for status in [Some(429), Some(503), Some(500), None] {
+        let mut controller = AimdController::new(1, 16);
+        for _ in 0..50 {
+            controller.record(FAST, Some(200));
+        }
+        let before = controller.limit();
+        assert_eq!(controller.record(FAST, status), before / 2, "{:?}", status);
+    }
+}
+
+#[test]
+fn backs_off_once_per_burst() {
+    let mut controller = AimdController::new(1, 16);
+    for _ in 0..50 {
+        controller.record(FAST, Some(200));
+    }
+    let halved = controller.record(FAST, Some(429));
+    // The rest of the burst was sent under the old limit and doesn't halve again
+    for _ in 0..halved {
+        assert_eq!(controller.record(FAST, Some(429)), halved);
+    }
+    assert_eq!(controller.record(FAST, Some(429)), halved / 2);
+}
+
+#[test]
+fn backs_off_when_latency_rises() {
+    let mut controller = AimdController::new(1, 16);
+    for _ in 0..50 {
+        controller.record(FAST, Some(200));
+    }
+    let before = controller.limit();
+    let mut limit = before;
+    for _ in 0..5 {
+        limit = controller.record(Duration::from_millis(500), Some(200));
+    }
+    assert!(limit < before, "{} -> {}", before, limit);
+}
+
+#[test]
+fn never_drops_below_min() {
+    let mut controller = AimdController::new(2, 8);
+    for _ in 0..20 {
+        controller.record(FAST, Some(503));
+    }
+    assert_eq!(controller.limit(), 2);
+}
+
+#[test]
+fn error_backoff_halves_on_error_spikes_and_restores() {
+    let mut backoff = ErrorBackoff::new(8);
+    assert_eq!(backoff.limit(), 8);
+    // 5 errors out of 20 is over the 20% threshold, decided at the end of the window
+    for i in 0..19 {
+        let status = if i < 5 { Some(503) } else { Some(200) };
+        assert_eq!(backoff.record(status), 8);
+    }
+    assert_eq!(backoff.record(Some(200)), 4);
+    for _ in 0..20 {
+        backoff.record(None);
+    }
+    assert_eq!(backoff.limit(), 2);
+
+    // Errors subsiding: at most 10% failing doubles back up, but not past the configured limit
+    for _ in 0..3 {
+        for i in 0..20 {
+            backoff.record(if i < 2 { Some(500) } else { Some(404) });
+        }
+    }
+    assert_eq!(backoff.limit(), 8);
+}
+
+#[test]
+fn error_backoff_holds_between_thresholds_and_never_drops_below_one() {
+    let mut backoff = ErrorBackoff::new(4);
+    for _ in 0..5 {
+        for i in 0..20 {
+            backoff.record(if i < 3 { Some(429) } else { Some(200) });
+        }
+    }
+    assert_eq!(backoff.limit(), 4);
+
+    for _ in 0..100 {
+        backoff.record(Some(502));
+    }
+    assert_eq!(backoff.limit(), 1);
+}
+
+#[tokio::test]
+async fn auto_concurrency_crawl_fetches_every_page_once() {
+    let server = MockServer::start().await;
+    let links: String = (0..20)
+        .map(|i| format!("<a href=\"/page/{}\">{}</a><a href=\"/\">home</a>", i, i))
+        .collect();
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    for i in 0..20 {
+        let status = if i % 5 == 0 { 404 } else { 200 };
+        Mock::given(path(format!("/page/{}", i)))
+            .respond_with(
+                ResponseTemplate::new(status)
+                    .set_body_raw("<a href=\"/page/0\">first</a>", "text/html")
+                    .set_delay(Duration::from_millis(10)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .auto_concurrency(Some(8))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let mut broken = common::broken_urls(&findings);
+    broken.sort();
+    let root = common::root_url(&server);
+    let expected: Vec<String> = [0, 10, 15, 5]
+        .iter()
+        .map(|i| root.join(&format!("/page/{}", i)).unwrap().to_string())
+        .collect();
+    assert_eq!(broken, expected);
+}
+
+#[tokio::test]
+async fn fixed_concurrency_with_backoff_fetches_every_page_once() {
+    let server = MockServer::start().await;
+    let links: String = (0..10)
+        .map(|i| format!("<a href=\"/page/{}\">{}</a>", i, i))
+        .collect();
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    for i in 0..10 {
+        let status = if i % 3 == 0 { 404 } else { 200 };
+        Mock::given(path(format!("/page/{}", i)))
+            .respond_with(
+                ResponseTemplate::new(status)
+                    .set_body_raw("<a href=\"/\">home</a>", "text/html")
+                    .set_delay(Duration::from_millis(10)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .concurrency(4)
+        .backoff_on_errors(true)
+        .build();
+    let concurrency = crawler.effective_concurrency();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(common::broken_urls(&findings).len(), 4);
+    assert_eq!(concurrency.load(std::sync::atomic::Ordering::Relaxed), 4);
+}
@@ -0,0 +1,48 @@
+use find_broken_links::config::CrawlConfig;
+use std::collections::BTreeMap;
+
+#[test]
+fn reads_every_setting_and_leaves_out_the_rest() {
+    let config = CrawlConfig::from_json(
+        r#"{
+            "root_url": "https://example.com/",
+            "seeds": ["https://example.com/orphan"],
+            "concurrency": 4,
+            "timeout_secs": 10,
+            "max_pages": 500,
+            "channel_buffer": 16,
+            "exclude": ["/search\\?"],
+            "headers": { "X-Staging-Token": "abc123" }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config,
+        CrawlConfig {
+            root_url: Some("https://example.com/".to_string()),
+            seeds: vec!["https://example.com/orphan".to_string()],
+            concurrency: Some(4),
+            timeout_secs: Some(10),
+            max_pages: Some(500),
+            channel_buffer: Some(16),
+            exclude: vec![r"/search\?".to_string()],
+            headers: BTreeMap::from([("X-Staging-Token".to_string(), "abc123".to_string())]),
+            ..CrawlConfig::default()
+        }
+    );
+    assert_eq!(
+        CrawlConfig::from_json("{}").unwrap(),
+        CrawlConfig::default()
+    );
+}
+
+#[test]
+fn rejects_unknown_keys() {
+    let error = CrawlConfig::from_json(r#"{ "concurency": 4 }"#).unwrap_err();
+    assert!(
+        error.to_string().contains("unknown field `concurency`"),
+        "{}",
+        error
+    );
+}
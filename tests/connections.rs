@@ -0,0 +1,75 @@
+mod common;
+
+use find_broken_links::connections::{
+    concurrency_warning, default_max_connections, open_file_limit, RESERVED_FDS,
+};
+use find_broken_links::crawler::Crawler;
+use std::time::{Duration, Instant};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn default_cap_leaves_room_under_the_open_file_limit() {
+    assert_eq!(default_max_connections(Some(1024)), Some(960));
+    assert_eq!(default_max_connections(Some(RESERVED_FDS)), Some(1));
+    assert_eq!(default_max_connections(None), None);
+    if cfg!(unix) {
+        assert!(open_file_limit().is_none_or(|limit| limit > 0));
+    }
+}
+
+#[test]
+fn warns_when_concurrency_or_the_cap_do_not_fit() {
+    // Fits
+    assert_eq!(concurrency_warning(16, Some(960), Some(1024)), None);
+    assert_eq!(concurrency_warning(2000, None, None), None);
+
+    let over_cap = concurrency_warning(2000, Some(960), Some(1024)).unwrap();
+    assert!(over_cap.contains("960 connections"), "{}", over_cap);
+    assert!(over_cap.contains("ulimit -n"), "{}", over_cap);
+
+    let cap_too_high = concurrency_warning(16, Some(1000), Some(1024)).unwrap();
+    assert!(
+        cap_too_high.contains("--max-connections 1000"),
+        "{}",
+        cap_too_high
+    );
+
+    // --max-connections 0: no cap, so only the limit matters
+    let uncapped = concurrency_warning(1000, None, Some(1024)).unwrap();
+    assert!(
+        uncapped.contains("close to the open file limit of 1024"),
+        "{}",
+        uncapped
+    );
+}
+
+#[tokio::test]
+async fn connections_cap_requests_in_flight() {
+    let server = MockServer::start().await;
+    let page: String = (0..4)
+        .map(|i| format!("<a href=\"/slow/{}\">{}</a>", i, i))
+        .collect();
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+        .mount(&server)
+        .await;
+    let delay = Duration::from_millis(200);
+    for i in 0..4 {
+        Mock::given(path(format!("/slow/{}", i)))
+            .respond_with(ResponseTemplate::new(200).set_delay(delay))
+            .mount(&server)
+            .await;
+    }
+
+    let started = Instant::now();
+    let crawler = Crawler::builder(common::root_url(&server))
+        .concurrency(4)
+        .max_connections(Some(1))
+        .build();
+    common::crawl(crawler).await;
+
+    // One at a time, despite the concurrency
+    assert!(started.elapsed() >= delay * 4, "{:?}", started.elapsed());
+    assert_eq!(server.received_requests().await.unwrap().len(), 5);
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn only_parses_html_responses() {
+    let server = MockServer::start().await;
+    let links =
+        r#"<a href="/logo.png">a</a> <a href="/notes.txt">b</a> <a href="/page.xhtml">c</a>"#;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .mount(&server)
+        .await;
+    // Bodies that happen to contain markup, but aren't HTML
+    Mock::given(path("/logo.png"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(r#"<a href="/from-png">x</a>"#, "image/png"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/notes.txt"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(r#"<a href="/from-txt">x</a>"#, "text/plain"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/page.xhtml"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<a href="/from-xhtml">x</a>"#,
+            "application/xhtml+xml; charset=utf-8",
+        ))
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/from-xhtml").unwrap().to_string()]
+    );
+    let requested: Vec<_> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| request.url.path().to_string())
+        .collect();
+    assert!(!requested
+        .iter()
+        .any(|path| path == "/from-png" || path == "/from-txt"));
+}
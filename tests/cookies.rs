@@ -0,0 +1,60 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use reqwest::cookie::Jar;
+use std::sync::Arc;
+use wiremock::matchers::{header, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Serves `route` as a page only to requests sending `cookie`; without it, it's a 404.
+async fn mount_behind_cookie(server: &MockServer, route: &str, cookie: &str) {
+    Mock::given(path(route))
+        .and(header("cookie", cookie))
+        .respond_with(common::page("<p>members only</p>"))
+        .with_priority(1)
+        .mount(server)
+        .await;
+    Mock::given(path(route))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn sends_back_cookies_the_site_sets() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            common::page(r#"<a href="/members">members</a>"#)
+                .insert_header("set-cookie", "session=abc; Path=/"),
+        )
+        .mount(&server)
+        .await;
+    mount_behind_cookie(&server, "/members", "session=abc").await;
+
+    let findings = common::crawl(Crawler::builder(common::root_url(&server)).build()).await;
+
+    assert!(
+        common::broken_urls(&findings).is_empty(),
+        "{:?}",
+        common::broken_urls(&findings)
+    );
+}
+
+#[tokio::test]
+async fn starts_with_the_cookies_in_the_jar() {
+    let server = MockServer::start().await;
+    mount_behind_cookie(&server, "/", "token=xyz").await;
+    let root = common::root_url(&server);
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+    assert_eq!(common::broken_urls(&findings), [root.to_string()]);
+
+    let jar = Jar::default();
+    jar.add_cookie_str("token=xyz; Path=/", &root);
+    let crawler = Crawler::builder(root.clone())
+        .cookie_jar(Arc::new(jar))
+        .build();
+    let findings = common::crawl(crawler).await;
+    assert!(common::broken_urls(&findings).is_empty());
+}
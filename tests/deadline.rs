@@ -0,0 +1,29 @@
+use find_broken_links::deadline::time_left;
+use std::time::{Duration, SystemTime};
+
+fn now() -> SystemTime {
+    humantime::parse_rfc3339("2026-10-14T09:00:00Z").unwrap()
+}
+
+#[test]
+fn reads_seconds_from_now() {
+    assert_eq!(time_left("2700", now()), Ok(Duration::from_secs(2700)));
+    assert_eq!(time_left(" 60\n", now()), Ok(Duration::from_secs(60)));
+}
+
+#[test]
+fn reads_rfc3339_times() {
+    assert_eq!(
+        time_left("2026-10-14T09:30:00Z", now()),
+        Ok(Duration::from_secs(30 * 60))
+    );
+    // Already past: stop right away
+    assert_eq!(time_left("2026-10-14T08:00:00Z", now()), Ok(Duration::ZERO));
+}
+
+#[test]
+fn rejects_anything_else() {
+    for invalid in ["", "soon", "45m", "-5", "2026-10-14"] {
+        assert!(time_left(invalid, now()).is_err(), "{}", invalid);
+    }
+}
@@ -0,0 +1,81 @@
+use find_broken_links::debug_channel::DebugChannel;
+use std::time::Duration;
+
+#[tokio::test]
+async fn counts_a_channel_left_full_once_per_stretch() {
+    let mut channel = DebugChannel::new(1).warn_when_pinned_for(Duration::from_millis(50));
+    let sender = channel.sender();
+    let send = tokio::spawn(async move {
+        for i in 0..3 {
+            sender.send(i).await.unwrap();
+        }
+    });
+    // Nothing reads the channel for a while, so the second send waits
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    for i in 0..3 {
+        assert_eq!(channel.recv().await, Some(i));
+    }
+    send.await.unwrap();
+
+    assert_eq!(channel.get_pinned_count(), 1);
+    assert_eq!(channel.get_max_buffer_size(), 1);
+}
+
+#[tokio::test]
+async fn a_receiver_keeping_up_is_not_backpressure() {
+    let mut channel = DebugChannel::new(4).warn_when_pinned_for(Duration::ZERO);
+    let sender = channel.sender();
+    for i in 0..3 {
+        sender.send(i).await.unwrap();
+        assert_eq!(channel.recv().await, Some(i));
+    }
+
+    assert_eq!(channel.get_pinned_count(), 0);
+}
+
+#[tokio::test]
+async fn try_send_spills_to_the_next_send_in_order() {
+    let mut channel = DebugChannel::new(2);
+    let sender = channel.sender();
+    for i in 0..5 {
+        sender.try_send(i).unwrap();
+    }
+    // Two fit in the channel, the other three wait for room
+    assert_eq!(channel.get_max_buffer_size(), 3);
+
+    let send = tokio::spawn(async move { sender.send(5).await });
+    for i in 0..6 {
+        assert_eq!(channel.recv().await, Some(i));
+    }
+    send.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn try_send_fails_once_the_receiver_is_gone() {
+    let channel = DebugChannel::new(1);
+    let sender = channel.sender();
+    drop(channel);
+
+    assert_eq!(sender.try_send(1).unwrap_err().0, 1);
+}
+
+#[tokio::test]
+async fn stats_count_every_value_sent() {
+    let mut channel = DebugChannel::new(3);
+    let sender = channel.sender();
+    let send = tokio::spawn(async move {
+        for i in 0..10 {
+            sender.send(i).await.unwrap();
+        }
+        sender.try_send(10).unwrap();
+    });
+    for i in 0..11 {
+        assert_eq!(channel.recv().await, Some(i));
+    }
+    send.await.unwrap();
+
+    let stats = channel.stats();
+    assert_eq!(stats.total_sent, 11);
+    assert_eq!(stats.current_usage, 0);
+    assert_eq!(stats.max_usage, channel.get_max_buffer_size());
+}
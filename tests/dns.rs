@@ -0,0 +1,22 @@
+use find_broken_links::dns;
+use url::Url;
+
+#[tokio::test]
+async fn resolves_each_distinct_host_once() {
+    let urls: Vec<Url> = [
+        "http://localhost:8080/a",
+        "http://localhost:8080/b",
+        "http://127.0.0.1/ip-hosts-need-no-lookup",
+        "mailto:someone@example.com",
+    ]
+    .iter()
+    .map(|url| Url::parse(url).unwrap())
+    .collect();
+    assert_eq!(dns::prefetch(&urls).await, 1);
+}
+
+#[tokio::test]
+async fn failed_lookups_are_not_errors() {
+    let url = Url::parse("http://does-not-exist.invalid/").unwrap();
+    assert_eq!(dns::prefetch([&url]).await, 0);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::skipped::SkipReason;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn lists_the_links_on_the_root_url_without_fetching_them() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<a href=\"/docs/\">docs</a>
+             <a href=\"/docs/\">docs again</a>
+             <img src=\"/logo.png\">
+             <a href=\"https://other.example/\">other</a>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+
+    let root = common::root_url(&server);
+    let crawler = Crawler::builder(root.clone())
+        .report_skipped(true)
+        .dry_run(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let would_fetch: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::WouldFetch {
+                url,
+                referrer,
+                check_only,
+            } => {
+                assert_eq!(*referrer, root.to_string());
+                Some((url.strip_prefix(root.as_str())?, *check_only))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(would_fetch, [("docs/", false), ("logo.png", true)]);
+    assert!(findings.iter().any(|finding| matches!(
+        finding,
+        Finding::Skipped(skipped)
+            if skipped.url == "https://other.example/" && skipped.reason == SkipReason::OutOfDomain
+    )));
+
+    let requested: Vec<_> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| request.url.path().to_string())
+        .collect();
+    assert_eq!(requested, ["/"]);
+}
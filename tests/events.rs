@@ -0,0 +1,87 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::events::{CrawlEvent, EventWriter};
+use find_broken_links::progress::CrawlSummary;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn writes_one_typed_event_per_line() {
+    let site = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(
+            r#"<a href="/gone">x</a><a href="mailto:me@example.com">mail</a>"#,
+        ))
+        .mount(&site)
+        .await;
+    Mock::given(path("/gone"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&site)
+        .await;
+    let root = common::root_url(&site);
+
+    let crawler = Crawler::builder(root.clone())
+        .report_checked(true)
+        .report_skipped(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+    let mut out = Vec::new();
+    let mut events = EventWriter::new(&mut out);
+    for finding in &findings {
+        events.record_finding(finding).unwrap();
+    }
+    let summary = CrawlSummary {
+        pages_crawled: 2,
+        links_found: 1,
+        broken: 1,
+        broken_by_status: BTreeMap::from([(404, 1)]),
+        elapsed_ms: 5,
+        max_channel_buffer: 1,
+        findings_sent: 4,
+    };
+    events.record(&CrawlEvent::Done { summary }).unwrap();
+
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<Value> = out
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    for line in &lines {
+        assert!(
+            line["timestamp"].as_str().unwrap().ends_with('Z'),
+            "{}",
+            line
+        );
+    }
+    let of_type =
+        |kind: &str| -> Vec<&Value> { lines.iter().filter(|line| line["type"] == kind).collect() };
+    let gone = root.join("/gone").unwrap().to_string();
+
+    let checked: Vec<_> = of_type("page_checked")
+        .iter()
+        .map(|line| (line["url"].as_str().unwrap(), line["status"].as_u64()))
+        .collect();
+    assert!(
+        checked.contains(&(root.as_str(), Some(200))),
+        "{:?}",
+        checked
+    );
+    assert!(
+        checked.contains(&(gone.as_str(), Some(404))),
+        "{:?}",
+        checked
+    );
+    let broken = of_type("broken_link");
+    assert_eq!(broken.len(), 1);
+    assert_eq!(broken[0]["url"], gone.as_str());
+    assert_eq!(broken[0]["source_url"], root.as_str());
+    assert_eq!(broken[0]["status"], 404);
+    let skipped = of_type("skipped");
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0]["url"], "mailto:me@example.com");
+    assert_eq!(lines.last().unwrap()["type"], "done");
+    assert_eq!(lines.last().unwrap()["summary"]["broken"], 1);
+}
@@ -0,0 +1,259 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::scope::ScopeEntry;
+use find_broken_links::skipped::SkipReason;
+use std::time::{Duration, Instant};
+use wiremock::matchers::{method, path, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// The site's pages, on `site`, link to pages on `other`: `/ok`, `/gone` (from two pages),
+/// `/no-head`, which only answers GET, and to a port nothing listens on.
+async fn mount_sites(site: &MockServer, other: &MockServer) {
+    let other_url = common::root_url(other);
+    let link = |route: &str| format!("<a href=\"{}\">x</a>", other_url.join(route).unwrap());
+    let home = format!(
+        "{}{}{}<a href=\"/about\">about</a><a href=\"http://localhost:1/\">down</a>",
+        link("/ok"),
+        link("/gone"),
+        link("/no-head")
+    );
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(home, "text/html"))
+        .mount(site)
+        .await;
+    Mock::given(path("/about"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(link("/gone"), "text/html"))
+        .mount(site)
+        .await;
+
+    Mock::given(method("HEAD"))
+        .and(path("/ok"))
+        .respond_with(ResponseTemplate::new(200).insert_header("content-type", "text/html"))
+        .expect(1)
+        .mount(other)
+        .await;
+    Mock::given(method("HEAD"))
+        .and(path("/gone"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(other)
+        .await;
+    Mock::given(method("HEAD"))
+        .and(path("/no-head"))
+        .respond_with(ResponseTemplate::new(405))
+        .mount(other)
+        .await;
+    // Never parsed, so its link isn't followed
+    Mock::given(method("GET"))
+        .and(path("/no-head"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/deeper\">x</a>", "text/html"),
+        )
+        .expect(1)
+        .mount(other)
+        .await;
+}
+
+/// Only the site itself is in scope: the other server is on `localhost` too.
+fn site_only(site: &MockServer) -> Vec<ScopeEntry> {
+    let port = common::root_url(site).port().unwrap();
+    vec![format!("localhost:{}", port).parse().unwrap()]
+}
+
+#[tokio::test]
+async fn checks_links_to_other_sites_once_without_crawling_them() {
+    let (site, other) = (MockServer::start().await, MockServer::start().await);
+    mount_sites(&site, &other).await;
+    let crawler = Crawler::builder(common::root_url(&site))
+        .scope(site_only(&site))
+        .check_external(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let gone = common::root_url(&other).join("/gone").unwrap().to_string();
+    let mut broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound {
+                url, status, error, ..
+            } => Some((url.clone(), *status, error.is_some())),
+            _ => None,
+        })
+        .collect();
+    broken.sort();
+    assert_eq!(
+        broken,
+        [
+            ("http://localhost:1/".to_string(), Some(0), true),
+            (gone.clone(), Some(404), false),
+        ]
+    );
+    // The second page linking to it is listed too
+    assert!(findings.iter().any(|finding| matches!(
+        finding,
+        Finding::AlsoLinkedFrom { url, .. } if *url == gone
+    )));
+    let requested: Vec<_> = other
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| request.url.path().to_string())
+        .collect();
+    assert!(
+        !requested.contains(&"/deeper".to_string()),
+        "{:?}",
+        requested
+    );
+}
+
+#[tokio::test]
+async fn skips_links_to_other_sites_by_default() {
+    let (site, other) = (MockServer::start().await, MockServer::start().await);
+    mount_sites(&site, &other).await;
+    let crawler = Crawler::builder(common::root_url(&site))
+        .scope(site_only(&site))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert!(common::broken_urls(&findings).is_empty());
+    assert!(other.received_requests().await.unwrap().is_empty());
+    // Drop the expectations of a crawl with `check_external`
+    other.reset().await;
+}
+
+#[tokio::test]
+async fn contacts_at_most_max_distinct_hosts_other_sites() {
+    let (site, other) = (MockServer::start().await, MockServer::start().await);
+    // `other` by IP, and two more hosts that nothing listens on
+    let other_url = |route: &str| format!("{}{}", other.uri(), route);
+    let links = format!(
+        r#"<a href="{}">a</a> <a href="{}">b</a> <a href="http://127.0.0.2:1/">c</a>
+        <a href="http://127.0.0.3:1/">d</a> <a href="http://127.0.0.2:1/x">e</a>
+        <a href="/about">about</a>"#,
+        other_url("/ok"),
+        other_url("/gone")
+    );
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .mount(&site)
+        .await;
+    // In scope, so not held back by the cap
+    Mock::given(path("/about"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(r#"<a href="/missing">x</a>"#, "text/html"),
+        )
+        .mount(&site)
+        .await;
+    Mock::given(path("/ok"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&other)
+        .await;
+    let root = common::root_url(&site);
+
+    let crawler = Crawler::builder(root.clone())
+        .scope(site_only(&site))
+        .check_external(true)
+        .max_distinct_hosts(Some(1))
+        .report_skipped(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let mut broken = common::broken_urls(&findings);
+    broken.sort();
+    assert_eq!(
+        broken,
+        [
+            other_url("/gone"),
+            root.join("/missing").unwrap().to_string()
+        ]
+    );
+    let mut capped: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Skipped(link) if link.reason == SkipReason::TooManyHosts => {
+                Some(link.url.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    capped.sort();
+    assert_eq!(
+        capped,
+        [
+            "http://127.0.0.2:1/",
+            "http://127.0.0.2:1/x",
+            "http://127.0.0.3:1/"
+        ]
+    );
+}
+
+/// A site whose home page links to `/slow/0` to `/slow/3` on `other`, by IP, each taking
+/// `delay` to answer, and to `/about`, which does the same.
+async fn mount_slow_links(site: &MockServer, other: &MockServer, delay: Duration) {
+    let links: String = (0..4)
+        .map(|i| format!(r#"<a href="{}/slow/{}">x</a>"#, other.uri(), i))
+        .collect();
+    Mock::given(path("/"))
+        .respond_with(common::page(&format!(
+            r#"{}<a href="/about">about</a>"#,
+            links
+        )))
+        .mount(site)
+        .await;
+    Mock::given(path("/about"))
+        .respond_with(common::page(&links))
+        .mount(site)
+        .await;
+    Mock::given(path_regex("^/slow/"))
+        .respond_with(ResponseTemplate::new(200).set_delay(delay))
+        .mount(other)
+        .await;
+}
+
+#[tokio::test]
+async fn checks_other_sites_alongside_the_crawl_with_their_own_concurrency() {
+    let (site, other) = (MockServer::start().await, MockServer::start().await);
+    mount_slow_links(&site, &other, Duration::from_millis(400)).await;
+
+    let crawler = Crawler::builder(common::root_url(&site))
+        .scope(site_only(&site))
+        .check_external(true)
+        .external_concurrency(Some(4))
+        .build();
+    let started = Instant::now();
+    let findings = common::crawl(crawler).await;
+
+    assert!(common::broken_urls(&findings).is_empty());
+    assert_eq!(other.received_requests().await.unwrap().len(), 4);
+    // One at a time, sharing the crawl's single slot, they'd take 1.6s
+    assert!(
+        started.elapsed() < Duration::from_millis(1200),
+        "{:?}",
+        started.elapsed()
+    );
+}
+
+#[tokio::test]
+async fn paces_requests_to_each_other_site() {
+    let (site, other) = (MockServer::start().await, MockServer::start().await);
+    mount_slow_links(&site, &other, Duration::ZERO).await;
+
+    let crawler = Crawler::builder(common::root_url(&site))
+        .scope(site_only(&site))
+        .check_external(true)
+        .external_concurrency(Some(4))
+        .external_delay(Duration::from_millis(200))
+        .build();
+    let started = Instant::now();
+    common::crawl(crawler).await;
+
+    // Four requests to the same site, 200ms apart
+    assert_eq!(other.received_requests().await.unwrap().len(), 4);
+    assert!(
+        started.elapsed() >= Duration::from_millis(600),
+        "{:?}",
+        started.elapsed()
+    );
+}
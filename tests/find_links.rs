@@ -0,0 +1,348 @@
+use find_broken_links::links::{
+    extract_links, extract_links_from, extract_template_links, find_links, fragment_exists,
+    needs_percent_encoding, page_anchors, HtmxMethod, LinkElement, LinkSources,
+};
+use find_broken_links::skipped::SkipReason;
+use std::fs;
+use std::path::Path;
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {}", path.display(), e))
+}
+
+#[test]
+fn extracts_exact_links_from_fixtures() {
+    // (fixture file, `<a href>` links expected in document order)
+    let cases: &[(&str, &[&str])] = &[
+        (
+            "basic.html",
+            &[
+                "https://example.com/absolute",
+                "relative/page",
+                "/root-relative",
+                "../up-one",
+                "//cdn.example.com/protocol-relative",
+                "?page=2",
+                "/with#fragment",
+            ],
+        ),
+        ("denied.html", &["/kept"]),
+        ("missing_href.html", &["", "/present"]),
+        ("nested.html", &["/nav/one", "/nav/two", "/outer", "/inner"]),
+        ("non_anchor.html", &["/only-anchor"]),
+        ("noscript.html", &["/fallback/home", "/fallback/docs"]),
+        // Template content isn't part of the document tree
+        ("template.html", &[]),
+        ("htmx.html", &["/plain"]),
+    ];
+
+    for (name, expected) in cases {
+        let links = extract_links(&fixture(name)).links;
+        assert_eq!(links, *expected, "fixture {}", name);
+    }
+}
+
+#[test]
+fn empty_document_has_no_links() {
+    assert!(find_links("").is_empty());
+    assert!(find_links("<p>no anchors here</p>").is_empty());
+}
+
+#[test]
+fn finds_resources_to_check_but_not_crawl() {
+    let found = find_links(&fixture("non_anchor.html"));
+    let found: Vec<_> = found
+        .iter()
+        .map(|link| (link.url.as_str(), link.element))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("/only-anchor", LinkElement::Anchor),
+            ("/image.png", LinkElement::Img),
+            ("/app.js", LinkElement::Script),
+            ("/style.css", LinkElement::Link),
+            ("/embedded", LinkElement::Iframe),
+        ]
+    );
+    assert!(LinkElement::Anchor.is_crawlable());
+    assert!(!LinkElement::Img.is_crawlable());
+
+    let extracted = extract_links(
+        r#"<link rel="preconnect" href="https://fonts.gstatic.com">
+           <link rel="DNS-Prefetch" href="//cdn.example.com">
+           <img src="data:image/png;base64,AA=="><img src="">
+           <iframe src="blob:https://example.com/6e2b1d4c"></iframe>"#,
+    );
+    assert!(extracted.resources.is_empty());
+    assert_eq!(
+        extracted.skipped,
+        [
+            (
+                "data:image/png;base64,AA==".to_string(),
+                SkipReason::DeniedProtocol
+            ),
+            (
+                "blob:https://example.com/6e2b1d4c".to_string(),
+                SkipReason::DeniedProtocol
+            )
+        ]
+    );
+}
+
+#[test]
+fn leaves_out_nofollow_links_when_asked() {
+    let html = r#"<a href="/kept" rel="noopener">kept</a>
+        <a href="/login" rel="NoFollow">log in</a>
+        <a href="/ugc" rel="ugc nofollow noopener">user link</a>
+        <a href="/not-nofollow" rel="nofollower">kept</a>"#;
+    assert_eq!(
+        extract_links(html).links,
+        ["/kept", "/login", "/ugc", "/not-nofollow"]
+    );
+
+    let respecting = LinkSources {
+        respect_nofollow: true,
+        ..LinkSources::default()
+    };
+    let extracted = extract_links_from(html, respecting);
+    assert_eq!(extracted.links, ["/kept", "/not-nofollow"]);
+    assert_eq!(
+        extracted.skipped,
+        [
+            ("/login".to_string(), SkipReason::Nofollow),
+            ("/ugc".to_string(), SkipReason::Nofollow),
+        ]
+    );
+}
+
+#[test]
+fn finds_the_first_base_href() {
+    let extracted = extract_links(
+        r#"<head><base target="_blank"><base href="/app/"><base href="/other/"></head>
+           <a href="start">start</a>"#,
+    );
+    assert_eq!(extracted.base.as_deref(), Some("/app/"));
+    assert_eq!(extracted.links, ["start"]);
+
+    assert_eq!(extract_links(&fixture("basic.html")).base, None);
+    // Neither fallback markup nor templates can change the page's base
+    let fragments = LinkSources {
+        templates: true,
+        ..LinkSources::default()
+    };
+    let html = r#"<noscript><base href="/ns/"></noscript><template><base href="/t/"></template>"#;
+    assert_eq!(extract_links_from(html, fragments).base, None);
+}
+
+#[test]
+fn finds_the_first_canonical_link() {
+    let extracted = extract_links(
+        r#"<head><link rel="stylesheet" href="/site.css">
+           <link rel="Canonical" href="/post"><link rel="canonical" href="/other"></head>"#,
+    );
+    assert_eq!(extracted.canonical.as_deref(), Some("/post"));
+
+    assert_eq!(extract_links(&fixture("basic.html")).canonical, None);
+    let html = r#"<noscript><link rel="canonical" href="/ns"></noscript>"#;
+    assert_eq!(extract_links(html).canonical, None);
+}
+
+#[test]
+fn anchors_are_ids_and_anchor_names_outside_templates() {
+    let html = r#"<h1 id="intro">Intro</h1> <a name="legacy"></a> <div name="ignored"></div>
+        <p id="">x</p> <template><h2 id="hidden">x</h2></template>"#;
+    assert_eq!(page_anchors(html), ["intro", "legacy"]);
+
+    let sources = LinkSources {
+        anchors: true,
+        templates: true,
+        ..LinkSources::default()
+    };
+    assert_eq!(
+        extract_links_from(html, sources).anchors,
+        ["intro", "legacy"]
+    );
+    assert!(extract_links(html).anchors.is_empty());
+}
+
+#[test]
+fn fragments_match_decoded_anchors() {
+    let anchors = [
+        "intro".to_string(),
+        "old name".to_string(),
+        "café".to_string(),
+    ];
+    assert!(fragment_exists("intro", &anchors));
+    assert!(fragment_exists("old%20name", &anchors));
+    assert!(fragment_exists("caf%C3%A9", &anchors));
+    assert!(fragment_exists("intro:~:text=hello", &anchors));
+    assert!(fragment_exists(":~:text=hello", &anchors));
+    assert!(fragment_exists("top", &anchors));
+    assert!(fragment_exists("", &anchors));
+    assert!(!fragment_exists("Intro", &anchors));
+    assert!(!fragment_exists("missing", &anchors));
+}
+
+#[test]
+fn detects_links_needing_percent_encoding() {
+    for link in ["/my file.pdf", "/a|b", "/quote\"d", "/{id}", "/tab\there"] {
+        assert!(needs_percent_encoding(link), "{}", link);
+    }
+    for link in ["/my%20file.pdf", "/plain/path?q=1&x=y#frag", "/caf\u{e9}"] {
+        assert!(!needs_percent_encoding(link), "{}", link);
+    }
+}
+
+#[test]
+fn reports_why_denied_links_were_skipped() {
+    let extracted = extract_links(&fixture("denied.html"));
+    assert_eq!(extracted.links, ["/kept"]);
+    assert_eq!(
+        extracted.skipped,
+        [
+            (
+                "mailto:someone@example.com".to_string(),
+                SkipReason::DeniedProtocol
+            ),
+            ("tel:+15555555555".to_string(), SkipReason::DeniedProtocol),
+            (
+                "ftp://files.example.com/file.txt".to_string(),
+                SkipReason::DeniedProtocol
+            ),
+            ("#".to_string(), SkipReason::DeniedLink),
+            ("javascript:void(0)".to_string(), SkipReason::DeniedLink),
+            ("javascript:alert(1)".to_string(), SkipReason::DeniedLink),
+            (" JavaScript:openMenu()".to_string(), SkipReason::DeniedLink),
+            (
+                "data:text/plain,hello".to_string(),
+                SkipReason::DeniedProtocol
+            ),
+            (
+                "blob:https://example.com/6e2b1d4c".to_string(),
+                SkipReason::DeniedProtocol
+            ),
+        ]
+    );
+}
+
+#[test]
+fn finds_links_only_inside_templates() {
+    let extracted = extract_template_links(&fixture("template.html"));
+    assert_eq!(extracted.links, ["/items/detail", "/badges/new"]);
+    assert_eq!(
+        extracted.skipped,
+        [(
+            "mailto:help@example.com".to_string(),
+            SkipReason::DeniedProtocol
+        )]
+    );
+
+    let unclosed = extract_template_links("<template><a href=\"/open\">x</a>");
+    assert_eq!(unclosed.links, ["/open"]);
+    assert!(extract_template_links(&fixture("basic.html"))
+        .links
+        .is_empty());
+}
+
+#[test]
+fn finds_htmx_endpoints_when_asked() {
+    let html = fixture("htmx.html");
+    let get_only = LinkSources {
+        htmx: true,
+        ..LinkSources::default()
+    };
+    let extracted = extract_links_from(&html, get_only);
+    assert_eq!(extracted.links, ["/plain"]);
+    assert_eq!(
+        extracted.htmx,
+        [
+            ("/fragments/cart".to_string(), HtmxMethod::Get),
+            ("/fragments/news".to_string(), HtmxMethod::Get),
+        ]
+    );
+    assert_eq!(
+        extracted.skipped,
+        [("/cart/add".to_string(), SkipReason::HtmxPost)]
+    );
+
+    let with_post = LinkSources {
+        htmx_post: true,
+        ..get_only
+    };
+    let extracted = extract_links_from(&html, with_post);
+    assert_eq!(extracted.htmx.len(), 3);
+    assert_eq!(
+        extracted.htmx[2],
+        ("/cart/add".to_string(), HtmxMethod::Post)
+    );
+    assert!(extracted.skipped.is_empty());
+
+    assert!(extract_links(&html).htmx.is_empty());
+}
+
+#[cfg(feature = "fast-parser")]
+#[test]
+fn tokenizer_finds_the_same_links_as_the_tree() {
+    use find_broken_links::link_tokenizer;
+    use find_broken_links::links::document_links;
+
+    let mut pages: Vec<String> =
+        fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+            .map(|path| fs::read_to_string(path).unwrap())
+            .collect();
+    pages.extend(
+        [
+            r#"<script>document.write('<a href="/script">x</a>')</script><a href="/after">x</a>"#,
+            r#"<style>a[href="/style"] {}</style><textarea><a href="/textarea"></textarea>"#,
+            r#"<title><a href="/title"></title><iframe><a href="/iframe"></iframe><a href="/ok">"#,
+            r#"<template><a href="/t"><template><a href="/tt"></template></template><a href="/out">"#,
+            r#"<noscript><a href="/ns"><div hx-get="/ns-hx"></div></noscript><a href="/page">"#,
+            r#"<noscript><a href="/first"></noscript><noscript><a href="/second">"#,
+            r#"<svg><a xlink:href="/svg"></a><title><a href="/svg-title"></a></title></svg>"#,
+            r#"<svg><script><a href="/svg-script"></a></script></svg><script><a href="/no">"#,
+            r#"<a href="/one" href="/two"><a data-hx-post="/post" hx-get="">"#,
+            r##"<a href="mailto:a@example.com"><a href="#"><a href="/a?b=1&amp;c=2">"##,
+            r#"<!-- <a href="/comment"> --><a href = '/single' >"#,
+            r#"<plaintext><a href="/plaintext">"#,
+            r#"<iframe src="/frame"><img src="/in-frame"></iframe><link rel=preconnect href=/o>"#,
+            r#"<svg><script src="/svg.js"></script></svg><img src="data:,x"><script src=/app.js>"#,
+            r#"<noscript><base href="/ns/"></noscript><base><base href="/b/"><base href="/c/">"#,
+            r##"<a href="/a" rel="nofollow"><a href="#" rel="nofollow"><a rel="nofollow">"##,
+        ]
+        .map(String::from),
+    );
+
+    let all = [
+        LinkSources::default(),
+        LinkSources {
+            htmx: true,
+            ..LinkSources::default()
+        },
+        LinkSources {
+            htmx: true,
+            htmx_post: true,
+            ..LinkSources::default()
+        },
+        LinkSources {
+            respect_nofollow: true,
+            ..LinkSources::default()
+        },
+    ];
+    for html in &pages {
+        for sources in all {
+            assert_eq!(
+                link_tokenizer::document_links(html, sources),
+                document_links(html, sources),
+                "{}",
+                html
+            );
+        }
+    }
+}
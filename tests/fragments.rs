@@ -0,0 +1,96 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::links::LinkKind;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer};
+
+async fn site_with_fragments() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(
+            r##"<h2 id="local">Here</h2>
+            <a href="#local">a</a> <a href="#nowhere">b</a> <a href="/guide#install">c</a>
+            <a href="/guide#gone">d</a> <a href="/guide#top">e</a> <a href="/guide#old%20name">f</a>
+            <a href="/about">about</a>"##,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/about"))
+        .respond_with(common::page(r#"<a href="/guide#gone">x</a>"#))
+        .mount(&server)
+        .await;
+    Mock::given(path("/guide"))
+        .respond_with(common::page(
+            r#"<section id="install">Install</section> <a name="old name"></a>"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn reports_fragments_missing_from_their_page() {
+    let server = site_with_fragments().await;
+    let root = common::root_url(&server);
+    let url = |route: &str| root.join(route).unwrap().to_string();
+
+    let crawler = Crawler::builder(root.clone()).check_fragments(true).build();
+    let findings = common::crawl(crawler).await;
+
+    let mut missing: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound {
+                url,
+                referrer,
+                kind,
+                status,
+                error,
+                ..
+            } => Some((
+                url.clone(),
+                referrer.clone().unwrap(),
+                *kind,
+                *status,
+                error.clone().unwrap(),
+            )),
+            _ => None,
+        })
+        .collect();
+    missing.sort();
+    assert_eq!(
+        missing,
+        [
+            (
+                url("/#nowhere"),
+                url("/"),
+                LinkKind::Fragment,
+                Some(200),
+                "missing fragment: nothing on the page has id=\"nowhere\"".to_string()
+            ),
+            (
+                url("/guide#gone"),
+                url("/"),
+                LinkKind::Fragment,
+                Some(200),
+                "missing fragment: nothing on the page has id=\"gone\"".to_string()
+            ),
+        ]
+    );
+    // Reported once, with the other page linking to it
+    assert!(findings.iter().any(|finding| matches!(
+        finding,
+        Finding::AlsoLinkedFrom { url: link, referrer } if *link == url("/guide#gone") && *referrer == url("/about")
+    )));
+}
+
+#[tokio::test]
+async fn leaves_fragments_alone_by_default() {
+    let server = site_with_fragments().await;
+
+    let findings = common::crawl(Crawler::builder(common::root_url(&server)).build()).await;
+
+    assert!(common::broken_urls(&findings).is_empty());
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use find_broken_links::crawler::{basic_auth, Crawler};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use wiremock::matchers::{header, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A staging site that answers 401 unless both the gate header and the credentials are sent.
+async fn gated_site() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .and(header("x-staging-gate", "open"))
+        .and(header("authorization", "Basic dXNlcjpwYXNz"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(r#"<a href="/gone">x</a>"#, "text/html"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn sends_the_headers_with_every_request() {
+    let server = gated_site().await;
+    let root = common::root_url(&server);
+    let mut headers = HeaderMap::new();
+    headers.insert("x-staging-gate", HeaderValue::from_static("open"));
+    headers.insert(AUTHORIZATION, basic_auth("user", "pass"));
+
+    let crawler = Crawler::builder(root.clone()).headers(headers).build();
+    let findings = common::crawl(crawler).await;
+
+    // The page only loads and links to `/gone` with both headers
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/gone").unwrap().to_string()]
+    );
+    let requests = server.received_requests().await.unwrap();
+    let gone = requests.iter().find(|r| r.url.path() == "/gone").unwrap();
+    assert_eq!(gone.headers.get("x-staging-gate").unwrap(), "open");
+}
+
+#[tokio::test]
+async fn is_turned_away_without_them() {
+    let server = gated_site().await;
+    let root = common::root_url(&server);
+    let mut headers = HeaderMap::new();
+    headers.insert("x-staging-gate", HeaderValue::from_static("open"));
+
+    let crawler = Crawler::builder(root.clone()).headers(headers).build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(common::broken_urls(&findings), [root.to_string()]);
+}
+
+#[test]
+fn basic_auth_credentials_are_sensitive() {
+    let value = basic_auth("user", "pass");
+    assert_eq!(value, "Basic dXNlcjpwYXNz");
+    assert!(value.is_sensitive());
+    assert_eq!(format!("{:?}", value), "Sensitive");
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use std::sync::{Arc, Mutex};
+
+#[tokio::test]
+async fn calls_on_broken_link_for_each_broken_link() {
+    let site = common::site(vec![
+        (
+            "/",
+            common::page(r#"<a href="/a">a</a><a href="/gone">gone</a>"#),
+        ),
+        ("/a", common::page(r#"<a href="/gone">gone</a>"#)),
+    ])
+    .await;
+    let root = common::root_url(&site);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let hook_seen = Arc::clone(&seen);
+    let crawler = Crawler::builder(root.clone())
+        .on_broken_link(move |error| {
+            hook_seen
+                .lock()
+                .unwrap()
+                .push((error.url.clone(), error.status));
+        })
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let gone = root.join("/gone").unwrap().to_string();
+    assert_eq!(common::broken_urls(&findings), [gone.as_str()]);
+    assert_eq!(*seen.lock().unwrap(), [(gone, Some(404))]);
+}
+
+#[tokio::test]
+async fn should_crawl_replaces_the_domain_check() {
+    let site = common::site(vec![
+        (
+            "/",
+            common::page(r#"<a href="/docs/intro">intro</a><a href="/blog/post">blog</a>"#),
+        ),
+        ("/docs/intro", common::page(r#"<a href="/docs/gone">x</a>"#)),
+        ("/blog/post", common::page(r#"<a href="/blog/gone">x</a>"#)),
+    ])
+    .await;
+    let root = common::root_url(&site);
+
+    let crawler = Crawler::builder(root.clone())
+        .should_crawl(|url| url.path() == "/" || url.path().starts_with("/docs/"))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/docs/gone").unwrap().to_string()]
+    );
+    assert_eq!(
+        common::requested_paths(&site).await,
+        ["GET /", "GET /docs/gone", "GET /docs/intro"]
+    );
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::links::LinkKind;
+use find_broken_links::safety::UnsafeUrlFilter;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// `/` has a broken `hx-get`, a broken plain link and an `hx-post` endpoint that only takes
+/// POST.
+async fn mount_htmx_page(server: &MockServer) {
+    let page = r#"<a href="/gone">gone</a>
+        <div hx-get="/fragments/missing"></div>
+        <form hx-post="/cart/add"></form>"#;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/cart/add"))
+        .respond_with(ResponseTemplate::new(405))
+        .mount(server)
+        .await;
+}
+
+fn broken_with_kind(findings: &[Finding]) -> Vec<(String, LinkKind)> {
+    let mut broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, kind, .. } => Some((url.clone(), *kind)),
+            _ => None,
+        })
+        .collect();
+    broken.sort();
+    broken
+}
+
+#[tokio::test]
+async fn reports_broken_htmx_endpoints_in_their_own_category() {
+    let server = MockServer::start().await;
+    mount_htmx_page(&server).await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone()).htmx_links(true).build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        broken_with_kind(&findings),
+        [
+            (
+                root.join("/fragments/missing").unwrap().to_string(),
+                LinkKind::Htmx
+            ),
+            (root.join("/gone").unwrap().to_string(), LinkKind::Link),
+        ]
+    );
+    // The hx-post endpoint was checked, and its 405 didn't count as broken
+    let requested: Vec<_> = server.received_requests().await.unwrap();
+    assert!(requested
+        .iter()
+        .any(|request| request.url.path() == "/cart/add"));
+}
+
+#[tokio::test]
+async fn safe_only_skips_hx_post_endpoints() {
+    let server = MockServer::start().await;
+    mount_htmx_page(&server).await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .htmx_links(true)
+        .safe_only(Some(UnsafeUrlFilter::default()))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(broken_with_kind(&findings).len(), 2);
+    let requested: Vec<_> = server.received_requests().await.unwrap();
+    assert!(!requested
+        .iter()
+        .any(|request| request.url.path() == "/cart/add"));
+}
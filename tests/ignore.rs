@@ -0,0 +1,97 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::ignore::{IgnoreList, IgnorePattern};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn pattern(pattern: &str) -> IgnorePattern {
+    pattern.parse().unwrap()
+}
+
+#[test]
+fn matches_exact_urls_globs_and_regexes() {
+    // (pattern, URL, whether it matches)
+    let cases = [
+        ("https://example.com", "https://example.com/", true),
+        ("https://example.com/a", "https://example.com/a?b", false),
+        (
+            "https://api.example.com/*",
+            "https://api.example.com/v1/x",
+            true,
+        ),
+        ("*/login*", "https://example.com/account/login?next=/", true),
+        ("*/login*", "https://example.com/logout", false),
+        (
+            r"/^https://(www\.)?linkedin\.com//",
+            "https://www.linkedin.com/in/x",
+            true,
+        ),
+        (
+            r"/^https://(www\.)?linkedin\.com//",
+            "https://example.com/linkedin.com/",
+            false,
+        ),
+        (r"/\.pdf$/", "https://example.com/report.pdf", true),
+    ];
+    for (written, url, expected) in cases {
+        assert_eq!(
+            pattern(written).matches(url),
+            expected,
+            "{} {}",
+            written,
+            url
+        );
+    }
+    assert!(matches!(pattern("/a+/"), IgnorePattern::Regex(_)));
+    assert!("/(/".parse::<IgnorePattern>().is_err());
+}
+
+#[test]
+fn parses_ignore_files() {
+    let list = IgnoreList::parse(
+        "# Rate-limited\nhttps://api.example.com/*\n\n  https://example.com/members  \n",
+    )
+    .unwrap();
+    assert_eq!(
+        list.matching_pattern("https://example.com/members")
+            .map(|pattern| pattern.to_string()),
+        Some("https://example.com/members".to_string())
+    );
+    assert!(list
+        .matching_pattern("https://api.example.com/v1")
+        .is_some());
+    assert!(list.matching_pattern("https://example.com/").is_none());
+
+    let error = IgnoreList::parse("https://example.com/\n/[/\n").unwrap_err();
+    assert!(error.starts_with("line 2:"), "{}", error);
+}
+
+#[tokio::test]
+async fn leaves_ignored_broken_links_out_of_the_findings() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<a href=\"/gone\">gone</a><a href=\"/members\">members</a>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/members"))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&server)
+        .await;
+
+    let root = common::root_url(&server);
+    let crawler = Crawler::builder(root.clone())
+        .ignore_broken(IgnoreList::new(vec![pattern("*/members")]))
+        .max_errors(Some(1))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    // Crawled depth-first, /members is checked first, and doesn't use up the error cap
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/gone").unwrap().to_string()]
+    );
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::json_links::{find_json_links, JsonPath};
+use serde_json::json;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn paths(paths: &[&str]) -> Vec<JsonPath> {
+    paths.iter().map(|path| path.parse().unwrap()).collect()
+}
+
+#[test]
+fn heuristic_only_takes_absolute_http_urls() {
+    let document = json!({
+        "title": "Hello world",
+        "canonical": "https://example.com/post",
+        "image": { "src": "http://cdn.example.com/a.png", "alt": "/not/a/link" },
+        "tags": ["news", "https://example.com/tags/news"],
+        "contact": "mailto:me@example.com",
+        "note": "see https://example.com/ for more",
+        "count": 3
+    });
+    let mut links = find_json_links(&document, &[]);
+    links.sort();
+    assert_eq!(
+        links,
+        [
+            "http://cdn.example.com/a.png",
+            "https://example.com/post",
+            "https://example.com/tags/news",
+        ]
+    );
+}
+
+#[test]
+fn paths_select_relative_links_too() {
+    let document = json!({
+        "items": [
+            { "url": "/posts/1", "author": { "href": "/authors/a" } },
+            { "url": "/posts/2", "author": { "href": "/authors/b" } }
+        ],
+        "next": { "href": "/page/2" }
+    });
+    assert_eq!(
+        find_json_links(&document, &paths(&["$.items[*].url"])),
+        ["/posts/1", "/posts/2"]
+    );
+    assert_eq!(
+        find_json_links(&document, &paths(&["$..href"])),
+        ["/authors/a", "/authors/b", "/page/2"]
+    );
+    assert_eq!(
+        find_json_links(&document, &paths(&["items[1]['url']", "next.*"])),
+        ["/posts/2", "/page/2"]
+    );
+}
+
+#[test]
+fn rejects_unsupported_paths() {
+    for path in ["$.", "$..", "$.items[", "$.items[?(@.url)]"] {
+        assert!(path.parse::<JsonPath>().is_err(), "{}", path);
+    }
+}
+
+#[tokio::test]
+async fn crawls_links_from_json_responses() {
+    let server = MockServer::start().await;
+    let root = common::root_url(&server);
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/api/posts\">api</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/api/posts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "posts": [{ "url": root.join("/posts/gone").unwrap().to_string() }]
+        })))
+        .mount(&server)
+        .await;
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+    assert!(common::broken_urls(&findings).is_empty());
+
+    let crawler = Crawler::builder(root.clone())
+        .json_links(Some(Vec::new()))
+        .build();
+    let findings = common::crawl(crawler).await;
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/posts/gone").unwrap().to_string()]
+    );
+}
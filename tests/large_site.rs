@@ -0,0 +1,81 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+const PAGES: usize = 500;
+
+/// `/page/N` links to the next ten pages (wrapping around) and to `/`, so every page is linked
+/// from many others; pages divisible by 50 are missing.
+struct InterlinkedSite;
+
+impl Respond for InterlinkedSite {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let page = match request.url.path() {
+            "/" => 0,
+            path => match path.strip_prefix("/page/").and_then(|n| n.parse().ok()) {
+                Some(n) if n < PAGES && n % 50 != 0 => n,
+                _ => return ResponseTemplate::new(404),
+            },
+        };
+        let links: String = (1..=10)
+            .map(|offset| format!("<a href=\"/page/{}\">next</a>", (page + offset) % PAGES))
+            .collect();
+        ResponseTemplate::new(200)
+            .set_body_raw(format!("<a href=\"/\">home</a>{}", links), "text/html")
+    }
+}
+
+/// Crawls the site `concurrency` pages at a time and checks that every page was requested once.
+async fn crawl_interlinked_site(concurrency: usize) {
+    let server = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(InterlinkedSite)
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .concurrency(concurrency)
+        .build();
+    // The whole queue is never bigger than the site if URLs are queued once
+    let progress = crawler.progress();
+    let most_queued = Arc::new(AtomicUsize::new(0));
+    let sampler = tokio::spawn({
+        let most_queued = most_queued.clone();
+        async move {
+            loop {
+                most_queued.fetch_max(progress.queued(), Ordering::Relaxed);
+                tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+            }
+        }
+    });
+    let findings = common::crawl(crawler).await;
+    sampler.abort();
+
+    let mut requests: HashMap<String, usize> = HashMap::new();
+    for request in server.received_requests().await.unwrap() {
+        *requests.entry(request.url.path().to_string()).or_default() += 1;
+    }
+    // `/page/0` is `/` under another name, and is missing
+    assert_eq!(requests.len(), PAGES + 1);
+    assert!(requests.values().all(|&count| count == 1), "{:?}", requests);
+    assert!(most_queued.load(Ordering::Relaxed) <= PAGES);
+
+    let mut broken = common::broken_urls(&findings);
+    broken.sort();
+    broken.dedup();
+    assert_eq!(broken.len(), PAGES / 50);
+}
+
+#[tokio::test]
+async fn requests_each_page_of_an_interlinked_site_once() {
+    crawl_interlinked_site(1).await;
+}
+
+#[tokio::test]
+async fn concurrent_requests_never_fetch_a_page_twice() {
+    crawl_interlinked_site(8).await;
+}
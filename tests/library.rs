@@ -0,0 +1,46 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use std::time::Duration;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn returns_the_broken_links_as_saved() {
+    let server = MockServer::start().await;
+    let pages = [
+        ("/", r#"<a href="/a">a</a> <a href="/gone">gone</a>"#),
+        ("/a", r#"<a href="/gone">gone</a>"#),
+    ];
+    for (route, body) in pages {
+        Mock::given(path(route))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/html"))
+            .mount(&server)
+            .await;
+    }
+    let root = common::root_url(&server);
+    let page = |route| root.join(route).unwrap().to_string();
+
+    let errors = Crawler::builder(root.clone())
+        .concurrency(1)
+        .request_timeout(Some(Duration::from_secs(5)))
+        .build()
+        .broken_links()
+        .await
+        .unwrap();
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.url, page("/gone"));
+    assert_eq!(error.status, Some(404));
+    let mut sources: Vec<_> = error.source_urls().collect();
+    sources.sort();
+    assert_eq!(sources, [page("/"), page("/a")]);
+}
+
+#[tokio::test]
+async fn returns_crawl_errors() {
+    let root = Url::parse("http://127.0.0.1/").unwrap();
+    let result = Crawler::builder(root).build().broken_links().await;
+    assert!(result.is_err());
+}
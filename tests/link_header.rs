@@ -0,0 +1,85 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::links::link_header_targets;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn parses_rel_targets_from_link_headers() {
+    let cases: &[(&[&str], &[&str])] = &[
+        // (header values, targets)
+        (&[], &[]),
+        (&["</page/2>; rel=\"next\""], &["/page/2"]),
+        (
+            &["<https://api.example.com/items?page=1>; rel=\"prev\", <https://api.example.com/items?page=3>; rel=\"next\""],
+            &[
+                "https://api.example.com/items?page=1",
+                "https://api.example.com/items?page=3",
+            ],
+        ),
+        (&["</a,b>; rel=\"NEXT last\""], &["/a,b"]),
+        (&["</last>; rel=\"last\"", "</style.css>; rel=preload"], &[]),
+        (&["</p/2>; title=\"a, b\"; rel=next"], &["/p/2"]),
+        (&["</unterminated; rel=next"], &[]),
+    ];
+    for (values, expected) in cases {
+        assert_eq!(
+            link_header_targets(values.iter().copied(), &["next", "prev"]),
+            *expected,
+            "{:?}",
+            values
+        );
+    }
+}
+
+/// `/items` -> `/items/2` -> `/items/3` (missing), linked only through `Link` headers.
+async fn mount_paginated_items(server: &MockServer) {
+    Mock::given(path("/items"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Link", "</items/2>; rel=\"next\"")
+                .set_body_raw("[]", "application/json"),
+        )
+        .mount(server)
+        .await;
+    Mock::given(path("/items/2"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Link", "</items>; rel=\"prev\", </items/3>; rel=\"next\"")
+                .set_body_raw("[]", "application/json"),
+        )
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn link_header_is_ignored_by_default() {
+    let server = MockServer::start().await;
+    mount_paginated_items(&server).await;
+    let root = common::root_url(&server).join("/items").unwrap();
+
+    let findings = common::crawl(Crawler::builder(root).build()).await;
+
+    assert!(common::broken_urls(&findings).is_empty());
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn follows_next_and_prev_link_header_targets() {
+    let server = MockServer::start().await;
+    mount_paginated_items(&server).await;
+    let root = common::root_url(&server).join("/items").unwrap();
+
+    let crawler = Crawler::builder(root.clone())
+        .follow_link_header(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/items/3").unwrap().to_string()]
+    );
+    // /items is reached again through rel="prev" but only fetched once
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
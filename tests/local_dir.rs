@@ -0,0 +1,70 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::local_dir::file_for_url;
+use std::fs;
+use std::path::PathBuf;
+use url::Url;
+
+/// A small built site in a fresh temporary directory.
+fn write_site(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fbl-local-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("docs")).unwrap();
+    fs::create_dir_all(dir.join("assets")).unwrap();
+    fs::write(
+        dir.join("index.html"),
+        r#"<a href="docs/">Docs</a> <a href="/missing.html">Gone</a>"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("docs/index.html"),
+        r#"<a href="setup.html">Setup</a> <a href="../assets/logo.png">Logo</a>"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("docs/setup.html"),
+        r#"<a href="next.html">Next</a>"#,
+    )
+    .unwrap();
+    fs::write(dir.join("assets/logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+    dir
+}
+
+#[test]
+fn maps_url_paths_to_files() {
+    let dir = write_site("map");
+    let root = Url::parse("https://example.com/").unwrap();
+    let file = |path: &str| file_for_url(&dir, &root.join(path).unwrap());
+
+    assert_eq!(file("/"), Some(dir.join("index.html")));
+    assert_eq!(file("/docs/"), Some(dir.join("docs/index.html")));
+    assert_eq!(file("/docs"), Some(dir.join("docs/index.html")));
+    assert_eq!(file("/docs/setup.html"), Some(dir.join("docs/setup.html")));
+    assert_eq!(file("/my%20file.pdf"), Some(dir.join("my file.pdf")));
+    // Dot segments are resolved by the URL parser and can't climb out of the directory
+    assert_eq!(file("/../etc/passwd"), Some(dir.join("etc/passwd")));
+    assert_eq!(file("/docs/%2E%2E%2F%2E%2E%2Fetc"), None);
+    assert_eq!(file("/%2Fetc%2Fpasswd"), None);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn reports_links_to_missing_files() {
+    let dir = write_site("crawl");
+    let root = Url::parse("http://localhost/").unwrap();
+
+    let crawler = Crawler::builder(root).local_dir(Some(dir.clone())).build();
+    let mut broken = common::broken_urls(&common::crawl(crawler).await);
+    broken.sort();
+
+    // `setup.html` and `next.html` resolve against the page's directory, not the root
+    assert_eq!(
+        broken,
+        [
+            "http://localhost/docs/next.html",
+            "http://localhost/missing.html"
+        ]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
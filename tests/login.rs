@@ -0,0 +1,87 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::login::{find_input_value, LoginForm};
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn finds_hidden_input_by_name() {
+    let html = r#"<form>
+        <input type="hidden" name="other" value="nope">
+        <input type="hidden" name="csrf_token" value="abc123">
+        <input type="hidden" name="empty">
+    </form>"#;
+    assert_eq!(
+        find_input_value(html, "csrf_token").as_deref(),
+        Some("abc123")
+    );
+    assert_eq!(find_input_value(html, "empty").as_deref(), Some(""));
+    assert_eq!(find_input_value(html, "missing"), None);
+}
+
+#[tokio::test]
+async fn crawls_with_the_session_from_a_login_form() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/login"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<form method="post"><input type="hidden" name="csrf_token" value="abc123"></form>"#,
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/login"))
+        .and(body_string_contains("user=me"))
+        .and(body_string_contains("csrf_token=abc123"))
+        .respond_with(
+            ResponseTemplate::new(200).insert_header("Set-Cookie", "session=s3cret; Path=/"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    // Only a logged-in client sees the page with the broken link on it
+    Mock::given(path("/"))
+        .and(header("cookie", "session=s3cret"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/missing\">x</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+
+    let root = common::root_url(&server);
+    let client = reqwest::Client::builder()
+        .cookie_store(true)
+        .build()
+        .unwrap();
+    let form = LoginForm {
+        url: root.join("/login").unwrap(),
+        fields: vec![("user".to_string(), "me".to_string())],
+        csrf_field: Some("csrf_token".to_string()),
+    };
+    form.submit(&client).await.unwrap();
+
+    let findings = common::crawl(Crawler::builder(root.clone()).client(client).build()).await;
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/missing").unwrap().to_string()]
+    );
+}
+
+#[tokio::test]
+async fn failed_login_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/login"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&server)
+        .await;
+
+    let form = LoginForm {
+        url: common::root_url(&server).join("/login").unwrap(),
+        fields: vec![("user".to_string(), "me".to_string())],
+        csrf_field: None,
+    };
+    assert!(form.submit(&reqwest::Client::new()).await.is_err());
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn server_with_broken_links(count: usize) -> MockServer {
+    let server = MockServer::start().await;
+    let links: String = (0..count)
+        .map(|i| format!("<a href=\"/missing/{}\">{}</a>", i, i))
+        .collect();
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn stops_after_max_errors_broken_links() {
+    let server = server_with_broken_links(10).await;
+    let crawler = Crawler::builder(common::root_url(&server))
+        .max_errors(Some(3))
+        .build();
+    let progress = crawler.progress();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(common::broken_urls(&findings).len(), 3);
+    // Root page plus the three broken links, nothing after the cap
+    assert_eq!(server.received_requests().await.unwrap().len(), 4);
+    assert!(progress.stopped_at_max_errors());
+}
+
+#[tokio::test]
+async fn reaching_the_cap_on_the_last_link_is_not_stopping_early() {
+    let server = server_with_broken_links(3).await;
+    let crawler = Crawler::builder(common::root_url(&server))
+        .max_errors(Some(3))
+        .build();
+    let progress = crawler.progress();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(common::broken_urls(&findings).len(), 3);
+    assert!(!progress.stopped_at_max_errors());
+}
+
+#[tokio::test]
+async fn cap_holds_with_requests_in_flight() {
+    let server = server_with_broken_links(30).await;
+    let crawler = Crawler::builder(common::root_url(&server))
+        .auto_concurrency(Some(8))
+        .max_errors(Some(5))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(common::broken_urls(&findings).len(), 5);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Every page links to two more, so the site never runs out of pages.
+struct EndlessSite;
+
+impl Respond for EndlessSite {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let path = request.url.path().trim_end_matches('/');
+        let links = format!("<a href=\"{0}/a/\">a</a><a href=\"{0}/b/\">b</a>", path);
+        ResponseTemplate::new(200).set_body_raw(links, "text/html")
+    }
+}
+
+async fn crawl_endless_site(
+    max_pages: u64,
+    concurrency: usize,
+    breadth_first: bool,
+) -> Vec<String> {
+    let server = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(EndlessSite)
+        .mount(&server)
+        .await;
+    let crawler = Crawler::builder(common::root_url(&server))
+        .concurrency(concurrency)
+        .max_pages(Some(max_pages))
+        .breadth_first(breadth_first)
+        .build();
+    common::crawl(crawler).await;
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| request.url.path().to_string())
+        .collect()
+}
+
+async fn requests_with_max_pages(max_pages: u64, concurrency: usize) -> usize {
+    crawl_endless_site(max_pages, concurrency, false)
+        .await
+        .len()
+}
+
+/// Links followed from the root URL to get to `path`
+fn depth(path: &str) -> usize {
+    path.matches('/').count() - 1
+}
+
+#[tokio::test]
+async fn stops_after_max_pages_requests() {
+    assert_eq!(requests_with_max_pages(5, 1).await, 5);
+}
+
+#[tokio::test]
+async fn concurrent_requests_do_not_overshoot_the_budget() {
+    assert_eq!(requests_with_max_pages(20, 8).await, 20);
+}
+
+#[tokio::test]
+async fn breadth_first_spends_the_budget_on_shallow_pages() {
+    // The root, its two pages and their four
+    let paths = crawl_endless_site(7, 1, true).await;
+    assert_eq!(paths.len(), 7);
+    assert_eq!(paths.iter().map(|path| depth(path)).max(), Some(2));
+    assert_eq!(paths[..3], ["/", "/a/", "/b/"]);
+
+    // Depth-first dives down one branch instead
+    let paths = crawl_endless_site(7, 1, false).await;
+    assert_eq!(paths.iter().map(|path| depth(path)).max(), Some(6));
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use std::net::TcpListener;
+
+/// (URL, error) of every broken link.
+fn broken_with_errors(findings: &[Finding]) -> Vec<(String, Option<String>)> {
+    findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, error, .. } => Some((url.clone(), error.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn reports_a_dead_domain_and_keeps_crawling() {
+    let server = common::site(vec![(
+        "/",
+        common::page(
+            r#"<a href="http://does-not-exist.invalid/">gone</a>
+                   <a href="/missing">missing</a>"#,
+        ),
+    )])
+    .await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone()).check_external(true).build();
+    let mut broken = broken_with_errors(&common::crawl(crawler).await);
+    broken.sort();
+
+    assert_eq!(
+        broken,
+        [
+            (
+                "http://does-not-exist.invalid/".to_string(),
+                Some("no response: dns lookup failed for does-not-exist.invalid".to_string())
+            ),
+            (root.join("/missing").unwrap().to_string(), None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reports_a_refused_connection_and_keeps_crawling() {
+    // A port nothing listens on once the listener is dropped
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let refused = format!("http://localhost:{}/", port);
+    let server = common::site(vec![(
+        "/",
+        common::page(&format!(
+            r#"<a href="{}">down</a> <a href="/missing">missing</a>"#,
+            refused
+        )),
+    )])
+    .await;
+    let root = common::root_url(&server);
+
+    let broken = broken_with_errors(&common::crawl(Crawler::builder(root.clone()).build()).await);
+
+    assert_eq!(broken.len(), 2, "{:?}", broken);
+    let refused_error = Some("no response: connection refused".to_string());
+    assert!(broken.contains(&(refused, refused_error)), "{:?}", broken);
+    assert!(broken.contains(&(root.join("/missing").unwrap().to_string(), None)));
+}
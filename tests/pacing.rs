@@ -0,0 +1,132 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::pacing::{HostPacer, SlowStart};
+use find_broken_links::scope::ScopeEntry;
+use std::time::{Duration, Instant};
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn slow_start_ramps_linearly_to_the_target() {
+    let slow_start = SlowStart {
+        initial_delay: Duration::from_secs(2),
+        warmup_requests: 4,
+    };
+    let gaps: Vec<Duration> = (0..7)
+        .map(|request| slow_start.delay_before(request, Duration::ZERO))
+        .collect();
+    let ms = Duration::from_millis;
+    assert_eq!(
+        gaps,
+        [ms(0), ms(2000), ms(1500), ms(1000), ms(500), ms(0), ms(0)]
+    );
+
+    // Ramps down to the target gap, not below it
+    let target = ms(400);
+    assert_eq!(slow_start.delay_before(1, target), ms(2000));
+    assert_eq!(slow_start.delay_before(3, target), ms(1200));
+    assert_eq!(slow_start.delay_before(5, target), target);
+}
+
+#[tokio::test]
+async fn first_requests_are_spaced_out() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<a href=\"/1\">1</a><a href=\"/2\">2</a><a href=\"/3\">3</a><a href=\"/4\">4</a>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .slow_start(Some(SlowStart {
+            initial_delay: Duration::from_millis(200),
+            warmup_requests: 2,
+        }))
+        .build();
+    let started = Instant::now();
+    common::crawl(crawler).await;
+
+    // Gaps of 200ms and 100ms, then full speed
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(300), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(1000), "{:?}", elapsed);
+}
+
+#[test]
+fn host_pacer_spaces_out_requests_to_each_host() {
+    let pacer = HostPacer::new(Duration::from_millis(100));
+    let now = Instant::now();
+    let ms = Duration::from_millis;
+    let starts: Vec<Duration> = (0..3)
+        .map(|_| pacer.reserve("example.com", now) - now)
+        .collect();
+    assert_eq!(starts, [ms(0), ms(100), ms(200)]);
+    // Other hosts keep their own schedule
+    assert_eq!(pacer.reserve("other.example", now), now);
+
+    // Like a Crawl-delay from robots.txt
+    pacer.set_delay("Slow.example", Duration::from_secs(2));
+    assert_eq!(pacer.delay_for("slow.example"), Duration::from_secs(2));
+    assert_eq!(pacer.delay_for("example.com"), ms(100));
+    pacer.reserve("slow.example", now);
+    assert_eq!(
+        pacer.reserve("slow.example", now) - now,
+        Duration::from_secs(2)
+    );
+
+    // A gap that has passed already doesn't hold anything up
+    let later = now + Duration::from_secs(10);
+    assert_eq!(pacer.reserve("example.com", later), later);
+}
+
+/// `/` on the site links to `/1` to `/4` on the site and on `other`.
+async fn two_hosts() -> (MockServer, MockServer) {
+    let (site, other) = (MockServer::start().await, MockServer::start().await);
+    let other_url = Url::parse(&other.uri()).unwrap();
+    let links: String = (1..=4)
+        .map(|n| {
+            let elsewhere = other_url.join(&n.to_string()).unwrap();
+            format!(
+                "<a href=\"/{}\">{}</a><a href=\"{}\">{}</a>",
+                n, n, elsewhere, n
+            )
+        })
+        .collect();
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .mount(&site)
+        .await;
+    (site, other)
+}
+
+#[tokio::test]
+async fn request_delay_only_holds_up_requests_to_the_same_host() {
+    let (site, other) = two_hosts().await;
+    let root = common::root_url(&site);
+    // `localhost` and `127.0.0.1` are different hosts
+    let scope: Vec<ScopeEntry> = [
+        format!("localhost:{}", root.port().unwrap()),
+        other.uri().trim_start_matches("http://").to_string(),
+    ]
+    .iter()
+    .map(|entry| entry.parse().unwrap())
+    .collect();
+    let crawler = Crawler::builder(root)
+        .scope(scope)
+        .concurrency(8)
+        .request_delay(Duration::from_millis(100))
+        .build();
+    let started = Instant::now();
+    common::crawl(crawler).await;
+
+    assert_eq!(other.received_requests().await.unwrap().len(), 4);
+    // Five requests to the site take 400ms, while the four to the other host go alongside;
+    // one schedule for both would take 800ms
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(400), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(700), "{:?}", elapsed);
+}
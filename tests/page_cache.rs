@@ -0,0 +1,145 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::page_cache::PageCache;
+use std::sync::Arc;
+use wiremock::matchers::{header, header_exists, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn cache_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("fbl-page-cache-{}-{}", name, std::process::id()))
+        .join("localhost.json")
+}
+
+async fn crawl_with_cache(site: &MockServer, path: &std::path::Path) -> Vec<String> {
+    let cache = Arc::new(PageCache::open(path));
+    let crawler = Crawler::builder(common::root_url(site))
+        .page_cache(Some(Arc::clone(&cache)))
+        .build();
+    let findings = common::crawl(crawler).await;
+    cache.save().unwrap();
+    common::broken_urls(&findings)
+}
+
+/// Requests for `/`, and whether each asked for the page only if it changed.
+async fn root_requests(site: &MockServer) -> Vec<bool> {
+    site.received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|request| request.url.path() == "/")
+        .map(|request| request.headers.contains_key("if-none-match"))
+        .collect()
+}
+
+#[tokio::test]
+async fn reuses_the_links_of_an_unchanged_page() {
+    let site = MockServer::start().await;
+    Mock::given(path("/"))
+        .and(header("if-none-match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .mount(&site)
+        .await;
+    Mock::given(path("/"))
+        .respond_with(
+            common::page(r#"<a href="/about">about</a><a href="/gone">gone</a>"#)
+                .insert_header("etag", "\"v1\""),
+        )
+        .mount(&site)
+        .await;
+    Mock::given(path("/about"))
+        .respond_with(common::page("about"))
+        .mount(&site)
+        .await;
+    let gone = common::root_url(&site).join("/gone").unwrap().to_string();
+    let path = cache_path("unchanged");
+
+    assert_eq!(crawl_with_cache(&site, &path).await, [gone.as_str()]);
+    assert_eq!(crawl_with_cache(&site, &path).await, [gone.as_str()]);
+
+    assert_eq!(root_requests(&site).await, [false, true]);
+    // The page is still cached after being revalidated
+    let root = common::root_url(&site).to_string();
+    let cached = PageCache::open(&path).get(&root).unwrap();
+    assert_eq!(cached.etag.as_deref(), Some("\"v1\""));
+    assert_eq!(cached.links.links.len(), 2);
+}
+
+#[tokio::test]
+async fn parses_a_changed_page_again() {
+    let site = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            common::page(r#"<a href="/gone">gone</a>"#)
+                .insert_header("last-modified", "Wed, 14 Oct 2026 07:28:00 GMT"),
+        )
+        .up_to_n_times(1)
+        .mount(&site)
+        .await;
+    Mock::given(path("/"))
+        // `header` would split the date at its comma
+        .and(header_exists("if-modified-since"))
+        .respond_with(common::page(r#"<a href="/also-gone">gone</a>"#))
+        .mount(&site)
+        .await;
+    let root = common::root_url(&site);
+    let path = cache_path("changed");
+
+    assert_eq!(
+        crawl_with_cache(&site, &path).await,
+        [root.join("/gone").unwrap().to_string()]
+    );
+    assert_eq!(
+        crawl_with_cache(&site, &path).await,
+        [root.join("/also-gone").unwrap().to_string()]
+    );
+    // Without validators this time, the page isn't cached anymore
+    assert_eq!(PageCache::open(&path).get(root.as_str()), None);
+}
+
+#[tokio::test]
+async fn revalidates_a_page_reached_through_another_form_of_its_url() {
+    let site = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(r#"<a href="/page#top">page</a>"#))
+        .up_to_n_times(1)
+        .mount(&site)
+        .await;
+    // Same page: the empty query and the fragment are left out of its dedup key
+    Mock::given(path("/"))
+        .respond_with(common::page(r#"<a href="/page?">page</a>"#))
+        .mount(&site)
+        .await;
+    Mock::given(path("/page"))
+        .and(header("if-none-match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .mount(&site)
+        .await;
+    Mock::given(path("/page"))
+        .respond_with(common::page(r#"<a href="/gone">gone</a>"#).insert_header("etag", "\"v1\""))
+        .mount(&site)
+        .await;
+    let gone = common::root_url(&site).join("/gone").unwrap().to_string();
+    let path = cache_path("equivalent");
+
+    assert_eq!(crawl_with_cache(&site, &path).await, [gone.as_str()]);
+    assert_eq!(crawl_with_cache(&site, &path).await, [gone.as_str()]);
+
+    let pages: Vec<_> = site
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|request| request.url.path() == "/page")
+        .map(|request| {
+            (
+                request.url.query().map(str::to_string),
+                request.headers.contains_key("if-none-match"),
+            )
+        })
+        .collect();
+    assert_eq!(pages, [(None, false), (Some(String::new()), true)]);
+}
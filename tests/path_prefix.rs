@@ -0,0 +1,98 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::skipped::SkipReason;
+use wiremock::MockServer;
+
+/// `/docs/` links to pages under `/docs` (one of them broken), to `/docsearch` and `/blog/post`
+/// outside it, and loads `/static/logo.png`. `/blog/post` links to `/blog/gone`.
+async fn docs_site() -> MockServer {
+    common::site(vec![
+        (
+            "/docs/",
+            common::page(concat!(
+                r#"<a href="/docs/intro">intro</a><a href="/docs/gone">gone</a>"#,
+                r#"<a href="/docsearch">search</a><a href="/blog/post">blog</a>"#,
+                r#"<img src="/static/logo.png">"#,
+            )),
+        ),
+        ("/docs/intro", common::page("intro")),
+        ("/docsearch", common::page("search")),
+        ("/blog/post", common::page(r#"<a href="/blog/gone">x</a>"#)),
+        ("/static/logo.png", common::page("")),
+    ])
+    .await
+}
+
+#[tokio::test]
+async fn only_follows_links_under_the_path_prefix() {
+    let site = docs_site().await;
+    let root = common::root_url(&site).join("/docs/").unwrap();
+
+    let crawler = Crawler::builder(root.clone())
+        .path_prefix(Some("/docs".to_string()))
+        .report_skipped(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/docs/gone").unwrap().to_string()]
+    );
+    // The logo is outside the prefix, but checked like every resource
+    assert_eq!(
+        common::requested_paths(&site).await,
+        [
+            "GET /docs/",
+            "GET /docs/gone",
+            "GET /docs/intro",
+            "HEAD /static/logo.png"
+        ]
+    );
+    let mut outside: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Skipped(skipped) if skipped.reason == SkipReason::OutsidePathPrefix => {
+                Some(skipped.url.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    outside.sort();
+    assert_eq!(
+        outside,
+        [
+            root.join("/blog/post").unwrap().to_string(),
+            root.join("/docsearch").unwrap().to_string()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn checks_links_outside_the_path_prefix_without_following_them() {
+    let site = docs_site().await;
+    let root = common::root_url(&site).join("/docs/").unwrap();
+
+    let crawler = Crawler::builder(root.clone())
+        .path_prefix(Some("docs/".to_string()))
+        .check_out_of_scope(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/docs/gone").unwrap().to_string()]
+    );
+    // `/blog/gone` is never requested, since `/blog/post` is only checked
+    assert_eq!(
+        common::requested_paths(&site).await,
+        [
+            "GET /docs/",
+            "GET /docs/gone",
+            "GET /docs/intro",
+            "HEAD /blog/post",
+            "HEAD /docsearch",
+            "HEAD /static/logo.png"
+        ]
+    );
+}
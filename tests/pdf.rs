@@ -0,0 +1,108 @@
+#![cfg(feature = "pdf")]
+
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::pdf::find_pdf_links;
+use lopdf::{dictionary, Document, Object};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Builds a one-page PDF with a URI link annotation for each of `uris`.
+fn pdf_with_links(uris: &[&str]) -> Vec<u8> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let annotations: Vec<Object> = uris
+        .iter()
+        .map(|uri| {
+            doc.add_object(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Link",
+                "Rect" => vec![0.into(), 0.into(), 100.into(), 20.into()],
+                "A" => dictionary! {
+                    "S" => "URI",
+                    "URI" => Object::string_literal(*uri),
+                },
+            })
+            .into()
+        })
+        .collect();
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        "Annots" => annotations,
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn extracts_uri_link_annotations() {
+    let pdf = pdf_with_links(&["https://example.com/a", "/relative/b"]);
+    assert_eq!(
+        find_pdf_links(&pdf).unwrap(),
+        ["https://example.com/a", "/relative/b"]
+    );
+}
+
+#[test]
+fn rejects_non_pdf_input() {
+    assert!(find_pdf_links(b"<html></html>").is_err());
+}
+
+#[tokio::test]
+async fn reports_broken_pdf_links_with_the_pdf_as_referrer() {
+    let server = MockServer::start().await;
+    let root = common::root_url(&server);
+    let pdf_url = root.join("/guide.pdf").unwrap();
+    let missing_url = root.join("/missing").unwrap();
+
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<a href=\"/guide.pdf\">Guide</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/guide.pdf"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(pdf_with_links(&[missing_url.as_str()]), "application/pdf"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/missing"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(root).check_pdf_links(true).build();
+    let broken: Vec<_> = common::crawl(crawler)
+        .await
+        .into_iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, referrer, .. } => Some((url, referrer)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        broken,
+        [(missing_url.to_string(), Some(pdf_url.to_string()))]
+    );
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::debug_channel::ChannelStats;
+use find_broken_links::progress::{Periodic, ProgressLog};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn progress_log_lines_reflect_the_crawl() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<a href=\"/a\">a</a><a href=\"/gone\">x</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/a"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server)).build();
+    let progress = crawler.progress();
+    let mut out = Vec::new();
+    let mut progress_log = ProgressLog::new(&mut out, progress.clone());
+    assert!(!progress_log.due(Duration::from_secs(60), Some(3)));
+
+    common::crawl(crawler).await;
+    assert!(progress_log.due(Duration::from_secs(60), Some(3)));
+    progress_log.write_line().unwrap();
+    assert!(!progress_log.due(Duration::from_secs(60), Some(3)));
+
+    let line = String::from_utf8(out).unwrap();
+    assert!(
+        line.contains(" pages=3 queued=0 broken=1 rate="),
+        "{}",
+        line
+    );
+    assert!(line.ends_with("/s\n"), "{}", line);
+}
+
+#[tokio::test]
+async fn summary_counts_pages_links_and_broken_statuses() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<a href="/a">a</a> <a href="/gone">b</a> <a href="/down">c</a> <a href="/a">d</a>"#,
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/a"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(r#"<a href="/gone">x</a>"#, "text/html"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/down"))
+        .respond_with(ResponseTemplate::new(410))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server)).build();
+    let progress = crawler.progress();
+    common::crawl(crawler).await;
+
+    let channel = ChannelStats {
+        current_usage: 0,
+        max_usage: 4,
+        total_sent: 9,
+    };
+    let summary = progress.summary(Duration::from_millis(1500), channel);
+    assert_eq!(summary.pages_crawled, 4);
+    assert_eq!(summary.links_found, 3);
+    assert_eq!(
+        summary.broken_by_status,
+        BTreeMap::from([(404, 1), (410, 1)])
+    );
+    assert_eq!(
+        summary.to_string(),
+        "pages=4 links=3 broken=2 (404: 1, 410: 1) elapsed=1.5s max_channel_buffer=4 \
+         findings_sent=9"
+    );
+}
+
+#[test]
+fn periodic_is_due_after_either_limit() {
+    let mut by_pages = Periodic::new(None, Some(10));
+    assert!(!by_pages.due(9));
+    assert!(by_pages.due(10));
+    by_pages.done(10);
+    assert!(!by_pages.due(19));
+    assert!(by_pages.due(20));
+
+    let mut by_time = Periodic::new(Some(Duration::ZERO), None);
+    assert!(by_time.due(0));
+    by_time.done(0);
+    assert!(by_time.due(0));
+    assert!(!Periodic::new(Some(Duration::from_secs(60)), None).due(1_000));
+
+    assert!(!Periodic::new(None, None).due(u64::MAX));
+}
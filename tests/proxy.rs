@@ -0,0 +1,55 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use url::Url;
+use wiremock::matchers::{header, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn sends_every_request_through_the_proxy() {
+    // Stands in for the proxy, answering for a site that doesn't resolve
+    let proxy = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(r#"<a href="/gone">x</a>"#, "text/html"),
+        )
+        .mount(&proxy)
+        .await;
+    let root = Url::parse("http://site.invalid/").unwrap();
+    let proxy_url = Url::parse(&proxy.uri()).unwrap();
+
+    let crawler = Crawler::builder(root.clone())
+        .proxy(Some(reqwest::Proxy::all(proxy_url.as_str()).unwrap()))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/gone").unwrap().to_string()]
+    );
+    let requests = proxy.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests
+        .iter()
+        .all(|request| request.headers["host"] == "site.invalid"));
+}
+
+#[tokio::test]
+async fn sends_the_credentials_in_the_proxy_url() {
+    let proxy = MockServer::start().await;
+    // base64 of "user:secret"
+    Mock::given(header("proxy-authorization", "Basic dXNlcjpzZWNyZXQ="))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("no links", "text/html"))
+        .mount(&proxy)
+        .await;
+    let mut proxy_url = Url::parse(&proxy.uri()).unwrap();
+    proxy_url.set_username("user").unwrap();
+    proxy_url.set_password(Some("secret")).unwrap();
+
+    let crawler = Crawler::builder(Url::parse("http://site.invalid/").unwrap())
+        .proxy(Some(reqwest::Proxy::all(proxy_url.as_str()).unwrap()))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert!(common::broken_urls(&findings).is_empty());
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::links::LinkKind;
+use find_broken_links::recheck::{
+    load_report, recheck, report_path, RecheckReport, RecheckedLink, StatusFilter,
+};
+use find_broken_links::report::{group_by_referrer, NotFoundError};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn broken(url: &str, source_url: &str, status: Option<u16>) -> NotFoundError {
+    NotFoundError {
+        url: url.to_string(),
+        title: None,
+        source_url: Some(source_url.to_string()),
+        suggestion: None,
+        kind: LinkKind::Link,
+        error: None,
+        class: None,
+        status,
+        redirected_to: None,
+        also_linked_from: Vec::new(),
+        soft_404: false,
+    }
+}
+
+#[test]
+fn parses_statuses_and_classes() {
+    assert_eq!("404".parse(), Ok(StatusFilter::Status(404)));
+    assert_eq!("4xx".parse(), Ok(StatusFilter::Class(4)));
+    assert_eq!("5XX".parse(), Ok(StatusFilter::Class(5)));
+    for invalid in ["", "xx", "44xx", "9xx", "4x", "99", "1000", "abc"] {
+        assert!(invalid.parse::<StatusFilter>().is_err(), "{}", invalid);
+    }
+
+    assert!(StatusFilter::Class(4).matches(410));
+    assert!(!StatusFilter::Class(4).matches(500));
+    assert!(!StatusFilter::Status(404).matches(410));
+}
+
+#[test]
+fn reads_both_report_shapes() {
+    let errors = vec![
+        broken(
+            "https://example.com/gone",
+            "https://example.com/",
+            Some(404),
+        ),
+        broken(
+            "https://example.com/gone",
+            "https://example.com/a",
+            Some(404),
+        ),
+    ];
+    let by_url = serde_json::to_string(&errors).unwrap();
+    let by_referrer = serde_json::to_string(&group_by_referrer(&errors)).unwrap();
+
+    assert_eq!(load_report(&by_url).unwrap(), errors);
+    assert_eq!(load_report(&by_referrer).unwrap(), errors);
+    assert!(load_report(r#"{"total": 3}"#).is_err());
+}
+
+#[test]
+fn older_reports_without_statuses_hold_404s_and_body_errors() {
+    let errors = load_report(
+        r#"[{"url": "https://example.com/gone", "title": null},
+            {"url": "https://example.com/api", "title": null, "error": "not found"}]"#,
+    )
+    .unwrap();
+    let statuses: Vec<_> = errors.iter().map(NotFoundError::response_status).collect();
+    assert_eq!(statuses, [404, 200]);
+}
+
+#[test]
+fn writes_the_report_next_to_the_previous_one() {
+    assert_eq!(
+        report_path(Path::new("results/example.com.json")),
+        Path::new("results/example.com-recheck.json")
+    );
+}
+
+#[tokio::test]
+async fn reports_which_links_are_fixed() {
+    let server = MockServer::start().await;
+    Mock::given(path("/fixed"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/api"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "error": "not found" })))
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+    let url = |route: &str| root.join(route).unwrap().to_string();
+    let page = url("/");
+
+    // `/fixed` is on two pages, but only requested once
+    let errors = [
+        broken(&url("/fixed"), &page, Some(404)),
+        broken(&url("/fixed"), &url("/a"), Some(404)),
+        broken(&url("/still-gone"), &page, Some(404)),
+        NotFoundError {
+            error: Some("not found".to_string()),
+            ..broken(&url("/api"), &page, Some(200))
+        },
+    ];
+    let crawler = Crawler::builder(root.clone())
+        .body_error_paths(Some(vec!["$.error".parse().unwrap()]))
+        .build();
+    let report = RecheckReport::new(recheck(Arc::new(crawler), &errors, 2).await);
+
+    let link =
+        |route: &str, source: &str, previous_status, status, error: Option<&str>| RecheckedLink {
+            url: url(route),
+            source_url: Some(source.to_string()),
+            previous_status,
+            status,
+            error: error.map(str::to_string),
+        };
+    assert_eq!(
+        report,
+        RecheckReport {
+            fixed: vec![
+                link("/fixed", &page, 404, Some(200), None),
+                link("/fixed", &url("/a"), 404, Some(200), None),
+            ],
+            still_broken: vec![
+                link("/still-gone", &page, 404, Some(404), None),
+                link("/api", &page, 200, Some(200), Some("not found")),
+            ],
+        }
+    );
+}
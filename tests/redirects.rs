@@ -0,0 +1,201 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::links::resolve_redirect;
+use find_broken_links::warnings::WarningCategory;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn redirect(location: &str) -> ResponseTemplate {
+    ResponseTemplate::new(301).insert_header("Location", location)
+}
+
+/// `/` links to `/old`, `/loop` and `/moved`: `/old` redirects to a 404, `/loop` to `/loop/b`
+/// and back, and `/moved` to a page that loads through a relative `Location` with a query.
+async fn redirecting_site() -> MockServer {
+    let server = MockServer::start().await;
+    let links = r#"<a href="/old">a</a> <a href="/loop">b</a> <a href="/moved#top">c</a>"#;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/old"))
+        .respond_with(redirect("/gone"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/loop"))
+        .respond_with(redirect("/loop/b"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/loop/b"))
+        .respond_with(redirect("/loop"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/moved"))
+        .respond_with(redirect("new?from=moved"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/new"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[test]
+fn resolves_location_against_the_request_url() {
+    let request = Url::parse("https://example.com/docs/guide/page?lang=en").unwrap();
+    let cases = [
+        // (Location, final URL)
+        (
+            "other?lang=fr",
+            "https://example.com/docs/guide/other?lang=fr",
+        ),
+        (
+            "../intro?v=2#setup",
+            "https://example.com/docs/intro?v=2#setup",
+        ),
+        ("/moved?id=7", "https://example.com/moved?id=7"),
+        ("/moved", "https://example.com/moved"),
+        ("?lang=de", "https://example.com/docs/guide/page?lang=de"),
+        (
+            "#section",
+            "https://example.com/docs/guide/page?lang=en#section",
+        ),
+        ("//cdn.example.com/page", "https://cdn.example.com/page"),
+        (" https://other.example/x ", "https://other.example/x"),
+    ];
+    for (location, expected) in cases {
+        assert_eq!(
+            resolve_redirect(&request, location).unwrap().as_str(),
+            expected,
+            "{}",
+            location
+        );
+    }
+}
+
+#[test]
+fn inherits_the_request_fragment_unless_location_has_one() {
+    let request = Url::parse("https://example.com/old#faq").unwrap();
+    assert_eq!(
+        resolve_redirect(&request, "/new?x=1").unwrap().as_str(),
+        "https://example.com/new?x=1#faq"
+    );
+    assert_eq!(
+        resolve_redirect(&request, "/new#top").unwrap().as_str(),
+        "https://example.com/new#top"
+    );
+}
+
+#[test]
+fn rejects_unparseable_locations() {
+    let request = Url::parse("https://example.com/").unwrap();
+    assert!(resolve_redirect(&request, "http://[broken").is_err());
+}
+
+#[tokio::test]
+async fn reports_broken_links_with_where_they_redirected_to() {
+    let server = redirecting_site().await;
+    let root = common::root_url(&server);
+    let url = |route: &str| root.join(route).unwrap().to_string();
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+
+    let mut broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound {
+                url,
+                status,
+                error,
+                redirected_to,
+                ..
+            } => Some((url.clone(), *status, error.clone(), redirected_to.clone())),
+            _ => None,
+        })
+        .collect();
+    broken.sort();
+    assert_eq!(
+        broken,
+        [
+            (
+                url("/loop"),
+                Some(0),
+                Some(format!(
+                    "broken redirect: redirect loop at {}",
+                    url("/loop")
+                )),
+                Some(url("/loop/b")),
+            ),
+            (url("/old"), Some(404), None, Some(url("/gone"))),
+        ]
+    );
+    // Working redirects aren't flagged unless asked
+    assert!(!findings
+        .iter()
+        .any(|finding| matches!(finding, Finding::Warning(_))));
+}
+
+#[tokio::test]
+async fn flag_redirects_warns_about_links_that_work_through_a_redirect() {
+    let server = redirecting_site().await;
+    let root = common::root_url(&server);
+    let url = |route: &str| root.join(route).unwrap().to_string();
+
+    let crawler = Crawler::builder(root.clone()).flag_redirects(true).build();
+    let findings = common::crawl(crawler).await;
+
+    let warnings: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Warning(warning) => Some(warning),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].category, WarningCategory::Redirect);
+    assert_eq!(warnings[0].url, url("/moved#top"));
+    // Resolved against `/moved`, with the fragment of the link
+    assert_eq!(
+        warnings[0].message,
+        format!(
+            "redirects to {}, linked from {}",
+            url("/new?from=moved#top"),
+            url("/")
+        )
+    );
+}
+
+#[tokio::test]
+async fn reports_a_redirect_without_a_location() {
+    let site = common::site(vec![
+        ("/", common::page(r#"<a href="/nowhere">nowhere</a>"#)),
+        ("/nowhere", ResponseTemplate::new(301)),
+    ])
+    .await;
+    let root = common::root_url(&site);
+
+    // Even with only_404, where other statuses on the site fail the crawl
+    let crawler = Crawler::builder(root.clone()).only_404(true).build();
+    let findings = common::crawl(crawler).await;
+
+    let broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound {
+                url, status, error, ..
+            } => Some((url.clone(), *status, error.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        broken,
+        [(
+            root.join("/nowhere").unwrap().to_string(),
+            Some(301),
+            Some("broken redirect: 301 that couldn't be followed".to_string()),
+        )]
+    );
+}
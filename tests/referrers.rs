@@ -0,0 +1,63 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn page(server: &MockServer, route: &str, body: &str) {
+    Mock::given(path(route))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body.to_string(), "text/html"))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn reports_every_page_with_a_broken_link() {
+    let server = MockServer::start().await;
+    // Pages are crawled last found first: `/a` while `/gone` is still queued, `/b` after it
+    page(
+        &server,
+        "/",
+        r#"<a href="/b">b</a> <a href="/gone">gone</a> <a href="/a">a</a>"#,
+    )
+    .await;
+    page(
+        &server,
+        "/a",
+        r#"<a href="/gone">x</a> <a href="/gone">y</a>"#,
+    )
+    .await;
+    page(&server, "/b", r#"<a href="/gone?">gone</a>"#).await;
+    Mock::given(path("/gone"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let root = common::root_url(&server);
+    let crawler = Crawler::builder(root.clone())
+        .url_normalizer(find_broken_links::normalize::UrlNormalizer::default().strip_query(true))
+        .concurrency(1)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let gone = root.join("/gone").unwrap().to_string();
+    let mut sources = Vec::new();
+    for finding in &findings {
+        match finding {
+            Finding::NotFound { url, referrer, .. } => {
+                assert_eq!(*url, gone);
+                sources.extend(referrer.clone());
+            }
+            Finding::AlsoLinkedFrom { url, referrer } => {
+                assert_eq!(*url, gone);
+                sources.push(referrer.clone());
+            }
+            _ => {}
+        }
+    }
+    sources.sort();
+    sources.dedup();
+    let page = |route| root.join(route).unwrap().to_string();
+    assert_eq!(sources, [page("/"), page("/a"), page("/b")]);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::links::make_absolute_url;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer};
+
+/// Crawls `/`, which links to `page`, and returns the paths requested besides those two.
+async fn paths_requested_from(page: &str, body: &str) -> Vec<String> {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(&format!(r#"<a href="{}">x</a>"#, page)))
+        .mount(&server)
+        .await;
+    Mock::given(path(page))
+        .respond_with(common::page(body))
+        .mount(&server)
+        .await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(common::page(""))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server)).build();
+    let findings = common::crawl(crawler).await;
+    assert_eq!(common::broken_urls(&findings), Vec::<String>::new());
+
+    let mut paths: Vec<_> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| request.url.path().to_string())
+        .filter(|requested| requested != "/" && requested != page)
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[tokio::test]
+async fn resolves_relative_links_against_the_page_they_are_on() {
+    let paths = paths_requested_from(
+        "/docs/guide/intro",
+        r#"<a href="setup">x</a><a href="../api/">x</a><a href="/top">x</a>"#,
+    )
+    .await;
+    assert_eq!(paths, ["/docs/api/", "/docs/guide/setup", "/top"]);
+}
+
+#[tokio::test]
+async fn resolves_relative_links_against_the_base_href() {
+    let paths = paths_requested_from(
+        "/docs/guide/intro",
+        r#"<head><base href="/app/"></head><a href="start">x</a><img src="logo.png">"#,
+    )
+    .await;
+    assert_eq!(paths, ["/app/logo.png", "/app/start"]);
+
+    // A base that can't have relative links resolved against it is ignored
+    let paths = paths_requested_from(
+        "/docs/intro",
+        r#"<base href="javascript:void(0)"><a href="setup">x</a>"#,
+    )
+    .await;
+    assert_eq!(paths, ["/docs/setup"]);
+}
+
+#[test]
+fn protocol_relative_links_keep_the_page_scheme() {
+    for scheme in ["http", "https"] {
+        let page = Url::parse(&format!("{}://example.com/docs/intro", scheme)).unwrap();
+        let resolved = make_absolute_url(&page, "//cdn.example.com/app.js?v=2").unwrap();
+        assert_eq!(
+            resolved.as_str(),
+            format!("{}://cdn.example.com/app.js?v=2", scheme)
+        );
+    }
+}
@@ -0,0 +1,473 @@
+use find_broken_links::crawler::{CheckedUrl, Finding};
+use find_broken_links::links::LinkKind;
+use find_broken_links::progress::CrawlSummary;
+use find_broken_links::recheck::load_report;
+use find_broken_links::report::{
+    copy_report, csv_report, group_by_referrer, save_json, text_report, url_report, BrokenLink,
+    CheckedCsvWriter, JsonlWriter, LycheeStats, NotFoundError, ReferrerReport, Report,
+    ReportHeader, ResultsDir, REPORT_VERSION,
+};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn broken(url: &str, source_url: Option<&str>) -> NotFoundError {
+    NotFoundError {
+        url: url.to_string(),
+        title: None,
+        source_url: source_url.map(str::to_string),
+        suggestion: None,
+        kind: LinkKind::Link,
+        error: None,
+        class: None,
+        status: None,
+        redirected_to: None,
+        also_linked_from: Vec::new(),
+        soft_404: false,
+    }
+}
+
+#[test]
+fn groups_broken_links_by_the_page_they_are_on() {
+    let errors = [
+        broken(
+            "https://example.com/b/missing",
+            Some("https://example.com/b"),
+        ),
+        broken("https://example.com/gone", Some("https://example.com/a")),
+        broken(
+            "https://example.com/also-gone",
+            Some("https://example.com/b"),
+        ),
+        broken("https://example.com/", None),
+    ];
+    let link = |url: &str| BrokenLink {
+        url: url.to_string(),
+        title: None,
+        suggestion: None,
+        kind: LinkKind::Link,
+        error: None,
+        class: None,
+        status: None,
+        redirected_to: None,
+        soft_404: false,
+    };
+
+    assert_eq!(
+        group_by_referrer(&errors),
+        [
+            ReferrerReport {
+                referrer: None,
+                broken_links: vec![link("https://example.com/")],
+            },
+            ReferrerReport {
+                referrer: Some("https://example.com/a".to_string()),
+                broken_links: vec![link("https://example.com/gone")],
+            },
+            ReferrerReport {
+                referrer: Some("https://example.com/b".to_string()),
+                broken_links: vec![
+                    link("https://example.com/b/missing"),
+                    link("https://example.com/also-gone"),
+                ],
+            },
+        ]
+    );
+}
+
+#[test]
+fn only_writes_the_kind_of_links_that_are_not_plain_links() {
+    let htmx = NotFoundError {
+        kind: LinkKind::Htmx,
+        ..broken("https://example.com/fragment", Some("https://example.com/"))
+    };
+    let plain = broken("https://example.com/gone", None);
+
+    let json = serde_json::to_value([&htmx, &plain]).unwrap();
+    assert_eq!(json[0]["kind"], "htmx");
+    assert!(json[1].get("kind").is_none());
+    assert_eq!(
+        group_by_referrer(&[htmx])[0].broken_links[0].kind,
+        LinkKind::Htmx
+    );
+}
+
+#[test]
+fn a_link_on_several_pages_is_listed_for_each() {
+    let mut error = broken("https://example.com/gone", Some("https://example.com/a"));
+    for page in [
+        "https://example.com/b",
+        "https://example.com/a",
+        "https://example.com/b",
+    ] {
+        error.add_source(page.to_string());
+    }
+    assert_eq!(error.also_linked_from, ["https://example.com/b"]);
+
+    let referrers: Vec<_> = group_by_referrer(std::slice::from_ref(&error))
+        .into_iter()
+        .map(|page| (page.referrer.unwrap(), page.broken_links.len()))
+        .collect();
+    assert_eq!(
+        referrers,
+        [
+            ("https://example.com/a".to_string(), 1),
+            ("https://example.com/b".to_string(), 1)
+        ]
+    );
+    assert_eq!(
+        text_report(std::slice::from_ref(&error)),
+        "404 https://example.com/gone (from https://example.com/a)\n\
+         404 https://example.com/gone (from https://example.com/b)\n"
+    );
+    let stats = LycheeStats::from_broken_links("https://example.com/", &[error]);
+    assert_eq!(stats.errors, 2);
+    assert_eq!(stats.fail_map.len(), 2);
+}
+
+#[test]
+fn jsonl_report_has_a_line_per_finding_and_reads_back_merged() {
+    let findings = [
+        Finding::NotFound {
+            url: "https://example.com/gone".to_string(),
+            title: None,
+            referrer: Some("https://example.com/a".to_string()),
+            suggestion: None,
+            kind: LinkKind::Link,
+            error: None,
+            class: None,
+            status: Some(410),
+            redirected_to: None,
+            soft_404: false,
+        },
+        Finding::AlsoLinkedFrom {
+            url: "https://example.com/gone".to_string(),
+            referrer: "https://example.com/b".to_string(),
+        },
+    ];
+    let mut out = Vec::new();
+    let mut jsonl = JsonlWriter::new(&mut out);
+    for finding in findings {
+        jsonl.record(finding).unwrap();
+    }
+    jsonl.finish().unwrap();
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(
+        out,
+        "{\"url\":\"https://example.com/gone\",\"title\":null,\"source_url\":\"https://example.com/a\",\"status\":410}\n\
+         {\"url\":\"https://example.com/gone\",\"title\":null,\"source_url\":\"https://example.com/b\"}\n"
+    );
+    let mut expected = broken("https://example.com/gone", Some("https://example.com/a"));
+    expected.status = Some(410);
+    expected.add_source("https://example.com/b".to_string());
+    assert_eq!(load_report(&out).unwrap(), [expected]);
+}
+
+#[test]
+fn csv_report_round_trips_fields_that_need_quoting() {
+    let errors = [
+        NotFoundError {
+            title: Some("Not \"found\", sorry".to_string()),
+            status: Some(404),
+            ..broken("https://example.com/", None)
+        },
+        NotFoundError {
+            title: Some("Gone\nfor good".to_string()),
+            status: Some(410),
+            redirected_to: None,
+            also_linked_from: vec!["https://example.com/b?x=1,2".to_string()],
+            ..broken("https://example.com/a,b", Some("https://example.com/"))
+        },
+    ];
+
+    let csv = csv_report(&errors);
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(csv.as_bytes());
+    let rows: Vec<Vec<String>> = reader.deserialize().collect::<Result<_, _>>().unwrap();
+    assert_eq!(
+        rows,
+        [
+            ["url", "status", "title", "source_url"],
+            ["https://example.com/", "404", "Not \"found\", sorry", ""],
+            [
+                "https://example.com/a,b",
+                "410",
+                "Gone\nfor good",
+                "https://example.com/"
+            ],
+            [
+                "https://example.com/a,b",
+                "410",
+                "Gone\nfor good",
+                "https://example.com/b?x=1,2"
+            ],
+        ]
+    );
+}
+
+#[test]
+fn csv_ledger_quotes_fields_that_need_it() {
+    let checked = [
+        CheckedUrl {
+            url: "https://example.com/".to_string(),
+            status: Some(200),
+            elapsed: Duration::from_millis(42),
+            content_type: Some("text/html; charset=utf-8".to_string()),
+            referrer: None,
+            checked_at: UNIX_EPOCH,
+        },
+        CheckedUrl {
+            url: "https://example.com/a,\"b\"".to_string(),
+            status: None,
+            elapsed: Duration::from_millis(1500),
+            content_type: None,
+            referrer: Some("https://example.com/".to_string()),
+            checked_at: UNIX_EPOCH + Duration::from_secs(1),
+        },
+    ];
+    let mut out = Vec::new();
+    let mut csv = CheckedCsvWriter::new(&mut out).unwrap();
+    for row in &checked {
+        csv.record(row).unwrap();
+    }
+    csv.finish().unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "url,status,elapsed_ms,content_type,referrer,checked_at\n\
+         https://example.com/,200,42,text/html; charset=utf-8,,1970-01-01T00:00:00.000Z\n\
+         \"https://example.com/a,\"\"b\"\"\",,1500,,https://example.com/,1970-01-01T00:00:01.000Z\n"
+    );
+}
+
+#[test]
+fn lychee_report_matches_golden_file() {
+    let errors = [
+        broken("https://example.com/gone", Some("https://example.com/a")),
+        broken(
+            "https://example.com/b/missing",
+            Some("https://example.com/b"),
+        ),
+        broken(
+            "https://example.com/also-gone",
+            Some("https://example.com/a"),
+        ),
+    ];
+    let stats = LycheeStats {
+        total: 12,
+        successful: 7,
+        excludes: 2,
+        duration_secs: 3,
+        ..LycheeStats::from_broken_links("https://example.com/", &errors)
+    };
+
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/lychee.json");
+    let expected = fs::read_to_string(&golden).unwrap();
+    assert_eq!(
+        serde_json::to_string_pretty(&stats).unwrap(),
+        expected.trim_end()
+    );
+}
+
+#[test]
+fn lychee_report_lists_a_broken_root_under_itself() {
+    let stats = LycheeStats::from_broken_links(
+        "https://example.com/",
+        &[broken("https://example.com/", None)],
+    );
+    assert_eq!(stats.errors, 1);
+    assert_eq!(
+        stats.fail_map.keys().collect::<Vec<_>>(),
+        ["https://example.com/"]
+    );
+}
+
+#[test]
+fn lychee_report_uses_the_body_error_as_the_status_text() {
+    let error = NotFoundError {
+        error: Some("not found".to_string()),
+        ..broken("https://example.com/api/item", Some("https://example.com/"))
+    };
+    let stats =
+        LycheeStats::from_broken_links("https://example.com/", std::slice::from_ref(&error));
+    let status = &stats.fail_map["https://example.com/"][0].status;
+    assert_eq!((status.text.as_str(), status.code), ("not found", None));
+
+    let json = serde_json::to_value([&error, &broken("https://example.com/gone", None)]).unwrap();
+    assert_eq!(json[0]["error"], "not found");
+    assert!(json[1].get("error").is_none());
+}
+
+#[test]
+fn lychee_report_uses_the_response_status() {
+    let error = NotFoundError {
+        status: Some(500),
+        ..broken("https://example.com/crash", Some("https://example.com/"))
+    };
+    let stats =
+        LycheeStats::from_broken_links("https://example.com/", std::slice::from_ref(&error));
+    let status = &stats.fail_map["https://example.com/"][0].status;
+    assert_eq!(
+        (status.text.as_str(), status.code),
+        ("500 Internal Server Error", Some(500))
+    );
+}
+
+#[test]
+fn text_report_is_sorted_and_the_same_whatever_the_order_found() {
+    let errors = [
+        broken("https://example.com/b", Some("https://example.com/")),
+        NotFoundError {
+            error: Some("not found".to_string()),
+            status: Some(200),
+            ..broken("https://example.com/api", Some("https://example.com/"))
+        },
+        broken("https://example.com/a", Some("https://example.com/z")),
+        broken("https://example.com/", None),
+        broken("https://example.com/a", Some("https://example.com/b")),
+        broken("https://example.com/b", Some("https://example.com/")),
+    ];
+    let expected = "200 https://example.com/api (from https://example.com/)\n\
+                    404 https://example.com/\n\
+                    404 https://example.com/a (from https://example.com/b)\n\
+                    404 https://example.com/a (from https://example.com/z)\n\
+                    404 https://example.com/b (from https://example.com/)\n";
+    assert_eq!(text_report(&errors), expected);
+
+    let mut reversed = errors.to_vec();
+    reversed.reverse();
+    assert_eq!(text_report(&reversed), expected);
+    assert_eq!(text_report(&[]), "");
+}
+
+#[test]
+fn save_json_replaces_the_file_without_leaving_a_temporary_one() {
+    let dir = std::env::temp_dir().join(format!("fbl-save-json-{}", std::process::id()));
+    let path = dir.join("results").join("example.com.json");
+
+    save_json(&vec![broken("https://example.com/a", None)], &path).unwrap();
+    let snapshot = vec![
+        broken("https://example.com/a", None),
+        broken("https://example.com/b", Some("https://example.com/")),
+    ];
+    save_json(&snapshot, &path).unwrap();
+
+    let saved: Vec<NotFoundError> =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved, snapshot);
+    let files: Vec<_> = fs::read_dir(path.parent().unwrap())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(files, ["example.com.json"]);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn timestamped_results_keep_earlier_runs_and_a_latest_copy() {
+    let plain = ResultsDir::new("./results", "example.com", None);
+    assert_eq!(
+        plain.file("", "json"),
+        PathBuf::from("./results/example.com.json")
+    );
+    assert_eq!(plain.latest("json"), None);
+
+    let dir = std::env::temp_dir().join(format!("fbl-timestamped-{}", std::process::id()));
+    let started = UNIX_EPOCH + Duration::from_secs(1_760_443_200);
+    let results = ResultsDir::new(dir.join("nested/results"), "example.com", Some(started));
+    let report = results.file("", "json");
+    assert_eq!(
+        report,
+        dir.join("nested/results/example.com-1760443200.json")
+    );
+    assert_eq!(
+        results.file("-warnings", "json"),
+        dir.join("nested/results/example.com-1760443200-warnings.json")
+    );
+
+    let broken_links = vec![broken("https://example.com/a", None)];
+    save_json(&broken_links, &report).unwrap();
+    let latest = results.latest("json").unwrap();
+    assert_eq!(latest, dir.join("nested/results/example.com-latest.json"));
+    copy_report(&report, &latest).unwrap();
+    let copied = fs::read_to_string(&latest).unwrap();
+    assert_eq!(load_report(&copied).unwrap(), broken_links);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn url_report_merges_duplicate_urls_and_sorts_them() {
+    let errors = [
+        broken("https://example.com/z", Some("https://example.com/")),
+        broken("https://example.com/gone", Some("https://example.com/b")),
+        broken("https://example.com/gone", Some("https://example.com/a")),
+        broken("https://example.com/gone", Some("https://example.com/b")),
+    ];
+
+    let report = url_report(&errors, true);
+
+    let mut gone = broken("https://example.com/gone", Some("https://example.com/b"));
+    gone.also_linked_from = vec!["https://example.com/a".to_string()];
+    assert_eq!(report, [gone, errors[0].clone()]);
+}
+
+#[test]
+fn url_report_without_dedupe_has_an_entry_per_page() {
+    let mut gone = broken("https://example.com/gone", Some("https://example.com/b"));
+    gone.also_linked_from = vec!["https://example.com/a".to_string()];
+    let root = broken("https://example.com/", None);
+
+    let report = url_report(&[gone, root.clone()], false);
+
+    assert_eq!(
+        report,
+        [
+            root,
+            broken("https://example.com/gone", Some("https://example.com/a")),
+            broken("https://example.com/gone", Some("https://example.com/b")),
+        ]
+    );
+}
+
+#[test]
+fn wraps_the_broken_links_in_a_versioned_report() {
+    let summary = CrawlSummary {
+        pages_crawled: 12,
+        links_found: 40,
+        broken: 1,
+        broken_by_status: BTreeMap::from([(404, 1)]),
+        elapsed_ms: 1500,
+        max_channel_buffer: 3,
+        findings_sent: 14,
+    };
+    let started_at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let errors = [broken(
+        "https://example.com/gone",
+        Some("https://example.com/"),
+    )];
+    let report = Report {
+        header: ReportHeader::new("https://example.com/", started_at, summary),
+        broken_links: url_report(&errors, true),
+    };
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["version"], REPORT_VERSION);
+    assert_eq!(json["crawled_url"], "https://example.com/");
+    assert_eq!(json["timestamp"], "2023-11-14T22:13:20Z");
+    assert_eq!(json["summary"]["broken_by_status"]["404"], 1);
+    assert_eq!(json["broken_links"][0]["url"], "https://example.com/gone");
+
+    let saved = serde_json::to_string(&report).unwrap();
+    assert_eq!(load_report(&saved).unwrap(), errors);
+    let grouped = Report {
+        header: report.header.clone(),
+        broken_links: group_by_referrer(&errors),
+    };
+    assert_eq!(
+        load_report(&serde_json::to_string(&grouped).unwrap()).unwrap(),
+        errors
+    );
+}
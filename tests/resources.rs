@@ -0,0 +1,142 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::links::LinkKind;
+use find_broken_links::skipped::SkipReason;
+use std::collections::HashSet;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn checks_what_pages_load_without_crawling_it() {
+    let server = MockServer::start().await;
+    let page = r#"<link rel="stylesheet" href="/style.css">
+        <script src="/missing.js"></script>
+        <img src="/missing.png">
+        <iframe src="/embed"></iframe>
+        <iframe src="/about"></iframe>
+        <a href="/about">about</a>"#;
+    Mock::given(path("/"))
+        .respond_with(common::page(page))
+        .mount(&server)
+        .await;
+    Mock::given(path("/style.css"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    // An embedded page's links aren't followed
+    Mock::given(path("/embed"))
+        .respond_with(common::page(r#"<a href="/from-embed">x</a>"#))
+        .mount(&server)
+        .await;
+    // Linked as well as loaded, so it's crawled
+    Mock::given(path("/about"))
+        .respond_with(common::page(r#"<a href="/team">x</a>"#))
+        .mount(&server)
+        .await;
+    Mock::given(path("/team"))
+        .respond_with(common::page(""))
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+
+    let mut broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound {
+                url,
+                kind,
+                referrer,
+                ..
+            } => Some((url.clone(), *kind, referrer.clone())),
+            _ => None,
+        })
+        .collect();
+    broken.sort();
+    let url = |route: &str| root.join(route).unwrap().to_string();
+    assert_eq!(
+        broken,
+        [
+            (url("/missing.js"), LinkKind::Resource, Some(url("/"))),
+            (url("/missing.png"), LinkKind::Resource, Some(url("/"))),
+        ]
+    );
+
+    let requested: HashSet<_> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| (request.method.to_string(), request.url.path().to_string()))
+        .collect();
+    assert!(requested.contains(&("HEAD".to_string(), "/style.css".to_string())));
+    assert!(requested.contains(&("GET".to_string(), "/team".to_string())));
+    assert!(!requested.iter().any(|(_, path)| path == "/from-embed"));
+}
+
+#[tokio::test]
+async fn skips_unparseable_urls_and_checks_the_rest() {
+    let server = MockServer::start().await;
+    let page = r#"<img src="http://[broken/logo.png"> <a href="/missing">a</a>
+        <a href="http://exa mple.com/">b</a> <img src="/missing.png">"#;
+    Mock::given(path("/"))
+        .respond_with(common::page(page))
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+
+    let mut broken = common::broken_urls(&findings);
+    broken.sort();
+    assert_eq!(
+        broken,
+        [
+            root.join("/missing").unwrap().to_string(),
+            root.join("/missing.png").unwrap().to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn keeps_crawling_past_unparseable_links_and_lists_them_as_skipped() {
+    let server = common::site(vec![
+        (
+            "/",
+            common::page(r#"<a href="http://exa mple.com/">x</a> <a href="/docs">docs</a>"#),
+        ),
+        (
+            "/docs",
+            common::page(r#"<a href="http://[::1">y</a> <a href="/docs/gone">gone</a>"#),
+        ),
+    ])
+    .await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone()).report_skipped(true).build();
+    let findings = common::crawl(crawler).await;
+
+    // Found two pages down, past a page with a garbage link on it
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/docs/gone").unwrap().to_string()]
+    );
+    let unparseable: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Skipped(skipped) if skipped.reason == SkipReason::InvalidUrl => {
+                Some((skipped.url.as_str(), skipped.referrer.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        unparseable,
+        [
+            ("http://exa mple.com/", root.to_string()),
+            ("http://[::1", root.join("/docs").unwrap().to_string()),
+        ]
+    );
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::retry::RetryPolicy;
+use std::time::Duration;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn parses_status_retry_pairs() {
+    let policy: RetryPolicy = "429:5, 503:3,500:1".parse().unwrap();
+    assert_eq!(policy.retries_for(429), 5);
+    assert_eq!(policy.retries_for(503), 3);
+    assert_eq!(policy.retries_for(500), 1);
+    // Anything not listed is not retried, even if the default policy would retry it
+    assert_eq!(policy.retries_for(502), 0);
+    assert_eq!(policy.retries_for(404), 0);
+}
+
+#[test]
+fn rejects_malformed_entries() {
+    for input in ["429", "abc:1", "429:x", "42:1", "700:1"] {
+        assert!(input.parse::<RetryPolicy>().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn default_policy_never_retries_404() {
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.retries_for(404), 0);
+    assert!(policy.retries_for(429) > 0);
+    assert!(policy.retries_for(503) > 0);
+}
+
+#[test]
+fn max_retries_covers_unlisted_server_errors() {
+    let policy: RetryPolicy = "503:1".parse::<RetryPolicy>().unwrap().max_retries(3);
+    assert_eq!(policy.retries_for(503), 1);
+    assert_eq!(policy.retries_for(500), 3);
+    assert_eq!(policy.retries_for(404), 0);
+    assert_eq!(policy.retries_without_response(), 3);
+    assert_eq!(RetryPolicy::default().retries_without_response(), 0);
+}
+
+#[test]
+fn backoff_doubles_with_up_to_half_again_of_jitter() {
+    let policy = RetryPolicy::default().base_delay(Duration::from_millis(100));
+    for (attempt, delay) in [(0, 100), (1, 200), (2, 400)] {
+        let delay = Duration::from_millis(delay);
+        let backoff = policy.backoff(attempt);
+        assert!(
+            backoff >= delay && backoff <= delay * 3 / 2,
+            "{:?}",
+            backoff
+        );
+    }
+}
+
+fn quick_retries(max_retries: u32) -> RetryPolicy {
+    "".parse::<RetryPolicy>()
+        .unwrap()
+        .max_retries(max_retries)
+        .base_delay(Duration::from_millis(10))
+}
+
+#[tokio::test]
+async fn retries_timeouts_and_server_errors_but_not_404s() {
+    let server = MockServer::start().await;
+    let links = r#"<a href="/flaky">a</a> <a href="/down">b</a> <a href="/gone">c</a>"#;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .mount(&server)
+        .await;
+    // Times out once, then loads
+    Mock::given(path("/flaky"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/flaky"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/down"))
+        .respond_with(ResponseTemplate::new(507))
+        .expect(3)
+        .mount(&server)
+        .await;
+    Mock::given(path("/gone"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone())
+        .retry_policy(quick_retries(2))
+        .request_timeout(Some(Duration::from_millis(200)))
+        .build();
+    let mut broken = common::broken_urls(&common::crawl(crawler).await);
+    broken.sort();
+
+    assert_eq!(
+        broken,
+        [
+            root.join("/down").unwrap().to_string(),
+            root.join("/gone").unwrap().to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reports_a_page_that_never_answers_once_the_retries_run_out() {
+    let server = MockServer::start().await;
+    // Same domain, so in scope, but nothing listens on port 1
+    let links = r#"<a href="http://localhost:1/">unreachable</a>"#;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .retry_policy(quick_retries(1))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, status, .. } => Some((url.as_str(), *status)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(broken, [("http://localhost:1/", Some(0))]);
+}
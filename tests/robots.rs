@@ -0,0 +1,188 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::links::extract_links;
+use find_broken_links::robots::RobotsDirectives;
+use find_broken_links::warnings::WarningCategory;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn parses_x_robots_tag_values() {
+    let cases: &[(&[&str], bool, bool)] = &[
+        // (header values, nofollow, noindex)
+        (&[], false, false),
+        (&["nofollow"], true, false),
+        (&["noindex, NoFollow"], true, true),
+        (&["none"], true, true),
+        (&["noarchive", "noindex"], false, true),
+        (&["googlebot: nofollow"], false, false),
+        (
+            &["noindex, unavailable_after: 25 Jun 2030 15:00:00 PST"],
+            false,
+            true,
+        ),
+    ];
+    for (values, nofollow, noindex) in cases {
+        let directives = RobotsDirectives::from_x_robots_tag(values.iter().copied());
+        assert_eq!(
+            directives,
+            RobotsDirectives {
+                nofollow: *nofollow,
+                noindex: *noindex
+            },
+            "{:?}",
+            values
+        );
+    }
+}
+
+#[test]
+fn parses_meta_robots_tags() {
+    let cases = [
+        // (head, nofollow, noindex)
+        ("", false, false),
+        (r#"<meta name="robots" content="nofollow">"#, true, false),
+        (
+            r#"<meta name="ROBOTS" content="NoIndex, NoFollow">"#,
+            true,
+            true,
+        ),
+        (r#"<meta name="robots" content="none">"#, true, true),
+        (r#"<meta name="robots" content="noindex">"#, false, true),
+        (
+            r#"<meta name="robots" content="index, follow">"#,
+            false,
+            false,
+        ),
+        (
+            r#"<meta name="robots" content="noarchive"><meta name="robots" content="nofollow">"#,
+            true,
+            false,
+        ),
+        (
+            r#"<meta name="googlebot" content="nofollow">"#,
+            false,
+            false,
+        ),
+        (r#"<meta name="description" content="none">"#, false, false),
+        (r#"<meta name="robots">"#, false, false),
+        // Fallback markup isn't the page's own
+        (
+            r#"<noscript><meta name="robots" content="nofollow"></noscript>"#,
+            false,
+            false,
+        ),
+    ];
+    for (head, nofollow, noindex) in cases {
+        let html = format!("<html><head>{}</head><body></body></html>", head);
+        assert_eq!(
+            extract_links(&html).robots,
+            RobotsDirectives { nofollow, noindex },
+            "{}",
+            head
+        );
+    }
+}
+
+#[tokio::test]
+async fn nofollow_header_stops_recursion_and_noindex_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-Robots-Tag", "noindex, nofollow")
+                .set_body_raw("<a href=\"/private\">private</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/private"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .respect_robots_header(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert!(common::broken_urls(&findings).is_empty());
+    assert!(findings.iter().any(|finding| matches!(
+        finding,
+        Finding::Warning(warning) if warning.category == WarningCategory::Noindex
+    )));
+}
+
+#[tokio::test]
+async fn header_is_ignored_by_default() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-Robots-Tag", "nofollow")
+                .set_body_raw("<a href=\"/missing\">missing</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+
+    let findings = common::crawl(Crawler::builder(common::root_url(&server)).build()).await;
+    assert_eq!(
+        common::broken_urls(&findings),
+        [common::root_url(&server)
+            .join("/missing")
+            .unwrap()
+            .to_string()]
+    );
+}
+
+#[tokio::test]
+async fn nofollow_meta_stops_recursion_but_the_page_is_checked() {
+    let server = common::site(vec![
+        (
+            "/",
+            common::page(r#"<a href="/private">private</a><a href="/gone">gone</a>"#),
+        ),
+        (
+            "/private",
+            common::page(concat!(
+                r#"<meta name="robots" content="noindex, nofollow">"#,
+                r#"<a href="/private/gone">x</a>"#,
+            )),
+        ),
+    ])
+    .await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone())
+        .respect_robots_meta(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/gone").unwrap().to_string()]
+    );
+    let noindex: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Warning(warning) if warning.category == WarningCategory::Noindex => {
+                Some(warning.url.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(noindex, [root.join("/private").unwrap().to_string()]);
+
+    // Without the flag, the meta tag is ignored
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+    let mut broken = common::broken_urls(&findings);
+    broken.sort();
+    assert_eq!(
+        broken,
+        [
+            root.join("/gone").unwrap().to_string(),
+            root.join("/private/gone").unwrap().to_string()
+        ]
+    );
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::safety::UnsafeUrlFilter;
+use find_broken_links::warnings::WarningCategory;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn default_patterns_match_path_and_query() {
+    let filter = UnsafeUrlFilter::default();
+    // (URL, pattern it should match)
+    let cases = [
+        ("https://example.com/delete?id=5", Some("*delete*")),
+        ("https://example.com/account/LogOut", Some("*logout*")),
+        (
+            "https://example.com/items?action=remove&id=1",
+            Some("*remove*"),
+        ),
+        (
+            "https://example.com/docs/deleted-files-faq",
+            Some("*delete*"),
+        ),
+        ("https://example.com/docs/page", None),
+        // Only path and query count, not the host
+        ("https://delete.example.com/", None),
+    ];
+    for (url, expected) in cases {
+        let url = Url::parse(url).unwrap();
+        assert_eq!(filter.matching_pattern(&url), expected, "{}", url);
+    }
+}
+
+#[test]
+fn custom_patterns_replace_the_defaults() {
+    let filter = UnsafeUrlFilter::new(["/admin/*", "*.cgi"]);
+    let matches = |url: &str| filter.matching_pattern(&Url::parse(url).unwrap()).is_some();
+    assert!(matches("https://example.com/admin/users"));
+    assert!(matches("https://example.com/bin/run.cgi"));
+    assert!(!matches("https://example.com/bin/run.cgi.txt"));
+    assert!(!matches("https://example.com/user/admin/"));
+    assert!(!matches("https://example.com/delete"));
+}
+
+#[tokio::test]
+async fn unsafe_links_are_reported_but_not_requested() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<a href=\"/delete?id=5\">delete</a><a href=\"/ok\">ok</a>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/delete"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(0)
+        .mount(&server)
+        .await;
+    Mock::given(path("/ok"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let root = common::root_url(&server);
+    let crawler = Crawler::builder(root.clone())
+        .safe_only(Some(UnsafeUrlFilter::default()))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert!(common::broken_urls(&findings).is_empty());
+    let unsafe_urls: Vec<&str> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Warning(warning) if warning.category == WarningCategory::UnsafeLink => {
+                Some(warning.url.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(unsafe_urls, [root.join("/delete?id=5").unwrap().as_str()]);
+}
@@ -0,0 +1,252 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::scope::{fuzzy_matches, is_subdomain_of, ScopeEntry};
+use find_broken_links::skipped::SkipReason;
+use find_broken_links::url_filter::UrlPatternFilter;
+use regex::Regex;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn entry(s: &str) -> ScopeEntry {
+    s.parse().unwrap()
+}
+
+#[test]
+fn matches_host_and_optional_scheme_and_port() {
+    let cases = [
+        ("example.com", "https://example.com/a", true),
+        ("example.com", "http://example.com:8080/a", true),
+        ("example.com", "https://www.example.com/", false),
+        ("Example.COM", "https://example.com/", true),
+        ("example.com:8443", "https://example.com:8443/", true),
+        ("example.com:8443", "https://example.com/", false),
+        ("example.com:443", "https://example.com/", true),
+        ("https://example.com", "http://example.com/", false),
+        (
+            "http://example.com:3000",
+            "http://example.com:3000/api",
+            true,
+        ),
+        ("127.0.0.1:3000", "http://127.0.0.1:3000/", true),
+        ("[::1]:8443", "https://[::1]:8443/", true),
+        ("[::1]", "https://[::1]:8443/", true),
+    ];
+    for (scope, url, expected) in cases {
+        assert_eq!(
+            entry(scope).matches(&Url::parse(url).unwrap()),
+            expected,
+            "{} vs {}",
+            scope,
+            url
+        );
+    }
+}
+
+#[test]
+fn rejects_malformed_entries() {
+    for scope in [
+        "",
+        "example.com:http",
+        "example.com:99999",
+        "example.com/docs",
+        "[::1",
+        "[::1]8443",
+        "https://",
+    ] {
+        assert!(scope.parse::<ScopeEntry>().is_err(), "{}", scope);
+    }
+}
+
+#[test]
+fn subdomains_are_compared_by_whole_labels() {
+    let cases = [
+        ("example.com", "example.com", true),
+        ("blog.example.com", "example.com", true),
+        ("a.b.example.com", "example.com", true),
+        ("Blog.Example.COM", "example.com", true),
+        ("blog.example.com.", "example.com", true),
+        ("notexample.com", "example.com", false),
+        ("example.com.evil.com", "example.com", false),
+        ("com", "example.com", false),
+        ("example.com", "www.example.com", false),
+        ("blog.example.com", "", false),
+    ];
+    for (domain, parent, expected) in cases {
+        assert_eq!(
+            is_subdomain_of(domain, parent),
+            expected,
+            "{} under {}",
+            domain,
+            parent
+        );
+    }
+}
+
+#[test]
+fn fuzzy_match_string_matches_whole_labels() {
+    let cases = [
+        ("api.example.com", "example.com", true),
+        ("example.com", "example.com", true),
+        ("example.com.evil.com", "example.com", false),
+        ("notexample.com", "example.com", false),
+        ("shop.example.com", "shop", true),
+        ("www.shop.io", "shop", true),
+        ("myshop.com", "shop", false),
+        ("shopping.com", "shop", false),
+        ("API.Example.com", "example.com", true),
+        ("example.com", "", false),
+    ];
+    for (domain, fuzzy, expected) in cases {
+        assert_eq!(
+            fuzzy_matches(domain, fuzzy),
+            expected,
+            "{} vs fuzzy {}",
+            domain,
+            fuzzy
+        );
+    }
+}
+
+/// Two services on localhost: the app links to a missing page on the API.
+async fn mount_services() -> (MockServer, MockServer) {
+    let app = MockServer::start().await;
+    let api = MockServer::start().await;
+    let page = format!("<a href=\"{}missing\">api</a>", common::root_url(&api));
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+        .mount(&app)
+        .await;
+    (app, api)
+}
+
+fn out_of_domain(findings: &[Finding]) -> Vec<String> {
+    findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Skipped(skipped) if skipped.reason == SkipReason::OutOfDomain => {
+                Some(skipped.url.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn scope_entries_replace_the_domain_comparison() {
+    let (app, api) = mount_services().await;
+    let app_url = common::root_url(&app);
+    let api_missing = common::root_url(&api).join("/missing").unwrap().to_string();
+    let only_app = entry(&format!("localhost:{}", app_url.port().unwrap()));
+
+    // Same domain, so the default follows the link to the API
+    let findings = common::crawl(Crawler::builder(app_url.clone()).build()).await;
+    assert_eq!(common::broken_urls(&findings), [api_missing.as_str()]);
+
+    let crawler = Crawler::builder(app_url.clone())
+        .scope(vec![only_app.clone()])
+        .report_skipped(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+    assert!(common::broken_urls(&findings).is_empty());
+    assert_eq!(out_of_domain(&findings), [api_missing.as_str()]);
+
+    let api_port = common::root_url(&api).port().unwrap();
+    let crawler = Crawler::builder(app_url)
+        .scope(vec![
+            only_app,
+            entry(&format!("http://localhost:{}", api_port)),
+        ])
+        .build();
+    let findings = common::crawl(crawler).await;
+    assert_eq!(common::broken_urls(&findings), [api_missing.as_str()]);
+}
+
+#[tokio::test]
+async fn scope_allows_an_ip_address_root() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/missing\">x</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    let root = Url::parse(&server.uri()).unwrap();
+
+    let crawler = Crawler::builder(root.clone())
+        .scope(vec![entry(&format!("127.0.0.1:{}", root.port().unwrap()))])
+        .build();
+    let findings = common::crawl(crawler).await;
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/missing").unwrap().to_string()]
+    );
+}
+
+#[tokio::test]
+async fn include_subdomains_follows_links_below_the_root_domain() {
+    let server = MockServer::start().await;
+    let port = common::root_url(&server).port().unwrap();
+    // Excluded once in scope, so nothing has to resolve `docs.localhost`
+    let page = format!(
+        "<a href=\"http://docs.localhost:{port}/missing\">docs</a>
+        <a href=\"http://notlocalhost:{port}/missing\">lookalike</a>"
+    );
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+        .mount(&server)
+        .await;
+    let docs = format!("http://docs.localhost:{port}/missing");
+    let lookalike = format!("http://notlocalhost:{port}/missing");
+    let crawler = |include_subdomains| {
+        Crawler::builder(common::root_url(&server))
+            .include_subdomains(include_subdomains)
+            .url_filter(UrlPatternFilter::new(
+                Vec::new(),
+                vec![Regex::new(r"//docs\.localhost").unwrap()],
+            ))
+            .report_skipped(true)
+            .build()
+    };
+
+    let findings = common::crawl(crawler(false)).await;
+    assert_eq!(
+        out_of_domain(&findings),
+        [docs.as_str(), lookalike.as_str()]
+    );
+
+    let findings = common::crawl(crawler(true)).await;
+    assert_eq!(out_of_domain(&findings), [lookalike.as_str()]);
+    assert!(findings.iter().any(|finding| matches!(
+        finding,
+        Finding::Skipped(skipped) if skipped.reason == SkipReason::Excluded && skipped.url == docs
+    )));
+}
+
+#[tokio::test]
+async fn fuzzy_match_string_skips_lookalike_domains() {
+    let server = MockServer::start().await;
+    let page = r#"<a href="http://api.example.com/missing">api</a>
+        <a href="http://example.com.evil.com/missing">lookalike</a>"#;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .fuzzy_match_string(Some("example.com".to_string()))
+        // Excluded once in scope, so nothing goes out to example.com
+        .url_filter(UrlPatternFilter::new(
+            Vec::new(),
+            vec![Regex::new("example").unwrap()],
+        ))
+        .report_skipped(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        out_of_domain(&findings),
+        ["http://example.com.evil.com/missing"]
+    );
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer};
+
+#[tokio::test]
+async fn crawls_every_seed_on_the_root_domain_and_only_checks_the_others() {
+    let site = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(r#"<a href="/docs">docs</a>"#))
+        .mount(&site)
+        .await;
+    Mock::given(path("/docs"))
+        .respond_with(common::page(r#"<a href="/docs/gone">x</a>"#))
+        .mount(&site)
+        .await;
+    Mock::given(path("/blog"))
+        .respond_with(common::page(r#"<a href="/blog/gone">x</a>"#))
+        .mount(&site)
+        .await;
+    // Addressed by IP, so not on the root URL's domain
+    let other = MockServer::start().await;
+    Mock::given(path("/page"))
+        .respond_with(common::page(r#"<a href="/page/gone">x</a>"#))
+        .mount(&other)
+        .await;
+    let root = common::root_url(&site);
+    let other_url = |route: &str| Url::parse(&other.uri()).unwrap().join(route).unwrap();
+
+    let crawler = Crawler::builder(root.clone())
+        .seed_urls(vec![
+            root.join("/docs").unwrap(),
+            root.join("/blog").unwrap(),
+            other_url("/page"),
+            other_url("/missing"),
+        ])
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let mut broken = common::broken_urls(&findings);
+    broken.sort();
+    let mut expected = vec![
+        root.join("/blog/gone").unwrap().to_string(),
+        root.join("/docs/gone").unwrap().to_string(),
+        other_url("/missing").to_string(),
+    ];
+    expected.sort();
+    assert_eq!(broken, expected);
+
+    // `/docs` is seeded and linked, but only requested once
+    let site_requests = site.received_requests().await.unwrap();
+    let docs = site_requests.iter().filter(|r| r.url.path() == "/docs");
+    assert_eq!(docs.count(), 1);
+    let other_requests = other.received_requests().await.unwrap();
+    // Checked like any other external link, without downloading the page
+    let page = other_requests
+        .iter()
+        .find(|r| r.url.path() == "/page")
+        .unwrap();
+    assert_eq!(page.method.as_str(), "HEAD");
+}
+
+#[tokio::test]
+async fn queues_a_seed_the_root_links_to_once_and_lists_the_root_as_linking_to_it() {
+    let site = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(r#"<a href="/gone">x</a>"#))
+        .mount(&site)
+        .await;
+    let root = common::root_url(&site);
+    let gone = root.join("/gone").unwrap();
+
+    let crawler = Crawler::builder(root.clone())
+        .seed_urls(vec![gone.clone(), root.clone()])
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(common::broken_urls(&findings), [gone.to_string()]);
+    // Still waiting in the queue as a seed when the root linked to it
+    assert!(findings.iter().any(|finding| matches!(
+        finding,
+        Finding::AlsoLinkedFrom { url, referrer } if *url == gone.as_str() && *referrer == root.as_str()
+    )));
+    let requests = site.received_requests().await.unwrap();
+    let paths: Vec<_> = requests.iter().map(|r| r.url.path()).collect();
+    assert_eq!(paths, ["/", "/gone"]);
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use find_broken_links::crawler::{CrawlMessage, Crawler};
+use find_broken_links::debug_channel::DebugChannel;
+use std::time::{Duration, Instant};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+#[tokio::test]
+async fn cancelled_crawl_sends_completion_without_fetching() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/next\">next</a>", "text/html"),
+        )
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server)).build();
+    crawler.cancellation_token().cancel();
+
+    let mut channel = DebugChannel::<CrawlMessage>::new(5);
+    crawler.run(channel.sender()).await.unwrap();
+    assert!(matches!(channel.recv().await, Some(CrawlMessage::Complete)));
+}
+
+#[tokio::test]
+async fn stops_requesting_pages_once_cancelled_mid_crawl() {
+    let server = MockServer::start().await;
+    // Every page links to the next one, so the crawl never runs out on its own
+    Mock::given(wiremock::matchers::any())
+        .respond_with(|request: &Request| {
+            let path = request.url.path().trim_end_matches('/');
+            let next = format!("<a href=\"{}/next\">next</a>", path);
+            ResponseTemplate::new(200)
+                .set_body_raw(next, "text/html")
+                .set_delay(Duration::from_millis(20))
+        })
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server)).build();
+    let cancel = crawler.cancellation_token();
+    let mut channel = DebugChannel::<CrawlMessage>::new(5);
+    let sender = channel.sender();
+    let task = tokio::spawn(async move { crawler.run(sender).await });
+
+    while server.received_requests().await.unwrap().len() < 3 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    cancel.cancel();
+
+    // The request in flight finishes, then the crawler signals completion and returns
+    while let Some(CrawlMessage::Finding(_)) = channel.recv().await {}
+    tokio::time::timeout(Duration::from_secs(5), task)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let requested = server.received_requests().await.unwrap().len();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(server.received_requests().await.unwrap().len(), requested);
+    assert!(requested <= 4, "{} requests", requested);
+}
+
+#[tokio::test]
+async fn slow_request_during_shutdown_is_bounded_by_abort() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server)).build();
+    let cancel = crawler.cancellation_token();
+    let mut channel = DebugChannel::<CrawlMessage>::new(5);
+    let sender = channel.sender();
+    let mut task = tokio::spawn(async move { crawler.run(sender).await });
+
+    // Let the request get in flight, then ask the crawler to stop
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let started = Instant::now();
+    cancel.cancel();
+
+    // Cooperative cancellation can't interrupt the hung request...
+    let grace = Duration::from_millis(300);
+    assert!(tokio::time::timeout(grace, &mut task).await.is_err());
+
+    // ...so after the grace period the task is aborted, well before the response would arrive
+    task.abort();
+    assert!(task.await.unwrap_err().is_cancelled());
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    // Nothing was reported, and the receiver isn't left waiting on a live crawler
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), channel.recv())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn runtime_stays_responsive_while_a_huge_page_is_processed() {
+    let server = MockServer::start().await;
+    let links: String = (0..50_000)
+        .map(|i| format!("<a href=\"/page/{}\">{}</a>", i, i))
+        .collect();
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/page/0"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .concurrency(1)
+        .build();
+    let cancel = crawler.cancellation_token();
+    let progress = crawler.progress();
+    // Cancel like CTRL+C would once the page is fetched, while it's parsed and its links queued
+    let task = tokio::spawn(common::crawl(crawler));
+    while server.received_requests().await.unwrap().is_empty() {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    cancel.cancel();
+
+    // This test runs on a single thread. A crawl that gave it back only while downloading the
+    // page left this loop around a thousand turns; one that also gives it back while the page is
+    // parsed and its links queued leaves it hundreds of times more, however fast the machine is
+    let mut turns = 0;
+    while !task.is_finished() {
+        tokio::task::yield_now().await;
+        turns += 1;
+    }
+    task.await.unwrap();
+    assert!(progress.pages_crawled() <= 2);
+    assert!(turns > 20_000, "the runtime only got {} turns", turns);
+}
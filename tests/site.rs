@@ -0,0 +1,112 @@
+mod common;
+
+use common::page;
+use find_broken_links::crawler::{Crawler, Finding};
+use std::collections::BTreeSet;
+use wiremock::{MockServer, ResponseTemplate};
+
+/// A small site: pages linking each other, a missing page linked from two of them, a redirect
+/// into the site and one to a missing page, and a link to another site.
+fn routes(other_site: &str) -> Vec<(&'static str, ResponseTemplate)> {
+    let home = format!(
+        r#"<a href="/about">about</a> <a href="/blog/">blog</a> <a href="/old">old</a>
+           <a href="{}elsewhere">elsewhere</a>"#,
+        other_site
+    );
+    vec![
+        ("/", page(&home)),
+        (
+            "/about",
+            page(r#"<a href="/">home</a> <a href="/team">team</a>"#),
+        ),
+        (
+            "/blog/",
+            page(r#"<a href="first">first</a> <a href="/team">team</a> <a href="/moved">x</a>"#),
+        ),
+        ("/blog/first", page(r#"<a href="/blog/">blog</a>"#)),
+        (
+            "/old",
+            ResponseTemplate::new(301).insert_header("location", "/about"),
+        ),
+        (
+            "/moved",
+            ResponseTemplate::new(302).insert_header("location", "/gone"),
+        ),
+    ]
+}
+
+/// (broken URL's path, path of every page linking to it), from `findings`.
+fn broken_with_referrers(findings: Vec<Finding>) -> BTreeSet<(String, BTreeSet<String>)> {
+    let path = |url: &str| url::Url::parse(url).unwrap().path().to_string();
+    let mut broken: Vec<(String, BTreeSet<String>)> = Vec::new();
+    for finding in findings {
+        match finding {
+            Finding::NotFound { url, referrer, .. } => {
+                broken.push((path(&url), referrer.iter().map(|r| path(r)).collect()));
+            }
+            Finding::AlsoLinkedFrom { url, referrer } => {
+                let entry = broken.iter_mut().find(|(p, _)| *p == path(&url)).unwrap();
+                entry.1.insert(path(&referrer));
+            }
+            _ => {}
+        }
+    }
+    broken.into_iter().collect()
+}
+
+fn expected(entries: &[(&str, &[&str])]) -> BTreeSet<(String, BTreeSet<String>)> {
+    entries
+        .iter()
+        .map(|(url, referrers)| {
+            let referrers = referrers.iter().map(|r| r.to_string()).collect();
+            (url.to_string(), referrers)
+        })
+        .collect()
+}
+
+/// Crawls the site of [`routes`], returning what it reported and the other site it links to.
+/// Both are on `localhost`, so the crawl is scoped to the site's port.
+async fn crawl_site(check_external: bool) -> (Vec<Finding>, MockServer) {
+    let other = common::site(vec![]).await;
+    let server = common::site(routes(common::root_url(&other).as_str())).await;
+    let root = common::root_url(&server);
+    let scope = format!("localhost:{}", root.port().unwrap())
+        .parse()
+        .unwrap();
+
+    let crawler = Crawler::builder(root)
+        .scope(vec![scope])
+        .check_external(check_external)
+        .build();
+    (common::crawl(crawler).await, other)
+}
+
+#[tokio::test]
+async fn reports_exactly_the_broken_links_of_a_site() {
+    let (findings, other) = crawl_site(false).await;
+
+    // /old loads /about, whose links resolve against /old. The redirect to /gone is reported
+    // under the link to it, and the other site isn't checked.
+    assert_eq!(
+        broken_with_referrers(findings),
+        expected(&[
+            ("/team", &["/about", "/blog/", "/old"]),
+            ("/moved", &["/blog/"])
+        ])
+    );
+    assert!(other.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn reports_broken_links_to_other_sites_with_check_external() {
+    let (findings, _other) = crawl_site(true).await;
+
+    assert_eq!(
+        broken_with_referrers(findings),
+        expected(&[
+            ("/team", &["/about", "/blog/", "/old"]),
+            ("/moved", &["/blog/"]),
+            ("/elsewhere", &["/"]),
+        ])
+    );
+}
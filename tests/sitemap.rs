@@ -0,0 +1,122 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::sitemap::Sitemap;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn gzip(text: &str) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(text.as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn urlset(locs: &[&str]) -> String {
+    let urls: String = locs
+        .iter()
+        .map(|loc| format!("<url><loc>{}</loc><lastmod>2026-01-01</lastmod></url>", loc))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+        <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">{}</urlset>"#,
+        urls
+    )
+}
+
+#[test]
+fn parses_url_sets_and_index_files() {
+    assert_eq!(
+        Sitemap::parse(urlset(&["https://example.com/a?x=1&amp;y=2", " /b "]).as_bytes()),
+        Ok(Sitemap::Urls(vec![
+            "https://example.com/a?x=1&y=2".to_string(),
+            "/b".to_string()
+        ]))
+    );
+    let index = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+        <sitemap><loc><![CDATA[https://example.com/pages.xml.gz]]></loc></sitemap>
+        </sitemapindex>"#;
+    assert_eq!(
+        Sitemap::parse(index.as_bytes()),
+        Ok(Sitemap::Index(vec![
+            "https://example.com/pages.xml.gz".to_string()
+        ]))
+    );
+    assert_eq!(
+        Sitemap::parse(&gzip(&urlset(&["https://example.com/c"]))),
+        Ok(Sitemap::Urls(vec!["https://example.com/c".to_string()]))
+    );
+    assert!(Sitemap::parse(b"<html><body>Not found</body></html>").is_err());
+}
+
+#[tokio::test]
+async fn crawls_the_urls_in_the_sitemap_and_its_index_files() {
+    let server = MockServer::start().await;
+    let root = common::root_url(&server);
+    let url = |route: &str| root.join(route).unwrap().to_string();
+    Mock::given(path("/"))
+        .respond_with(common::page("no links"))
+        .mount(&server)
+        .await;
+    let index = format!(
+        r#"<sitemapindex><sitemap><loc>{}</loc></sitemap></sitemapindex>"#,
+        url("/pages.xml.gz")
+    );
+    Mock::given(path("/sitemap.xml"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(index, "application/xml"))
+        .mount(&server)
+        .await;
+    // Relative locs aren't allowed, but resolve against the sitemap anyway
+    let pages = gzip(&urlset(&[&url("/orphan"), "/stale"]));
+    Mock::given(path("/pages.xml.gz"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(pages, "application/gzip"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/orphan"))
+        .respond_with(common::page(r#"<a href="/orphan/gone">x</a>"#))
+        .mount(&server)
+        .await;
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+    assert!(common::broken_urls(&findings).is_empty());
+    let requested = server.received_requests().await.unwrap();
+    assert!(requested.iter().all(|r| r.url.path() != "/sitemap.xml"));
+
+    let crawler = Crawler::builder(root.clone()).use_sitemap(true).build();
+    let findings = common::crawl(crawler).await;
+    let mut broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, referrer, .. } => Some((url.clone(), referrer.clone()?)),
+            _ => None,
+        })
+        .collect();
+    broken.sort();
+    assert_eq!(
+        broken,
+        [
+            (url("/orphan/gone"), url("/orphan")),
+            (url("/stale"), url("/pages.xml.gz")),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn crawls_on_without_a_sitemap() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(r#"<a href="/gone">x</a>"#))
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone()).use_sitemap(true).build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/gone").unwrap().to_string()]
+    );
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::links::{Destination, LinkClass};
+use find_broken_links::skipped::{SkipReason, SkippedLink};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn skipped(findings: &[Finding]) -> Vec<SkippedLink> {
+    findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Skipped(skipped) => Some(skipped.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn class(destination: Destination) -> LinkClass {
+    LinkClass {
+        destination,
+        asset: false,
+    }
+}
+
+#[tokio::test]
+async fn reports_out_of_scope_and_nofollow_links() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<a href=\"mailto:me@example.com\">mail</a>
+             <a href=\"https://other.example/\">other</a>
+             <a href=\"/private\">private</a>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/private"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-Robots-Tag", "nofollow")
+                .set_body_raw("<a href=\"/secret\">secret</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+
+    let root = common::root_url(&server);
+    let crawler = Crawler::builder(root.clone())
+        .respect_robots_header(true)
+        .report_skipped(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let private = root.join("/private").unwrap().to_string();
+    assert_eq!(
+        skipped(&findings),
+        [
+            SkippedLink {
+                url: "mailto:me@example.com".to_string(),
+                referrer: root.to_string(),
+                reason: SkipReason::DeniedProtocol,
+                class: Some(class(Destination::Contact)),
+            },
+            SkippedLink {
+                url: "https://other.example/".to_string(),
+                referrer: root.to_string(),
+                reason: SkipReason::OutOfDomain,
+                class: Some(class(Destination::External)),
+            },
+            SkippedLink {
+                url: "/secret".to_string(),
+                referrer: private,
+                reason: SkipReason::Nofollow,
+                class: Some(class(Destination::Internal)),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn nothing_is_reported_by_default() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<a href=\"https://other.example/\">x</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+
+    let findings = common::crawl(Crawler::builder(common::root_url(&server)).build()).await;
+    assert!(skipped(&findings).is_empty());
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::warnings::{LinkWarning, WarningCategory};
+use std::time::Duration;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn slow_page_warnings(findings: &[Finding]) -> Vec<&LinkWarning> {
+    findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Warning(warning) if warning.category == WarningCategory::SlowPage => {
+                Some(warning)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+async fn mount_pages(server: &MockServer) {
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/slow\">slow</a>", "text/html"),
+        )
+        .mount(server)
+        .await;
+    Mock::given(path("/slow"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<html></html>", "text/html")
+                .set_delay(Duration::from_millis(300)),
+        )
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn warns_about_pages_over_the_threshold() {
+    let server = MockServer::start().await;
+    mount_pages(&server).await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone())
+        .report_slow(Some(Duration::from_millis(200)))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let warnings = slow_page_warnings(&findings);
+    assert_eq!(warnings.len(), 1, "only /slow is slow");
+    assert_eq!(warnings[0].url, root.join("/slow").unwrap().to_string());
+    assert!(
+        warnings[0].message.contains("over the threshold of 200ms"),
+        "{}",
+        warnings[0].message
+    );
+}
+
+#[tokio::test]
+async fn no_threshold_by_default() {
+    let server = MockServer::start().await;
+    mount_pages(&server).await;
+
+    let findings = common::crawl(Crawler::builder(common::root_url(&server)).build()).await;
+
+    assert!(slow_page_warnings(&findings).is_empty());
+}
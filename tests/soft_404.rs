@@ -0,0 +1,200 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::soft_404::{PageText, Soft404Detector};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+/// A CMS that answers every missing page with a 200 error page echoing the path.
+struct ErrorPage;
+
+impl wiremock::Respond for ErrorPage {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        common::page(&format!(
+            "<title>Oops</title><p>Sorry, {} was not found.</p>",
+            request.url.path()
+        ))
+    }
+}
+
+/// (URL, error) of every soft 404 reported.
+fn soft_404s(findings: &[Finding]) -> Vec<(String, String)> {
+    let mut soft_404s: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound {
+                url,
+                error,
+                status,
+                soft_404: true,
+                ..
+            } => {
+                assert_eq!(*status, Some(200));
+                Some((url.clone(), error.clone()?))
+            }
+            _ => None,
+        })
+        .collect();
+    soft_404s.sort();
+    soft_404s
+}
+
+#[test]
+fn reads_the_title_and_visible_text() {
+    let page = PageText::from_html(
+        "<html><head><title> Page\n Not Found </title><style>p { color: red }</style></head>
+        <body><h1>Gone</h1> <script>var x = 'not found';</script><p>Try   the <a>home page</a></p>
+        </body></html>",
+    );
+
+    assert_eq!(page.title.as_deref(), Some("Page Not Found"));
+    assert_eq!(page.text, "Gone Try the home page");
+}
+
+#[test]
+fn default_patterns_match_the_usual_error_pages() {
+    let detector = Soft404Detector::default();
+    let matches = |html: &str| {
+        detector
+            .matching_pattern(&PageText::from_html(html))
+            .map(str::to_string)
+    };
+
+    assert_eq!(
+        matches("<title>Page Not Found</title>").as_deref(),
+        Some("not found")
+    );
+    assert_eq!(
+        matches("<p>Error 404: nothing here</p>").as_deref(),
+        Some(r"\b404\b")
+    );
+    assert!(matches("<p>Sorry, this page doesn't exist.</p>").is_some());
+    assert!(matches("<p>Call 555-4040 for help</p>").is_none());
+    assert!(matches("<title>Docs</title><p>Everything is fine</p>").is_none());
+
+    let custom = Soft404Detector::new(&["nothing to see"]).unwrap();
+    assert!(custom
+        .matching_pattern(&PageText::from_html("<p>Nothing to see here</p>"))
+        .is_some());
+    assert!(custom
+        .matching_pattern(&PageText::from_html("<title>Not found</title>"))
+        .is_none());
+    assert!(Soft404Detector::new(&["("]).is_err());
+}
+
+#[test]
+fn error_pages_look_alike_whatever_path_they_repeat() {
+    let error_page = |path: &str| {
+        PageText::from_html(&format!("<title>Oops</title><p>{} was not found</p>", path))
+    };
+
+    assert!(error_page("/a").looks_like("/a", &error_page("/probe"), "/probe"));
+    let other = PageText::from_html("<title>Oops</title><p>/a was not found, but try /b</p>");
+    assert!(!other.looks_like("/a", &error_page("/probe"), "/probe"));
+}
+
+#[tokio::test]
+async fn reports_pages_that_read_like_error_pages() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(
+            r#"<a href="/docs">docs</a> <a href="/old">old</a>"#,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/docs"))
+        .respond_with(common::page("<title>Docs</title><p>All good</p>"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/old"))
+        .respond_with(common::page(
+            r#"<title>Page not found</title><a href="/from-error-page">home</a>"#,
+        ))
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+
+    // Without it, the error page passes and its links are followed
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/from-error-page").unwrap().to_string()]
+    );
+
+    let crawler = Crawler::builder(root.clone())
+        .soft_404(Some(Soft404Detector::default()))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        soft_404s(&findings),
+        [(
+            root.join("/old").unwrap().to_string(),
+            "soft 404: the page matches \"not found\"".to_string()
+        )]
+    );
+    // An error page's links aren't followed
+    assert_eq!(common::broken_urls(&findings).len(), 1);
+}
+
+#[tokio::test]
+async fn probe_leaves_out_pages_unlike_the_error_page() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(
+            r#"<a href="/handling-404s">guide</a> <a href="/removed">removed</a>"#,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/handling-404s"))
+        .respond_with(common::page(
+            "<title>Guide</title><p>How to serve a 404 page</p>",
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ErrorPage)
+        .with_priority(10)
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+    let crawler = |probe| {
+        Crawler::builder(root.clone())
+            .soft_404(Some(Soft404Detector::default().probe(probe)))
+            .build()
+    };
+
+    let findings = common::crawl(crawler(false)).await;
+    assert_eq!(soft_404s(&findings).len(), 2);
+
+    let findings = common::crawl(crawler(true)).await;
+    assert_eq!(
+        soft_404s(&findings),
+        [(
+            root.join("/removed").unwrap().to_string(),
+            "soft 404: looks like the site's error page and matches \"not found\"".to_string()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn probe_finds_a_site_with_real_404s_has_no_soft_ones() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(r#"<a href="/handling-404s">guide</a>"#))
+        .mount(&server)
+        .await;
+    Mock::given(path("/handling-404s"))
+        .respond_with(common::page(
+            "<title>Guide</title><p>How to serve a 404 page</p>",
+        ))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .soft_404(Some(Soft404Detector::default().probe(true)))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert!(soft_404s(&findings).is_empty());
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A span as recorded: its name, its parent's name and its fields as `Debug` strings.
+#[derive(Debug, Clone)]
+struct RecordedSpan {
+    name: &'static str,
+    parent: Option<&'static str>,
+    fields: HashMap<&'static str, String>,
+}
+
+impl Visit for RecordedSpan {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+/// Keeps every span, so the test can check what the crawl reported.
+#[derive(Clone, Default)]
+struct SpanRecorder {
+    spans: Arc<Mutex<HashMap<u64, RecordedSpan>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Subscriber for SpanRecorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut spans = self.spans.lock().unwrap();
+        let parent = attrs
+            .parent()
+            .map(|parent| parent.into_u64())
+            .or_else(|| attrs.is_contextual().then(|| self.current()).flatten())
+            .and_then(|parent| spans.get(&parent))
+            .map(|parent| parent.name);
+        let mut span = RecordedSpan {
+            name: attrs.metadata().name(),
+            parent,
+            fields: HashMap::new(),
+        };
+        attrs.record(&mut span);
+        spans.insert(id, span);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, id: &Id, values: &Record<'_>) {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            values.record(span);
+        }
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, id: &Id) {
+        CURRENT.with(|current| current.lock().unwrap().push(id.into_u64()));
+    }
+
+    fn exit(&self, _: &Id) {
+        CURRENT.with(|current| current.lock().unwrap().pop());
+    }
+}
+
+thread_local! {
+    static CURRENT: Mutex<Vec<u64>> = const { Mutex::new(Vec::new()) };
+}
+
+impl SpanRecorder {
+    fn current(&self) -> Option<u64> {
+        CURRENT.with(|current| current.lock().unwrap().last().copied())
+    }
+
+    fn named(&self, name: &str) -> Vec<RecordedSpan> {
+        let spans = self.spans.lock().unwrap();
+        let mut ids: Vec<_> = spans.keys().copied().collect();
+        ids.sort();
+        ids.iter()
+            .map(|id| &spans[id])
+            .filter(|span| span.name == name)
+            .cloned()
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn one_span_per_url_with_its_phases() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/missing\">x</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+
+    let recorder = SpanRecorder::default();
+    let _default = tracing::subscriber::set_default(recorder.clone());
+    common::crawl(Crawler::builder(root.clone()).build()).await;
+
+    let pages = recorder.named("page");
+    let fields: Vec<_> = pages
+        .iter()
+        .map(|page| {
+            (
+                page.fields["url"].clone(),
+                page.fields["depth"].clone(),
+                page.fields["status"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            (root.to_string(), "0".to_string(), "200".to_string()),
+            (
+                root.join("/missing").unwrap().to_string(),
+                "1".to_string(),
+                "404".to_string()
+            ),
+        ]
+    );
+    assert!(pages
+        .iter()
+        .all(|page| page.fields.contains_key("elapsed_ms")));
+    assert_eq!(pages[0].fields["links"], "1");
+
+    for phase in ["fetch", "parse", "enqueue"] {
+        let spans = recorder.named(phase);
+        assert!(!spans.is_empty(), "no {} span", phase);
+        assert!(
+            spans.iter().all(|span| span.parent == Some("page")),
+            "{}",
+            phase
+        );
+    }
+    assert_eq!(recorder.named("enqueue")[0].fields["queued"], "1");
+}
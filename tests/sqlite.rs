@@ -0,0 +1,64 @@
+#![cfg(feature = "sqlite")]
+
+use find_broken_links::crawler::CheckedUrl;
+use find_broken_links::sqlite::{SqliteWriter, SCHEMA_VERSION};
+use rusqlite::Connection;
+use std::time::{Duration, SystemTime};
+
+fn checked(url: &str, status: Option<u16>, elapsed_ms: u64) -> CheckedUrl {
+    CheckedUrl {
+        url: url.to_string(),
+        status,
+        elapsed: Duration::from_millis(elapsed_ms),
+        content_type: Some("text/html".to_string()),
+        referrer: Some("https://example.com/".to_string()),
+        checked_at: SystemTime::now(),
+    }
+}
+
+#[test]
+fn writes_checked_urls_sortable_by_timing() {
+    let path = std::env::temp_dir().join(format!("fbl-sqlite-{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let writer = SqliteWriter::create(&path).unwrap();
+    writer
+        .record(&checked("https://example.com/fast", Some(200), 5))
+        .unwrap();
+    writer
+        .record(&checked("https://example.com/slow", Some(200), 900))
+        .unwrap();
+    writer
+        .record(&checked("https://example.com/gone", Some(404), 40))
+        .unwrap();
+    writer
+        .record(&checked("https://down.example/", None, 3000))
+        .unwrap();
+    writer.finish().unwrap();
+
+    let connection = Connection::open(&path).unwrap();
+    let version: i64 = connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, SCHEMA_VERSION);
+
+    let mut statement = connection
+        .prepare("SELECT url, status FROM checked_urls ORDER BY elapsed_ms DESC")
+        .unwrap();
+    let rows: Vec<(String, Option<u16>)> = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        [
+            ("https://down.example/".to_string(), None),
+            ("https://example.com/slow".to_string(), Some(200)),
+            ("https://example.com/gone".to_string(), Some(404)),
+            ("https://example.com/fast".to_string(), Some(200)),
+        ]
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
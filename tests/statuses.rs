@@ -0,0 +1,119 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::debug_channel::DebugChannel;
+use std::time::Duration;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// `/` links to a page answering each of `statuses`, at `/<status>`.
+async fn server_with_statuses(statuses: &[u16]) -> MockServer {
+    let server = MockServer::start().await;
+    let links: String = statuses
+        .iter()
+        .map(|status| format!("<a href=\"/{}\">{}</a>", status, status))
+        .collect();
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(links, "text/html"))
+        .mount(&server)
+        .await;
+    for status in statuses {
+        Mock::given(path(format!("/{}", status)))
+            .respond_with(ResponseTemplate::new(*status))
+            .mount(&server)
+            .await;
+    }
+    server
+}
+
+fn no_retries() -> find_broken_links::retry::RetryPolicy {
+    "".parse().unwrap()
+}
+
+#[tokio::test]
+async fn reports_every_error_status() {
+    let server = server_with_statuses(&[403, 404, 410, 500, 502]).await;
+    let crawler = Crawler::builder(common::root_url(&server))
+        .retry_policy(no_retries())
+        .build();
+
+    let findings = common::crawl(crawler).await;
+    let mut statuses: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, status, .. } => Some((url.rsplit('/').next()?, *status)),
+            _ => None,
+        })
+        .collect();
+    statuses.sort();
+    assert_eq!(
+        statuses,
+        [
+            ("403", Some(403)),
+            ("404", Some(404)),
+            ("410", Some(410)),
+            ("500", Some(500)),
+            ("502", Some(502)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn only_404_reports_404s() {
+    let server = server_with_statuses(&[404]).await;
+    let crawler = Crawler::builder(common::root_url(&server))
+        .only_404(true)
+        .build();
+
+    let findings = common::crawl(crawler).await;
+    assert_eq!(common::broken_urls(&findings).len(), 1);
+}
+
+#[tokio::test]
+async fn only_404_fails_the_crawl_on_other_statuses() {
+    let server = server_with_statuses(&[404, 500]).await;
+    let crawler = Crawler::builder(common::root_url(&server))
+        .retry_policy(no_retries())
+        .only_404(true)
+        .build();
+
+    let channel = DebugChannel::new(5);
+    assert!(crawler.run(channel.sender()).await.is_err());
+}
+
+#[tokio::test]
+async fn timed_out_requests_are_broken_links() {
+    let server = server_with_statuses(&[404]).await;
+    let page = r#"<a href="/slow">slow</a> <a href="/404">gone</a>"#;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/slow"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .mount(&server)
+        .await;
+    let crawler = Crawler::builder(common::root_url(&server))
+        .request_timeout(Some(Duration::from_millis(200)))
+        .build();
+
+    let findings = common::crawl(crawler).await;
+    let mut broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound {
+                url, status, error, ..
+            } => Some((url.rsplit('/').next()?, *status, error.clone())),
+            _ => None,
+        })
+        .collect();
+    broken.sort();
+    assert_eq!(
+        broken,
+        [
+            ("404", Some(404), None),
+            ("slow", Some(0), Some("no response: timed out".to_string())),
+        ]
+    );
+}
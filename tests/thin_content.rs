@@ -0,0 +1,68 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::warnings::{LinkWarning, WarningCategory};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn thin_content_warnings(findings: &[Finding]) -> Vec<&LinkWarning> {
+    findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::Warning(warning) if warning.category == WarningCategory::ThinContent => {
+                Some(warning)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+async fn mount_pages(server: &MockServer) {
+    let full = format!(
+        "<a href=\"/empty\">empty</a> <a href=\"/style.css\">css</a> {}",
+        "<p>content</p>".repeat(20)
+    );
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(full, "text/html"))
+        .mount(server)
+        .await;
+    Mock::given(path("/empty"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("<html></html>", "text/html"))
+        .mount(server)
+        .await;
+    Mock::given(path("/style.css"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("a{}", "text/css"))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn warns_about_html_pages_below_the_minimum() {
+    let server = MockServer::start().await;
+    mount_pages(&server).await;
+    let root = common::root_url(&server);
+
+    let crawler = Crawler::builder(root.clone())
+        .min_content_length(Some(100))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    let warnings = thin_content_warnings(&findings);
+    assert_eq!(warnings.len(), 1, "only the HTML page is thin");
+    assert_eq!(warnings[0].url, root.join("/empty").unwrap().to_string());
+    assert!(
+        warnings[0].message.contains("13 bytes"),
+        "{}",
+        warnings[0].message
+    );
+}
+
+#[tokio::test]
+async fn no_minimum_by_default() {
+    let server = MockServer::start().await;
+    mount_pages(&server).await;
+
+    let findings = common::crawl(Crawler::builder(common::root_url(&server)).build()).await;
+
+    assert!(thin_content_warnings(&findings).is_empty());
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::links::{page_title, LinkKind};
+use find_broken_links::report::NotFoundError;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn reads_the_title_element() {
+    assert_eq!(
+        page_title("<html><head><title>\n  Page Not Found\n  – MySite </title></head></html>"),
+        Some("Page Not Found – MySite".to_string())
+    );
+    assert_eq!(page_title("<title> </title><p>Gone</p>"), None);
+    assert_eq!(page_title("<h1>Not Found</h1>"), None);
+}
+
+#[tokio::test]
+async fn records_the_title_of_404_pages() {
+    let server = MockServer::start().await;
+    let page = r#"<a href="/gone">a</a> <a href="/untitled">b</a> <a href="/plain">c</a>"#;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(page, "text/html"))
+        .mount(&server)
+        .await;
+    let not_found = "<html><head><title>Page Not Found – MySite</title></head></html>";
+    Mock::given(path("/gone"))
+        .respond_with(
+            ResponseTemplate::new(404).set_body_raw(not_found, "text/html; charset=utf-8"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/untitled"))
+        .respond_with(ResponseTemplate::new(404).set_body_raw("<h1>Not Found</h1>", "text/html"))
+        .mount(&server)
+        .await;
+    // Not HTML, so the body isn't parsed even though it looks like it
+    Mock::given(path("/plain"))
+        .respond_with(
+            ResponseTemplate::new(404).set_body_raw("<title>Not Found</title>", "text/plain"),
+        )
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+    let mut titles: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound { url, title, .. } => Some((url.rsplit('/').next()?, title.clone())),
+            _ => None,
+        })
+        .collect();
+    titles.sort();
+    assert_eq!(
+        titles,
+        [
+            ("gone", Some("Page Not Found – MySite".to_string())),
+            ("plain", None),
+            ("untitled", None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn keeps_the_status_when_the_error_page_cant_be_read() {
+    let server = common::site(vec![
+        ("/", common::page(r#"<a href="/removed">removed</a>"#)),
+        (
+            "/removed",
+            ResponseTemplate::new(410)
+                .insert_header("content-encoding", "gzip")
+                .set_body_raw(b"<title>not gzip</title>".to_vec(), "text/html"),
+        ),
+    ])
+    .await;
+    let root = common::root_url(&server);
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+    let broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound {
+                url,
+                status,
+                title,
+                error,
+                ..
+            } => Some((url.clone(), *status, title.clone(), error.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        broken,
+        [(
+            root.join("/removed").unwrap().to_string(),
+            Some(410),
+            None,
+            None
+        )]
+    );
+}
+
+#[test]
+fn writes_the_title_to_the_report() {
+    let error = NotFoundError {
+        url: "https://example.com/gone".to_string(),
+        title: Some("Page Not Found – MySite".to_string()),
+        source_url: None,
+        suggestion: None,
+        kind: LinkKind::Link,
+        error: None,
+        class: None,
+        status: Some(404),
+        redirected_to: None,
+        also_linked_from: Vec::new(),
+        soft_404: false,
+    };
+    let json = serde_json::to_value(&error).unwrap();
+    assert_eq!(json["title"], "Page Not Found – MySite");
+}
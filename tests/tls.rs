@@ -0,0 +1,107 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_native_tls::native_tls::{Identity, TlsAcceptor};
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// An HTTPS server with a self-signed certificate for `localhost`, serving a page at `/page`
+/// that links to a missing `/gone`. wiremock only speaks plain HTTP.
+async fn self_signed_server() -> Url {
+    let identity = Identity::from_pkcs8(
+        include_bytes!("fixtures/self-signed.crt"),
+        include_bytes!("fixtures/self-signed.key"),
+    )
+    .unwrap();
+    let acceptor = tokio_native_tls::TlsAcceptor::from(TlsAcceptor::new(identity).unwrap());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                // A client that doesn't trust the certificate hangs up here
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let (status, body) = match request.split(' ').nth(1) {
+                    Some("/page") => ("200 OK", r#"<a href="/gone">x</a>"#),
+                    _ => ("404 Not Found", ""),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+    Url::parse(&format!("https://localhost:{}/", port)).unwrap()
+}
+
+/// A plain-HTTP root page linking to `/page` on the HTTPS server.
+async fn site_linking_to(https: &Url) -> (MockServer, Url) {
+    let server = MockServer::start().await;
+    let body = format!(r#"<a href="{}">x</a>"#, https.join("/page").unwrap());
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/html"))
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server);
+    (server, root)
+}
+
+#[tokio::test]
+async fn reports_an_untrusted_certificate_as_a_tls_error() {
+    let https = self_signed_server().await;
+    let (_server, root) = site_linking_to(&https).await;
+
+    let findings = common::crawl(Crawler::builder(root).build()).await;
+
+    let broken: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| match finding {
+            Finding::NotFound {
+                url, status, error, ..
+            } => Some((url.clone(), *status, error.clone().unwrap_or_default())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(broken.len(), 1, "{:?}", broken);
+    let (url, status, error) = &broken[0];
+    assert_eq!(url, https.join("/page").unwrap().as_str());
+    assert_eq!(*status, Some(0));
+    assert!(error.starts_with("tls error: "), "{}", error);
+}
+
+#[tokio::test]
+async fn accepts_invalid_certificates_when_asked() {
+    let https = self_signed_server().await;
+    let (_server, root) = site_linking_to(&https).await;
+
+    let crawler = Crawler::builder(root).accept_invalid_certs(true).build();
+    let findings = common::crawl(crawler).await;
+
+    // The page is fetched, so its own broken link is found
+    assert_eq!(
+        common::broken_urls(&findings),
+        [https.join("/gone").unwrap().to_string()]
+    );
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use wiremock::matchers::{header, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A firewall that only lets through requests carrying browser navigation headers.
+async fn firewalled_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .and(header("sec-fetch-mode", "navigate"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw("<a href=\"/missing\">x</a>", "text/html"),
+        )
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(403))
+        .with_priority(2)
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn retries_403_with_browser_headers() {
+    let server = firewalled_server().await;
+    let root = common::root_url(&server);
+
+    let findings = common::crawl(Crawler::builder(root.clone()).build()).await;
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/missing").unwrap().to_string()]
+    );
+}
+
+#[tokio::test]
+async fn fallback_can_be_turned_off() {
+    let server = firewalled_server().await;
+    let root = common::root_url(&server);
+    let crawler = Crawler::builder(root.clone()).ua_fallback(false).build();
+
+    // The firewall's 403 is all there is to report
+    let findings = common::crawl(crawler).await;
+    assert_eq!(common::broken_urls(&findings), [root.to_string()]);
+}
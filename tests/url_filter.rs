@@ -0,0 +1,96 @@
+mod common;
+
+use find_broken_links::crawler::{Crawler, Finding};
+use find_broken_links::skipped::SkipReason;
+use find_broken_links::url_filter::UrlPatternFilter;
+use regex::Regex;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer};
+
+fn patterns(patterns: &[&str]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).unwrap())
+        .collect()
+}
+
+#[test]
+fn exclude_wins_over_include() {
+    let filter = UrlPatternFilter::new(patterns(&["/docs/"]), patterns(&["/docs/old/", r"\?q="]));
+
+    assert!(filter.allows("https://example.com/docs/intro"));
+    assert!(!filter.allows("https://example.com/docs/old/intro"));
+    assert!(!filter.allows("https://example.com/docs/search?q=x"));
+    assert!(!filter.allows("https://example.com/blog/"));
+    assert!(UrlPatternFilter::default().allows("https://example.com/blog/"));
+}
+
+#[tokio::test]
+async fn never_fetches_excluded_urls() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(common::page(
+            r#"<a href="/search?q=a">search</a> <a href="/calendar/2026/10">calendar</a>
+            <a href="/about">about</a>"#,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/about"))
+        .respond_with(common::page(r#"<a href="/search?q=b">search</a>"#))
+        .mount(&server)
+        .await;
+
+    let crawler = Crawler::builder(common::root_url(&server))
+        .url_filter(UrlPatternFilter::new(
+            Vec::new(),
+            patterns(&[r"/search\?", "/calendar/"]),
+        ))
+        .report_skipped(true)
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::requested_paths(&server).await,
+        ["GET /", "GET /about"]
+    );
+    let excluded = findings
+        .iter()
+        .filter(|finding| {
+            matches!(
+                finding,
+                Finding::Skipped(link) if link.reason == SkipReason::Excluded
+            )
+        })
+        .count();
+    assert_eq!(excluded, 3);
+}
+
+#[tokio::test]
+async fn skips_same_domain_urls_not_matching_an_include_pattern() {
+    let server = MockServer::start().await;
+    Mock::given(path("/docs/"))
+        .respond_with(common::page(
+            r#"<a href="/docs/intro">intro</a> <a href="/blog/">blog</a>"#,
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/docs/intro"))
+        .respond_with(common::page(r#"<a href="/docs/gone">gone</a>"#))
+        .mount(&server)
+        .await;
+    let root = common::root_url(&server).join("/docs/").unwrap();
+
+    let crawler = Crawler::builder(root.clone())
+        .url_filter(UrlPatternFilter::new(patterns(&["/docs/"]), Vec::new()))
+        .build();
+    let findings = common::crawl(crawler).await;
+
+    assert_eq!(
+        common::requested_paths(&server).await,
+        ["GET /docs/", "GET /docs/gone", "GET /docs/intro"]
+    );
+    assert_eq!(
+        common::broken_urls(&findings),
+        [root.join("/docs/gone").unwrap().to_string()]
+    );
+}
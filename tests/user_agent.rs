@@ -0,0 +1,59 @@
+mod common;
+
+use find_broken_links::crawler::Crawler;
+use find_broken_links::user_agent::{UserAgentPicker, DEFAULT_USER_AGENT};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn site() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<a href=\"/a\">a</a><a href=\"/b\">b</a>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn user_agents(server: &MockServer) -> Vec<String> {
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    requests
+        .iter()
+        .map(|request| request.headers["user-agent"].to_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn every_request_carries_the_picked_user_agent() {
+    let server = site().await;
+    common::crawl(Crawler::builder(common::root_url(&server)).build()).await;
+    assert_eq!(user_agents(&server).await, [DEFAULT_USER_AGENT; 3]);
+
+    let server = site().await;
+    let crawler = Crawler::builder(common::root_url(&server))
+        .user_agents(UserAgentPicker::new(
+            vec!["link-checker/1".to_string()],
+            false,
+        ))
+        .build();
+    common::crawl(crawler).await;
+    assert_eq!(user_agents(&server).await, ["link-checker/1"; 3]);
+}
+
+#[tokio::test]
+async fn a_custom_client_sends_its_own_user_agent() {
+    let server = site().await;
+    let client = reqwest::Client::builder()
+        .user_agent("custom/1")
+        .build()
+        .unwrap();
+    let crawler = Crawler::builder(common::root_url(&server))
+        .user_agents(UserAgentPicker::new(vec!["ignored/1".to_string()], false))
+        .client(client)
+        .build();
+    common::crawl(crawler).await;
+    assert_eq!(user_agents(&server).await, ["custom/1"; 3]);
+}